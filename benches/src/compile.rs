@@ -0,0 +1,64 @@
+//! Front end of `#[derive(Template)]` in a crate with many templates sharing a layout
+//!
+//! Each iteration is one compilation: read `yarte.toml`, read and parse the layout
+//! and every page. `uncached` is the work done by each derive without the cache
+use std::{fs, path::Path};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use yarte_helpers::config::{get_config, get_source, read_config_file, Config};
+use yarte_parser::{parse, parse_cached, source_map};
+
+const TEMPLATES: usize = 300;
+
+criterion_group!(benches, front_end);
+criterion_main!(benches);
+
+fn front_end(c: &mut Criterion) {
+    let pages: Vec<_> = (0..TEMPLATES)
+        .map(|i| {
+            (
+                Config::new(&read_config_file())
+                    .get_dir()
+                    .join(format!("Page{}.hbs", i)),
+                format!(
+                    "{{{{#> compile/layout title = \"Page {}\" }}}}<p>{{{{ body{} }}}}</p>{{{{/compile/layout }}}}",
+                    i, i
+                ),
+            )
+        })
+        .collect();
+
+    let mut group = c.benchmark_group(format!("{} templates with layout", TEMPLATES));
+    group.bench_function("uncached", |b| {
+        b.iter(|| {
+            for (path, src) in &pages {
+                let toml = read_config_file();
+                let config = Config::new(&toml);
+                let (layout, _) = config
+                    .get_template(Path::new("compile/layout.hbs"))
+                    .unwrap();
+                let layout_src = fs::read_to_string(&layout).unwrap();
+                black_box(parse(source_map::get_cursor(path, src)).unwrap());
+                black_box(parse(source_map::get_cursor(&layout, &layout_src)).unwrap());
+                source_map::clean();
+            }
+        })
+    });
+    group.bench_function("cached", |b| {
+        b.iter(|| {
+            for (path, src) in &pages {
+                let config = get_config();
+                let (layout, _) = config
+                    .get()
+                    .get_template(Path::new("compile/layout.hbs"))
+                    .unwrap();
+                let layout_src = get_source(&layout);
+                black_box(parse_cached(path, src).unwrap());
+                black_box(parse_cached(&layout, &layout_src).unwrap());
+            }
+            source_map::clean();
+        })
+    });
+    group.finish();
+}
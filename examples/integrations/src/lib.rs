@@ -0,0 +1,20 @@
+use yarte::{TemplateBytes, TemplateBytesText};
+
+#[derive(TemplateBytes)]
+#[template(path = "hello")]
+pub struct Hello<'a> {
+    pub name: &'a str,
+}
+
+#[derive(TemplateBytesText)]
+#[template(src = "{{ count }} items")]
+pub struct Items {
+    pub count: usize,
+}
+
+#[derive(TemplateBytesText)]
+#[template(path = "status.json")]
+pub struct Status {
+    pub status: &'static str,
+    pub uptime: u64,
+}
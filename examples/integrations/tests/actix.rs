@@ -0,0 +1,36 @@
+use actix_web::{test, web, App, Responder};
+
+use integrations::{Hello, Items};
+
+async fn hello() -> impl Responder {
+    Hello { name: "<world>" }
+}
+
+async fn items() -> impl Responder {
+    Items { count: 3 }
+}
+
+#[actix_web::test]
+async fn test_actix() {
+    let app = test::init_service(
+        App::new()
+            .route("/", web::get().to(hello))
+            .route("/items", web::get().to(items)),
+    )
+    .await;
+
+    let res = test::call_service(&app, test::TestRequest::get().uri("/").to_request()).await;
+    assert!(res.status().is_success());
+    assert_eq!(
+        res.headers().get("content-type").unwrap(),
+        "text/html; charset=utf-8"
+    );
+    assert_eq!(test::read_body(res).await, "<h1>Hello, &lt;world&gt;!</h1>");
+
+    let res = test::call_service(&app, test::TestRequest::get().uri("/items").to_request()).await;
+    assert_eq!(
+        res.headers().get("content-type").unwrap(),
+        "text/plain; charset=utf-8"
+    );
+    assert_eq!(test::read_body(res).await, "3 items");
+}
@@ -0,0 +1,51 @@
+use axum::{body::Body, http::Request, routing::get, Router};
+use http_body_util::BodyExt;
+use tower::ServiceExt;
+
+use integrations::{Hello, Items, Status};
+
+async fn get_ok(app: Router, uri: &str) -> (String, String) {
+    let res = app
+        .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let content_type = res.headers()["content-type"].to_str().unwrap().to_owned();
+    let body = res.into_body().collect().await.unwrap().to_bytes();
+    (content_type, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn test_axum() {
+    let app = Router::new()
+        .route("/", get(|| async { Hello { name: "<world>" } }))
+        .route("/items", get(|| async { Items { count: 3 } }))
+        .route(
+            "/status",
+            get(|| async {
+                Status {
+                    status: "ok",
+                    uptime: 42,
+                }
+            }),
+        );
+
+    assert_eq!(
+        get_ok(app.clone(), "/").await,
+        (
+            "text/html; charset=utf-8".into(),
+            "<h1>Hello, &lt;world&gt;!</h1>".into()
+        )
+    );
+    assert_eq!(
+        get_ok(app.clone(), "/items").await,
+        ("text/plain; charset=utf-8".into(), "3 items".into())
+    );
+    assert_eq!(
+        get_ok(app, "/status").await,
+        (
+            "application/json".into(),
+            r#"{"status":"ok","uptime":42}"#.into()
+        )
+    );
+}
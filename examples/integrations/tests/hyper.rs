@@ -0,0 +1,22 @@
+use std::convert::Infallible;
+
+use http_body_util::{BodyExt, Full};
+use hyper::{service::service_fn, service::Service, Request, Response};
+use yarte::Bytes;
+
+use integrations::Hello;
+
+#[tokio::test]
+async fn test_hyper() {
+    let service = service_fn(|_req: Request<Full<Bytes>>| async {
+        Ok::<Response<Full<Bytes>>, Infallible>(Hello { name: "<world>" }.into())
+    });
+
+    let res = service.call(Request::new(Full::default())).await.unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["content-type"], "text/html; charset=utf-8");
+    assert_eq!(
+        res.into_body().collect().await.unwrap().to_bytes(),
+        "<h1>Hello, &lt;world&gt;!</h1>"
+    );
+}
@@ -0,0 +1,63 @@
+#![no_std]
+
+use yarte::{TemplateFixed, TemplateFixedText};
+
+#[derive(TemplateFixed)]
+#[template(src = "<h1>{{ name }}</h1>\
+{{#each sensors}}<p>{{ this.0 }}: {{ this.1 }}</p>{{/each}}\
+{{#if ok }}<b>ok</b>{{else}}<b>{{ code }}</b>{{/if}}")]
+pub struct Status<'a> {
+    pub name: &'a str,
+    pub sensors: &'a [(&'a str, f32)],
+    pub ok: bool,
+    pub code: char,
+}
+
+#[derive(TemplateFixedText)]
+#[template(src = "{{ id }};{{ uptime }};{{ temp }};{{ rssi }};{{ ok }}")]
+pub struct Telemetry {
+    pub id: u8,
+    pub uptime: u64,
+    pub temp: i16,
+    pub rssi: i32,
+    pub ok: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::MaybeUninit;
+
+    use super::*;
+    use yarte::TemplateFixedTrait;
+
+    #[test]
+    fn status() {
+        let status = Status {
+            name: "<node/1>",
+            sensors: &[("temp", 21.5), ("hum", 40.0)],
+            ok: false,
+            code: '&',
+        };
+        let buf = &mut [MaybeUninit::uninit(); 256];
+        assert_eq!(
+            status.render_into(buf).unwrap(),
+            &b"<h1>&lt;node&#x2f;1&gt;</h1><p>temp: 21.5</p><p>hum: 40.0</p><b>&amp;</b>"[..]
+        );
+
+        let buf = &mut [MaybeUninit::uninit(); 16];
+        assert!(status.render_into(buf).is_err());
+    }
+
+    #[test]
+    fn telemetry() {
+        let frame = Telemetry {
+            id: 7,
+            uptime: 86_400,
+            temp: -12,
+            rssi: -67,
+            ok: true,
+        };
+        assert_eq!(Telemetry::MAX_LEN, Some(49));
+        assert_eq!(&*frame.render_array::<49>(), b"7;86400;-12;-67;true");
+    }
+}
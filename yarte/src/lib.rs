@@ -6,7 +6,16 @@
 //!
 //! [Yarte book](https://yarte.netlify.com)
 //!
-use std::fmt::{self, Write};
+//! Without the default `std` feature only the `fixed` backend is available,
+//! `alloc` adds the `String` renders
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::fmt;
+#[cfg(feature = "std")]
+use core::fmt::Write;
+
+#[cfg(feature = "sanitize")]
+mod sanitize;
 
 /// Add auto sized buffer functionality wrapping ywrites macros
 ///
@@ -22,6 +31,8 @@ use std::fmt::{self, Write};
 /// ```
 pub use yarte_derive::auto;
 
+#[cfg(feature = "sanitize")]
+pub use self::sanitize::{Allowlist, AsSanitize, Sanitize};
 #[cfg(all(
     any(feature = "bytes-buf", feature = "bytes-buf-tokio2"),
     feature = "html-min"
@@ -29,35 +40,51 @@ pub use yarte_derive::auto;
 pub use yarte_derive::ywrite_min;
 #[cfg(any(feature = "bytes-buf", feature = "bytes-buf-tokio2"))]
 pub use yarte_derive::{html, ywrite, ywrite_html};
+#[cfg(feature = "std")]
 pub use yarte_derive::{yformat, yformat_html};
+#[cfg(feature = "json")]
 pub use yarte_helpers::at_helpers::*;
 pub use yarte_helpers::{
-    helpers::{
-        display_fn::DisplayFn, io_fmt::IoFmt, Aligned256, IntoCopyIterator, Render, RenderA,
-    },
-    recompile, Error, Result,
+    helpers::{adapt_size, Aligned256, IntoCopyIterator},
+    Error, Result,
+};
+#[cfg(feature = "std")]
+pub use yarte_helpers::{
+    helpers::{display_fn::DisplayFn, io_fmt::IoFmt, Render, RenderA},
+    recompile,
 };
 
+#[cfg(feature = "std")]
 /// Template trait, will implement by derive `fmt::Display`
 pub trait TemplateTrait: fmt::Display {
     /// which will write this template
     fn call(&self) -> Result<String> {
         let mut buf = String::with_capacity(Self::size_hint());
-        write!(buf, "{}", self).map(|_| buf)
+        write!(buf, "{}", self)?;
+        Self::record_size(buf.len());
+        Ok(buf)
     }
 
     /// Approximation of output size used in method `call`.
-    /// Yarte implements an heuristic algorithm of allocation.
+    /// Estimated from the template at compile time, `adaptive` templates
+    /// learn it from previous renders with [`adapt_size`].
     fn size_hint() -> usize;
+
+    /// Rendered length of `call`, used by `adaptive` templates
+    #[inline]
+    fn record_size(_len: usize) {}
 }
 
+#[cfg(feature = "std")]
 pub use yarte_derive::{Template, TemplateText};
+#[cfg(feature = "std")]
 pub use TemplateTrait as Template;
+#[cfg(feature = "std")]
 pub use TemplateTrait as TemplateText;
 
-#[cfg(feature = "html-min")]
+#[cfg(all(feature = "std", feature = "html-min"))]
 pub use yarte_derive::TemplateMin;
-#[cfg(feature = "html-min")]
+#[cfg(all(feature = "std", feature = "html-min"))]
 pub use TemplateTrait as TemplateMin;
 
 #[cfg(feature = "wasm")]
@@ -68,28 +95,124 @@ pub use TemplateBytesTrait as TemplateWasmServer;
 #[cfg(feature = "fixed")]
 /// Template trait
 pub trait TemplateFixedTrait {
+    /// Upper bound of the rendered length
+    ///
+    /// Computed by derive when every expression has a bounded size,
+    /// integer, `bool` or `char` fields
+    const MAX_LEN: Option<usize> = None;
+
+    /// Writes to buffer
+    ///
+    /// ```rust,ignore
+    /// # const N: usize = 1;
+    /// let buf = TemplateFixedTrait::render_into(&t, &mut [MaybeUninit::uninit(); N])?;
+    /// ```
+    fn render_into<'call>(
+        &self,
+        buf: &'call mut [core::mem::MaybeUninit<u8>],
+    ) -> core::result::Result<&'call [u8], Overflow>;
+
+    /// Writes to an array of `N` bytes
+    ///
+    /// Fails to compile when `MAX_LEN` is unknown or greater than `N`
+    fn render_array<const N: usize>(&self) -> FixedBuf<N>
+    where
+        Self: Sized,
+    {
+        #[allow(clippy::let_unit_value)]
+        let () = MaxLenCheck::<Self, N>::FITS;
+        let mut buf = FixedBuf {
+            buf: [core::mem::MaybeUninit::uninit(); N],
+            len: 0,
+        };
+        buf.len = self
+            .render_into(&mut buf.buf)
+            .expect("bounded by MAX_LEN")
+            .len();
+        buf
+    }
+
     /// Writes to buffer
     ///
     /// # Safety
-    /// Not respect the lifetime bounds it's possible borrow mut when it's borrow
+    /// Not respect the lifetime bounds it's possible borrow mut when it's borrow,
+    /// use `render_into`
     /// ```rust,ignore
     /// # const N: usize = 1;
     /// let buf = TemplateFixedTrait::call(&mut [MaybeUninit::uninit(); N]).expect("buffer overflow");
     /// ```
     unsafe fn call<'call>(
         &self,
-        buf: &'call mut [std::mem::MaybeUninit<u8>],
+        buf: &'call mut [core::mem::MaybeUninit<u8>],
     ) -> Option<&'call [u8]>;
 
     /// Writes to buffer and drop
     ///
     /// # Safety
-    /// Not respect the lifetime bounds it's possible borrow mut when it's borrow
+    /// Not respect the lifetime bounds it's possible borrow mut when it's borrow,
+    /// use `render_into`
     /// ```rust,ignore
     /// # const N: usize = 1;
     /// let buf = TemplateFixedTrait::ccall(&mut [MaybeUninit::uninit(); N]).expect("buffer overflow");
     /// ```
-    unsafe fn ccall(self, buf: &mut [std::mem::MaybeUninit<u8>]) -> Option<&[u8]>;
+    unsafe fn ccall(self, buf: &mut [core::mem::MaybeUninit<u8>]) -> Option<&[u8]>;
+}
+
+#[cfg(feature = "fixed")]
+/// Buffer of `render_into` is too small
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overflow {
+    /// Lower bound of the needed buffer length
+    pub needed_at_least: usize,
+}
+
+#[cfg(feature = "fixed")]
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "buffer overflow, needed at least {} bytes",
+            self.needed_at_least
+        )
+    }
+}
+
+#[cfg(all(feature = "fixed", feature = "std"))]
+impl std::error::Error for Overflow {}
+
+#[cfg(feature = "fixed")]
+/// Output of `render_array`
+pub struct FixedBuf<const N: usize> {
+    buf: [core::mem::MaybeUninit<u8>; N],
+    len: usize,
+}
+
+#[cfg(feature = "fixed")]
+impl<const N: usize> core::ops::Deref for FixedBuf<N> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        // Written bytes are initialized
+        unsafe { core::slice::from_raw_parts(self.buf.as_ptr() as *const u8, self.len) }
+    }
+}
+
+#[cfg(feature = "fixed")]
+impl<const N: usize> fmt::Debug for FixedBuf<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(feature = "fixed")]
+struct MaxLenCheck<T, const N: usize>(core::marker::PhantomData<T>);
+
+#[cfg(feature = "fixed")]
+impl<T: TemplateFixedTrait, const N: usize> MaxLenCheck<T, N> {
+    const FITS: () = assert!(
+        matches!(T::MAX_LEN, Some(len) if len <= N),
+        "`MAX_LEN` of template is unknown or greater than array length"
+    );
 }
 
 #[cfg(feature = "fixed")]
@@ -129,6 +252,10 @@ pub trait TemplateBytesTrait {
     /// # Panics
     /// Render length overflows usize
     fn write_ccall<B: Buffer>(self, buf: &mut B);
+    /// Approximation of output size, the minimum capacity of `call` and `ccall`.
+    /// Estimated from the template at compile time, `adaptive` templates
+    /// learn it from previous renders with [`adapt_size`].
+    fn size_hint() -> usize;
 }
 
 #[cfg(all(
@@ -148,6 +275,10 @@ pub use TemplateBytesTrait as TemplateBytesText;
 ))]
 pub use TemplateBytesTrait as TemplateBytesMin;
 
+#[cfg(any(feature = "bytes-buf", feature = "bytes-buf-tokio2"))]
+pub use yarte_helpers::helpers::fragment::{
+    fragment_cache, set_fragment_cache, FragmentCache, FragmentKey, LruCache,
+};
 #[cfg(any(feature = "bytes-buf", feature = "bytes-buf-tokio2"))]
 pub use yarte_helpers::helpers::{RenderBytes, RenderBytesA, RenderBytesSafe, RenderBytesSafeA};
 
@@ -159,6 +290,20 @@ pub use buf_min::Buffer;
 #[cfg(all(feature = "bytes-buf-tokio2", not(feature = "bytes-buf")))]
 pub use buf_min::t2::{Bytes, BytesMut};
 
+/// Web framework crates used by the derived responses
+#[doc(hidden)]
+#[cfg(any(feature = "axum", feature = "actix-web", feature = "hyper"))]
+pub mod integrations {
+    #[cfg(feature = "actix-web")]
+    pub use actix_web;
+    #[cfg(feature = "axum")]
+    pub use axum_core as axum;
+    #[cfg(any(feature = "axum", feature = "hyper"))]
+    pub use http;
+    #[cfg(feature = "hyper")]
+    pub use http_body_util;
+}
+
 #[cfg(feature = "json")]
 pub use yarte_derive::Serialize;
 #[cfg(feature = "json")]
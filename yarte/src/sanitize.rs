@@ -0,0 +1,92 @@
+use std::fmt::{self, Display};
+
+use yarte_html::sanitize::sanitize;
+pub use yarte_html::sanitize::Allowlist;
+
+use yarte_helpers::helpers::{io_fmt::IoFmt, Render};
+
+/// Sanitized markup, only elements and attributes in the allowlist are rendered
+///
+/// ```
+/// # use yarte::Sanitize;
+/// let comment = "<p onclick=\"alert(1)\">Hi <script>alert(2)</script>all</p>";
+/// assert_eq!(Sanitize::new(comment).to_string(), "<p>Hi all</p>");
+/// ```
+#[derive(Clone, Copy)]
+pub struct Sanitize<'a>(pub &'a str, pub Allowlist<'a>);
+
+impl<'a> Sanitize<'a> {
+    /// With default allowlist
+    pub fn new(src: &'a str) -> Self {
+        Sanitize(src, Allowlist::DEFAULT)
+    }
+}
+
+pub trait AsSanitize {
+    fn __as_sanitize<'a>(&'a self, allow: Allowlist<'a>) -> Sanitize<'a>;
+}
+
+impl<S: AsRef<str> + ?Sized> AsSanitize for S {
+    fn __as_sanitize<'a>(&'a self, allow: Allowlist<'a>) -> Sanitize<'a> {
+        Sanitize(self.as_ref(), allow)
+    }
+}
+
+impl<'a> Display for Sanitize<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Render::render(self, f)
+    }
+}
+
+impl<'a> Render for Sanitize<'a> {
+    #[inline]
+    fn render(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        sanitize(IoFmt::new(f), self.0, &self.1).map_err(|_| fmt::Error)
+    }
+}
+
+#[cfg(any(feature = "bytes-buf", feature = "bytes-buf-tokio2"))]
+mod bytes {
+    use std::io;
+
+    use buf_min::Buffer;
+
+    use yarte_helpers::helpers::{RenderBytes, RenderBytesSafe};
+
+    use super::*;
+
+    struct BufWriter<'a, B>(&'a mut B);
+
+    impl<'a, B: Buffer> io::Write for BufWriter<'a, B> {
+        #[inline]
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            // Safety: serializer only write whole utf-8 strings
+            unsafe { self.0.extend_from_slice(buf) };
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[inline]
+    fn render<B: Buffer>(s: Sanitize, buf: &mut B) {
+        // Writer never fails
+        let _ = sanitize(BufWriter(buf), s.0, &s.1);
+    }
+
+    impl<'a> RenderBytes for Sanitize<'a> {
+        #[inline]
+        fn render<B: Buffer>(self, buf: &mut B) {
+            render(self, buf)
+        }
+    }
+
+    impl<'a> RenderBytesSafe for Sanitize<'a> {
+        #[inline]
+        fn render<B: Buffer>(self, buf: &mut B) {
+            render(self, buf)
+        }
+    }
+}
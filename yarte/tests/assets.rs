@@ -0,0 +1,47 @@
+use yarte::{Template, TemplateText};
+
+#[derive(Template)]
+#[template(src = "<style>{{ @include_str \"assets/critical.css\" }}</style>\
+<p>{{ @include_str \"assets/critical.css\" }}</p>{{ @include_raw \"assets/x.svg\" }}")]
+struct IncludeTemplate;
+
+#[test]
+fn include() {
+    assert_eq!(
+        IncludeTemplate.call().unwrap(),
+        "<style>a > b { content: \"&\"; }\n</style>\
+         <p>a &gt; b { content: &quot;&amp;&quot;; }\n</p>\
+         <svg viewBox=\"0 0 8 8\"><path d=\"M0 0L8 8\"/></svg>\n"
+    );
+}
+
+#[derive(TemplateText)]
+#[template(src = "{{ @include_str \"assets/critical.css\" }}")]
+struct IncludeTextTemplate;
+
+#[test]
+fn include_text() {
+    assert_eq!(
+        IncludeTextTemplate.call().unwrap(),
+        "a > b { content: \"&\"; }\n"
+    );
+}
+
+#[derive(Template)]
+#[template(src = "<link href=\"{{ @asset \"assets/app.css\" }}\">\
+<script src=\"{{ @asset \"assets/app.js\" }}\"></script>")]
+struct AssetTemplate;
+
+#[test]
+fn asset() {
+    let html = AssetTemplate.call().unwrap();
+    let (link, script) = html.split_at(html.find("<script").unwrap());
+    assert_eq!(link, "<link href=\"/static/assets/app.3f2a1b.css\">");
+
+    let hash = script
+        .strip_prefix("<script src=\"/static/assets/app.js?v=")
+        .and_then(|x| x.strip_suffix("\"></script>"))
+        .unwrap();
+    assert_eq!(hash.len(), 16);
+    assert!(hash.chars().all(|x| x.is_ascii_hexdigit()));
+}
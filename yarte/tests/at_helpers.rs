@@ -12,6 +12,13 @@ mod json {
         f: usize,
     }
 
+    #[derive(Serialize, YSerialize, Clone, Copy)]
+    struct JsonS {
+        s: &'static str,
+    }
+
+    const SCRIPT: &str = "{\"s\":\"\\u003c/script\\u003e\\u003c!--\\u0026\\u2028\"}";
+
     #[derive(Template)]
     #[template(src = "{{ @json f }}")]
     struct JsonTemplate {
@@ -52,6 +59,49 @@ mod json {
         assert_eq!(serde_json::to_string_pretty(&f).unwrap(), t.call().unwrap());
     }
 
+    #[derive(Template)]
+    #[template(src = "{{ @json_script f }}")]
+    struct JsonScriptTemplate {
+        f: JsonS,
+    }
+
+    #[derive(TemplateText)]
+    #[template(src = "{{ @json_script f }}")]
+    struct JsonScriptTemplateT {
+        f: JsonS,
+    }
+
+    #[derive(Template)]
+    #[template(
+        src = "<script>var a = {{ @json f }};</script>{{ @json f }}<SCRIPT>{{ @json_pretty f }}</SCRIPT>"
+    )]
+    struct JsonInScriptTemplate {
+        f: JsonS,
+    }
+
+    #[test]
+    fn json_script() {
+        let f = JsonS {
+            s: "</script><!--&\u{2028}",
+        };
+        let t = JsonScriptTemplate { f };
+        assert_eq!(SCRIPT, t.call().unwrap());
+
+        let t = JsonScriptTemplateT { f };
+        assert_eq!(SCRIPT, t.call().unwrap());
+
+        let t = JsonInScriptTemplate { f };
+        assert_eq!(
+            format!(
+                "<script>var a = {};</script>{}<SCRIPT>{}</SCRIPT>",
+                SCRIPT,
+                serde_json::to_string(&f).unwrap(),
+                "{\n  \"s\": \"\\u003c/script\\u003e\\u003c!--\\u0026\\u2028\"\n}"
+            ),
+            t.call().unwrap()
+        );
+    }
+
     #[cfg(feature = "fixed")]
     mod fixed {
         use super::*;
@@ -110,6 +160,36 @@ mod json {
                 unsafe { t.call(&mut [MaybeUninit::uninit(); 1024]) }.unwrap()
             );
         }
+
+        #[derive(TemplateFixed)]
+        #[template(src = "<script>{{ @json f }}</script>")]
+        struct JsonScriptTemplateF {
+            f: JsonS,
+        }
+
+        #[derive(TemplateFixedText)]
+        #[template(src = "{{ @json_script f }}")]
+        struct JsonScriptTemplateFT {
+            f: JsonS,
+        }
+
+        #[test]
+        fn json_script() {
+            let f = JsonS {
+                s: "</script><!--&\u{2028}",
+            };
+            let t = JsonScriptTemplateF { f };
+            assert_eq!(
+                format!("<script>{}</script>", SCRIPT).as_bytes(),
+                unsafe { t.call(&mut [MaybeUninit::uninit(); 1024]) }.unwrap()
+            );
+
+            let t = JsonScriptTemplateFT { f };
+            assert_eq!(
+                SCRIPT.as_bytes(),
+                unsafe { t.call(&mut [MaybeUninit::uninit(); 1024]) }.unwrap()
+            );
+        }
     }
 
     #[cfg(feature = "bytes-buf")]
@@ -157,5 +237,32 @@ mod json {
                 t.ccall::<BytesMut>(0)
             );
         }
+
+        #[derive(TemplateBytes)]
+        #[template(src = "<script>{{ @json f }}</script>")]
+        struct JsonScriptTemplateF {
+            f: JsonS,
+        }
+
+        #[derive(TemplateBytesText)]
+        #[template(src = "{{ @json_script f }}")]
+        struct JsonScriptTemplateFT {
+            f: JsonS,
+        }
+
+        #[test]
+        fn json_script() {
+            let f = JsonS {
+                s: "</script><!--&\u{2028}",
+            };
+            let t = JsonScriptTemplateF { f };
+            assert_eq!(
+                Bytes::from(format!("<script>{}</script>", SCRIPT)),
+                t.ccall::<BytesMut>(0)
+            );
+
+            let t = JsonScriptTemplateFT { f };
+            assert_eq!(Bytes::from(SCRIPT), t.ccall::<BytesMut>(0));
+        }
     }
 }
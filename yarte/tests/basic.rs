@@ -292,3 +292,84 @@ struct CompileError;
 fn test_compile_error() {
     assert_eq!("", CompileError.call().unwrap());
 }
+
+#[derive(Template)]
+#[template(
+    src = "<ul>{{#each items}}<li>{{ this }}{{/each}}</ul><p class=\"{{ class }}\">{{> hello name = class }}",
+    strict = true
+)]
+struct Strict<'a> {
+    items: Vec<usize>,
+    class: &'a str,
+}
+
+#[test]
+fn test_strict() {
+    let t = Strict {
+        items: vec![1, 2],
+        class: "a",
+    };
+    assert_eq!(
+        "<ul><li>1<li>2</ul><p class=\"a\">Hello, a!",
+        t.call().unwrap()
+    );
+}
+
+#[derive(TemplateText)]
+#[template(src = "{{#each 0..4}}-{{ super::n }}{{/each}}")]
+struct SizeHintTemplate {
+    n: u8,
+}
+
+#[test]
+fn test_size_hint() {
+    assert_eq!(HelloTemplate::size_hint(), 24);
+    assert_eq!(SizeHintTemplate::size_hint(), 16);
+    assert_eq!(SizeHintTemplate { n: 1 }.call().unwrap(), "-1-1-1-1");
+}
+
+#[derive(TemplateText)]
+#[template(src = "{{ name }}", adaptive = true)]
+struct AdaptiveTemplate {
+    name: String,
+}
+
+#[test]
+fn test_adaptive() {
+    assert_eq!(AdaptiveTemplate::size_hint(), 16);
+    let name = "a".repeat(100);
+    assert_eq!(
+        AdaptiveTemplate { name: name.clone() }.call().unwrap(),
+        name
+    );
+    assert_eq!(AdaptiveTemplate::size_hint(), 100);
+    let name = "b".to_string();
+    assert_eq!(
+        AdaptiveTemplate { name: name.clone() }.call().unwrap(),
+        name
+    );
+    // Decays to smaller renders
+    assert_eq!(AdaptiveTemplate::size_hint(), 100 - 99 / 8);
+    for _ in 0..32 {
+        AdaptiveTemplate { name: name.clone() }.call().unwrap();
+    }
+    assert!(AdaptiveTemplate::size_hint() <= 8);
+}
+
+#[derive(Template)]
+#[template(
+    src = "<table>{{#each rows}}<tr><td>{{ this }}</td></tr>{{/each}}</table>",
+    strict = true
+)]
+struct StrictTable {
+    rows: Vec<usize>,
+}
+
+#[test]
+fn test_strict_table() {
+    let t = StrictTable { rows: vec![1, 2] };
+    assert_eq!(
+        "<table><tr><td>1</td></tr><tr><td>2</td></tr></table>",
+        t.call().unwrap()
+    );
+}
@@ -28,6 +28,7 @@ fn test_variables() {
         num: 42,
         i18n: "Iñtërnâtiônàlizætiøn".to_string(),
     };
+    assert_eq!(VariablesTemplate::size_hint(), 124);
     assert_eq!(
         s.call::<BytesMut>(128),
         "hello world, foo\nwith number: 42\nIñtërnâtiônàlizætiøn is important\nin vars too: \
@@ -124,3 +125,74 @@ fn test_nested_for() {
     s.write_call(&mut b);
     assert_eq!(b.freeze(), "1\n  0foo1bar2baz2\n  0bar1baz".byteb())
 }
+
+#[derive(TemplateBytes)]
+#[template(path = "hello", adaptive = true)]
+struct AdaptiveTemplate<'a> {
+    name: &'a str,
+}
+
+#[test]
+fn test_adaptive() {
+    assert_eq!(AdaptiveTemplate::size_hint(), 24);
+    let name = "a".repeat(100);
+    let s = AdaptiveTemplate { name: &name };
+    assert_eq!(
+        s.call::<BytesMut>(0),
+        Bytes::from(format!("Hello, {}!", name))
+    );
+    assert_eq!(AdaptiveTemplate::size_hint(), 108);
+    let s = AdaptiveTemplate { name: "world" };
+    assert_eq!(s.ccall::<BytesMut>(0), "Hello, world!".byteb());
+    // Decays to smaller renders
+    assert_eq!(AdaptiveTemplate::size_hint(), 108 - 95 / 8);
+    let mut b = BytesMut::new();
+    AdaptiveTemplate { name: "world" }.write_call(&mut b);
+    // Only `call` and `ccall` learn the capacity
+    assert_eq!(AdaptiveTemplate::size_hint(), 108 - 95 / 8);
+}
+
+#[derive(TemplateBytes)]
+#[template(src = "<ul>{{#cache key = id ttl = 300 }}<li>{{ name }}</li>{{/cache}}</ul>")]
+struct CacheTemplate<'a> {
+    id: usize,
+    name: &'a str,
+}
+
+#[test]
+fn test_cache() {
+    let s = CacheTemplate { id: 1, name: "<a>" };
+    assert_eq!(s.call::<BytesMut>(0), "<ul><li>&lt;a&gt;</li></ul>".byteb());
+    // Same key, the fragment isn't rendered again
+    let s = CacheTemplate { id: 1, name: "b" };
+    assert_eq!(
+        s.ccall::<BytesMut>(0),
+        "<ul><li>&lt;a&gt;</li></ul>".byteb()
+    );
+    let mut b = BytesMut::new();
+    CacheTemplate { id: 2, name: "b" }.write_call(&mut b);
+    assert_eq!(b.freeze(), "<ul><li>b</li></ul>".byteb());
+}
+
+#[derive(yarte::TemplateBytesText)]
+#[template(
+    src = "{{#each items }}{{#cache key = (super::lang, this) }}{{ super::lang }}:{{ this }}<{{/cache}};{{/each}}"
+)]
+struct CacheTextTemplate<'a> {
+    lang: &'a str,
+    items: &'a [u8],
+}
+
+#[test]
+fn test_cache_text() {
+    let s = CacheTextTemplate {
+        lang: "en",
+        items: &[1, 2, 1],
+    };
+    assert_eq!(s.call::<BytesMut>(0), "en:1<;en:2<;en:1<;".byteb());
+    let s = CacheTextTemplate {
+        lang: "es",
+        items: &[1],
+    };
+    assert_eq!(s.call::<BytesMut>(0), "es:1<;".byteb());
+}
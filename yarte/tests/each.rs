@@ -54,3 +54,16 @@ fn test_for_range() {
     let s = ForRangeTemplate { init: -1, end: 1 };
     assert_eq!(s.call().unwrap(), "foo\nfoo\nbar\nbar\nfoo\nbar\nbar\n");
 }
+
+#[derive(Template)]
+#[template(src = "{{#each strings key = this }}{{ index0 }}{{ this }}{{/each}}")]
+struct KeyedTemplate<'a> {
+    strings: &'a [&'a str],
+}
+
+#[test]
+fn test_keyed() {
+    let strings: &[&str] = &["foo", "bar"];
+    let s = KeyedTemplate { strings };
+    assert_eq!(s.call().unwrap(), "0foo1bar");
+}
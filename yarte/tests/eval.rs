@@ -238,3 +238,61 @@ fn test_const_partial2() {
     let t = ConstPartial2Template { s: "bar" };
     assert_eq!(t.call().unwrap(), "foo\nIn partial\n1bar\nIn partial\n2");
 }
+
+#[derive(Template)]
+#[template(src = "{{#if build_stage == \"test\" }}test{{else}}{{ missing }}{{/if}}")]
+struct VarTemplate;
+
+#[test]
+fn test_var() {
+    let t = VarTemplate;
+    assert_eq!(t.call().unwrap(), "test");
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#if build_stage == \"prod\" }}{{ version }}{{/if}} {{ build_stage }}",
+    vars(build_stage = "prod", version = 2)
+)]
+struct VarAttrTemplate;
+
+#[test]
+fn test_var_attr() {
+    let t = VarAttrTemplate;
+    assert_eq!(t.call().unwrap(), "2 prod");
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#each items }}{{ name }}{{ super::name }} {{/each}}{{#with item }}{{ name }}{{/with}}",
+    vars(name = "var")
+)]
+struct VarScopeTemplate {
+    items: Vec<Item>,
+    item: Item,
+}
+
+struct Item {
+    name: &'static str,
+}
+
+#[test]
+fn test_var_scope() {
+    let t = VarScopeTemplate {
+        items: vec![Item { name: "a" }, Item { name: "b" }],
+        item: Item { name: "c" },
+    };
+    assert_eq!(t.call().unwrap(), "avar bvar c");
+}
+
+#[derive(Template)]
+#[template(
+    src = "{{#if cfg!(feature = \"std\") && cfg!(not(feature = \"missing\")) }}std{{else}}{{ missing }}{{/if}}"
+)]
+struct CfgTemplate;
+
+#[test]
+fn test_cfg() {
+    let t = CfgTemplate;
+    assert_eq!(t.call().unwrap(), "std");
+}
@@ -57,3 +57,24 @@ fn main() {
         Bytes::from(expected)
     )
 }
+
+#[derive(TemplateBytesMin)]
+#[template(src = "<ul>\n  {{#cache key = id }}\n  <li> {{ name }} </li>\n  {{/cache}}\n</ul>")]
+struct CacheTemplateB<'a> {
+    id: usize,
+    name: &'a str,
+}
+
+#[test]
+fn cache_min() {
+    let t = CacheTemplateB { id: 1, name: "a" };
+    assert_eq!(
+        TemplateBytesMin::call::<BytesMut>(&t, 0),
+        Bytes::from("<ul><li>a</li></ul>")
+    );
+    let t = CacheTemplateB { id: 1, name: "b" };
+    assert_eq!(
+        TemplateBytesMin::call::<BytesMut>(&t, 0),
+        Bytes::from("<ul><li>a</li></ul>")
+    );
+}
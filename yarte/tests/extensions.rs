@@ -0,0 +1,82 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(path = "extensions/hello.txt")]
+struct TextTemplate<'a> {
+    name: &'a str,
+}
+
+#[test]
+fn test_text_extension() {
+    let t = TextTemplate {
+        name: "<Tom & Jerry>",
+    };
+    assert_eq!("Hello, <Tom & Jerry>!", t.call().unwrap());
+}
+
+#[derive(Template)]
+#[template(path = "extensions/hello.txt.hbs")]
+struct TextFullTemplate<'a> {
+    name: &'a str,
+}
+
+#[test]
+fn test_text_full_extension() {
+    let t = TextFullTemplate {
+        name: "<Tom & Jerry>",
+    };
+    assert_eq!("Hello, <Tom & Jerry>!", t.call().unwrap());
+}
+
+#[derive(Template)]
+#[template(path = "extensions/item.xml")]
+struct XmlTemplate<'a> {
+    title: &'a str,
+}
+
+#[test]
+fn test_xml_extension() {
+    let t = XmlTemplate { title: "a < b" };
+    assert_eq!("<title>a &lt; b</title>", t.call().unwrap());
+}
+
+#[derive(Template)]
+#[template(path = "extensions/mail.eml")]
+struct ConfigTemplate<'a> {
+    subject: &'a str,
+}
+
+#[test]
+fn test_config_extension() {
+    let t = ConfigTemplate { subject: "\"Hi\"" };
+    assert_eq!("Subject: \"Hi\"", t.call().unwrap());
+}
+
+#[cfg(feature = "html-min")]
+mod min {
+    use yarte::TemplateMin;
+
+    #[derive(TemplateMin)]
+    #[template(path = "extensions/hello.html")]
+    struct HtmlMinTemplate<'a> {
+        name: &'a str,
+    }
+
+    #[test]
+    fn test_html_min_extension() {
+        let t = HtmlMinTemplate { name: "&" };
+        assert_eq!("<p>&amp;</p>", t.call().unwrap());
+    }
+
+    #[derive(TemplateMin)]
+    #[template(path = "extensions/hello.txt")]
+    struct TextMinTemplate<'a> {
+        name: &'a str,
+    }
+
+    #[test]
+    fn test_text_min_extension() {
+        let t = TextMinTemplate { name: "&" };
+        assert_eq!("Hello, &!", t.call().unwrap());
+    }
+}
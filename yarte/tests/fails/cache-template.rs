@@ -0,0 +1,10 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(src = "{{#cache key = id }}<b>{{ name }}</b>{{/cache}}")]
+struct Test<'a> {
+    id: usize,
+    name: &'a str,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use yarte::Template;
+
+#[derive(Template)]
+#[template(src = "<div>\n  <span>{{ foo }}</p>\n</div>", strict = true)]
+struct Test {
+    foo: usize
+}
+
+fn main() {}
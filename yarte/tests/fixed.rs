@@ -167,3 +167,48 @@ fn test_forc() {
         "1234".repeat(2).as_bytes()
     );
 }
+
+#[derive(TemplateFixed)]
+#[template(src = "<p>{{ n }}{{# if ok }}{{ c }}{{ else }}none{{/if }}</p>")]
+struct Bounded {
+    n: u8,
+    ok: bool,
+    c: char,
+}
+
+#[derive(TemplateFixed)]
+#[template(src = "{{# each list }}<li>{{ this }}{{ super::n }}</li>{{/each }}")]
+struct BoundedEach {
+    list: [u16; 4],
+    n: u8,
+}
+
+#[test]
+fn test_render_into() {
+    use yarte::{Overflow, TemplateFixedTrait};
+
+    assert_eq!(Bounded::MAX_LEN, Some(3 + 3 + 6 + 4));
+    assert_eq!(ForTemplateC::MAX_LEN, None);
+    assert_eq!(BoundedEach::MAX_LEN, Some(4 * (4 + 5 + 3 + 5)));
+
+    let s = Bounded {
+        n: 255,
+        ok: true,
+        c: '<',
+    };
+    assert_eq!(
+        s.render_into(&mut [MaybeUninit::uninit(); 16]).unwrap(),
+        b"<p>255&lt;</p>"
+    );
+    assert_eq!(
+        s.render_into(&mut [MaybeUninit::uninit(); 13]),
+        Err(Overflow {
+            needed_at_least: 14
+        })
+    );
+    assert_eq!(
+        s.render_into(&mut [MaybeUninit::uninit(); 4]),
+        Err(Overflow { needed_at_least: 4 })
+    );
+    assert_eq!(&*s.render_array::<16>(), b"<p>255&lt;</p>");
+}
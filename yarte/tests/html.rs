@@ -214,7 +214,25 @@ fn test_raw_html() {
     use yarte_helpers::config::{read_config_file, Config};
     let config = read_config_file();
     let config = Config::new(&config);
-    let (_, expected) = config.get_template(Path::new("html/raw/expected.html"));
+    let (_, expected) = config
+        .get_template(Path::new("html/raw/expected.html"))
+        .unwrap();
 
     assert_eq!(RawHtml.call().unwrap(), expected);
 }
+
+#[derive(Template)]
+#[template(
+    src = "<div>\n  <!-- removed -->\n  <!-- {{ id }} -->\n  <!--[if IE]><p>IE</p><![endif]-->\n  <pre>  {{ id }}\n</pre>\n</div>"
+)]
+struct CommentsTemplate {
+    id: usize,
+}
+
+#[test]
+fn test_comments() {
+    assert_eq!(
+        CommentsTemplate { id: 1 }.call().unwrap(),
+        "<div><!-- 1 --> <!--[if IE]><p>IE</p><![endif]--><pre>  1\n</pre></div>"
+    );
+}
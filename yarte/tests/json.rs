@@ -331,3 +331,24 @@ fn named_lifetime() {
     let e = r#"{"f1":"snot","f2":"badger"}"#;
     test_encode_ok(&[(b, e)]);
 }
+
+#[test]
+fn adaptive() {
+    #[derive(Serialize, PartialEq, Debug)]
+    #[serialize(adaptive)]
+    struct Adaptive<'a> {
+        name: &'a str,
+    }
+
+    #[derive(Serialize, PartialEq, Debug)]
+    #[serialize(adaptive)]
+    enum AdaptiveEnum {
+        Foo(usize),
+    }
+
+    let name = "a".repeat(100);
+    let e = format!(r#"{{"name":"{}"}}"#, name);
+    test_encode_ok(&[(Adaptive { name: &name }, e.as_str())]);
+    test_encode_ok(&[(Adaptive { name: "b" }, r#"{"name":"b"}"#)]);
+    test_encode_ok(&[(AdaptiveEnum::Foo(1), r#"{"Foo":1}"#)]);
+}
@@ -423,3 +423,27 @@ fn test_partial_block_with_ctx() {
 
     assert_eq!(t.call().unwrap(), "FoobBar_0a1");
 }
+
+#[derive(Template)]
+#[template(src = "<p>{{> button label = \"ok\" }}</p>")]
+struct PartialSearchDirTemplate;
+
+#[test]
+fn test_partial_search_dir() {
+    assert_eq!(
+        PartialSearchDirTemplate.call().unwrap(),
+        "<p><button>ok</button></p>"
+    )
+}
+
+#[derive(Template)]
+#[template(path = "button")]
+struct SearchDirTemplate<'a> {
+    label: &'a str,
+}
+
+#[test]
+fn test_search_dir() {
+    let t = SearchDirTemplate { label: "go" };
+    assert_eq!(t.call().unwrap(), "<button>go</button>")
+}
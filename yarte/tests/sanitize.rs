@@ -0,0 +1,68 @@
+#![cfg(feature = "sanitize")]
+use yarte::{Allowlist, Sanitize, Template};
+
+#[derive(Template)]
+#[template(src = "<div>{{ @sanitize comment }}</div>")]
+struct Comment<'a> {
+    comment: &'a str,
+}
+
+#[derive(Template)]
+#[template(src = "{{#each comments}}<li>{{ @sanitize this }}</li>{{/each}}")]
+struct Comments {
+    comments: Vec<String>,
+}
+
+const SRC: &str = "<p class=\"a\" style=\"b\" onclick=\"alert(1)\">Hi <b>all</b>\
+                   <script>alert(2)</script><a href=\"javascript:alert(3)\">x</a></p>";
+const EXPECTED: &str = "<p class=\"a\">Hi <b>all</b><a>x</a></p>";
+
+#[test]
+fn test_sanitize() {
+    let t = Comment { comment: SRC };
+    assert_eq!(format!("<div>{}</div>", EXPECTED), t.call().unwrap());
+
+    let t = Comments {
+        comments: vec!["<i>a</i>".into(), "b<img src=x onerror=alert(1)>".into()],
+    };
+    assert_eq!("<li><i>a</i></li><li>b</li>", t.call().unwrap());
+}
+
+#[test]
+fn test_wrapper() {
+    let allow = Allowlist {
+        tags: &["b"],
+        attrs: &[],
+    };
+    assert_eq!(
+        "a <b>b</b> c",
+        Sanitize("<i>a</i> <b id=\"b\">b</b> c", allow).to_string()
+    );
+    assert_eq!(
+        "<p>Hi <b>all</b><a>x</a></p>",
+        Sanitize::new(SRC).to_string()
+    );
+}
+
+#[cfg(any(feature = "bytes-buf", feature = "bytes-buf-tokio2"))]
+mod bytes {
+    use super::*;
+    use yarte::{RenderBytes, RenderBytesSafe, TemplateBytes};
+
+    #[derive(TemplateBytes)]
+    #[template(src = "<div>{{ @sanitize comment }}</div>")]
+    struct CommentB<'a> {
+        comment: &'a str,
+    }
+
+    #[test]
+    fn test_sanitize() {
+        let t = CommentB { comment: SRC };
+        assert_eq!(format!("<div>{}</div>", EXPECTED), t.call::<String>(0));
+
+        let mut buf = String::new();
+        RenderBytes::render(Sanitize::new("<b>a</b><u>b</u>"), &mut buf);
+        RenderBytesSafe::render(Sanitize::new("<x>c</x>"), &mut buf);
+        assert_eq!(buf, "<b>a</b><u>b</u>c");
+    }
+}
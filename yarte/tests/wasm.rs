@@ -31,14 +31,108 @@ fn wasm_server() {
     };
 
     assert_eq!(
-        t.call::<BytesMut>(10240),
-        Bytes::from(
-        "<!DOCTYPE html><html><head><title>Fortunes</title></head><body><table>\
+        render(t.call::<BytesMut>(10240)),
+        "<!DOCTYPE html><html><head><title>Fortunes</title>\
+        <link rel=\"modulepreload\" href=\"./pkg/example.js\"></head><body><table>\
         <thead><tr><th>id</th><th>message</th></tr></thead>\
         <tbody><tr><td>0</td><td>foo</td></tr><tr><td>1</td><td>bar</td></tr></tbody>\
         </table>\
-        <script>function get_state(){return JSON.stringify({\"fortunes\":[{\"id\":0,\"message\":\"foo\"},{\"id\":1,\"message\":\"bar\"}]});}</script>\
+        <script type=\"application/json\" id=\"yarte-state\" data-yarte-hash=\"\">\
+        {\"fortunes\":[{\"id\":0,\"message\":\"foo\"},{\"id\":1,\"message\":\"bar\"}]}</script>\
         <script type=\"module\">import init from \'./pkg/example.js\';async function run(){await init()}run()</script>\
-        </body></html>")
+        </body></html>"
     )
 }
+
+/// Remove template hash, it depends on the std hasher
+fn render(html: Bytes) -> String {
+    let html = std::str::from_utf8(&html).unwrap();
+    let (head, tail) = html.split_at(html.find("data-yarte-hash=\"").unwrap() + 17);
+    let (hash, tail) = tail.split_at(tail.find('"').unwrap());
+    assert!(!hash.is_empty() && hash.chars().all(|x| x.is_ascii_hexdigit()));
+
+    format!("{}{}", head, tail)
+}
+
+#[derive(Template, Serialize)]
+#[template(
+    src = "<!DOCTYPE html><html><head><title>{{ title }}</title></head><body><p>{{ title }}</p></body></html>",
+    script = "./pkg/app.js",
+    integrity = "sha384-js",
+    wasm = "./pkg/app_bg.wasm",
+    wasm_integrity = "sha384-wasm",
+    nonce = "nonce"
+)]
+struct Bootstrap {
+    title: String,
+    nonce: String,
+}
+
+#[test]
+fn bootstrap() {
+    let t = Bootstrap {
+        title: "foo".into(),
+        nonce: "bar".into(),
+    };
+
+    assert_eq!(
+        render(t.call::<BytesMut>(1024)),
+        "<!DOCTYPE html><html><head><title>foo</title>\
+        <link rel=\"modulepreload\" href=\"./pkg/app.js\" integrity=\"sha384-js\" nonce=\"bar\">\
+        <link rel=\"preload\" href=\"./pkg/app_bg.wasm\" as=\"fetch\" type=\"application/wasm\" \
+        crossorigin=\"anonymous\" integrity=\"sha384-wasm\"></head><body><p>foo</p>\
+        <script type=\"application/json\" id=\"yarte-state\" data-yarte-hash=\"\">{\"title\":\"foo\",\"nonce\":\"bar\"}</script>\
+        <script type=\"module\" nonce=\"bar\">import init from \'./pkg/app.js\';\
+        async function run(){await init(fetch(\'./pkg/app_bg.wasm\',{integrity:\'sha384-wasm\'}))}run()</script>\
+        </body></html>"
+    )
+}
+
+#[derive(Template, Serialize)]
+#[template(src = "<p>{{ count }}</p>", script = "./pkg/app.js", mount = "app")]
+struct Mount {
+    count: usize,
+}
+
+#[test]
+fn mount() {
+    assert_eq!(
+        render(Mount { count: 1 }.call::<BytesMut>(1024)),
+        "<p>1</p><link rel=\"modulepreload\" href=\"./pkg/app.js\">\
+        <script type=\"application/json\" id=\"app-state\" data-yarte-hash=\"\">{\"count\":1}</script>\
+        <script type=\"module\">import init from \'./pkg/app.js\';async function run(){await init()}run()</script>"
+    )
+}
+
+#[derive(Template, Serialize)]
+#[template(
+    src = "<form><input bind:value=\"{{ name }}\">\
+<input type=\"checkbox\" bind:checked=\"{{ done }}\"><p>{{ name }}</p></form>",
+    script = "./pkg/app.js",
+    mount = "app"
+)]
+struct Bind {
+    name: String,
+    done: bool,
+}
+
+#[test]
+fn bind() {
+    let t = Bind {
+        name: "foo".into(),
+        done: true,
+    };
+    assert_eq!(
+        render(t.call::<BytesMut>(1024)),
+        "<form><input value=\"foo\"><input type=\"checkbox\" checked><p>foo</p></form>\
+        <link rel=\"modulepreload\" href=\"./pkg/app.js\">\
+        <script type=\"application/json\" id=\"app-state\" data-yarte-hash=\"\">{\"name\":\"foo\",\"done\":true}</script>\
+        <script type=\"module\">import init from \'./pkg/app.js\';async function run(){await init()}run()</script>"
+    );
+    let t = Bind {
+        name: "bar".into(),
+        done: false,
+    };
+    assert!(render(t.call::<BytesMut>(1024))
+        .starts_with("<form><input value=\"bar\"><input type=\"checkbox\" ><p>bar</p></form>"));
+}
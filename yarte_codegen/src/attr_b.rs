@@ -68,4 +68,8 @@ impl<T: CodeGen> CodeGen for AttrBCodeGen<T> {
             }
         }
     }
+
+    fn take_errors(&mut self) -> Vec<String> {
+        self.codegen.take_errors()
+    }
 }
@@ -1,16 +1,17 @@
 use proc_macro2::{Ident, TokenStream};
 use quote::{format_ident, quote};
 
-use yarte_hir::{Struct, HIR};
+use yarte_hir::{Cache, Component, Struct, HIR};
 
 use crate::EachCodeGen;
-use crate::{CodeGen, IfElseCodeGen};
+use crate::{size_hint::size_hint, CodeGen, IfElseCodeGen};
 
 pub struct BytesCodeGen<'a, T: CodeGen> {
     codegen: T,
     s: &'a Struct<'a>,
     parent: Ident,
     buf: Ident,
+    text: bool,
 }
 
 impl<'a, T: CodeGen> BytesCodeGen<'a, T> {
@@ -25,34 +26,55 @@ impl<'a, T: CodeGen> BytesCodeGen<'a, T> {
             s,
             parent: format_ident!("{}", parent),
             buf,
+            text: false,
         }
     }
 
+    /// Without html escape, the responses are `text/plain`
+    pub fn text(self) -> Self {
+        BytesCodeGen { text: true, ..self }
+    }
+
     #[inline]
     fn template(&mut self, nodes: Vec<HIR>, tokens: &mut TokenStream) {
+        let size_hint = size_hint(&nodes, &self.s.fields);
         let nodes = self.codegen.gen(nodes);
         let parent = &self.parent;
         let buf = &self.buf;
-        tokens.extend(self.s.implement_head(
+        // Adaptive templates start with the capacity learnt from previous renders
+        let (capacity, record, hint) = if self.s.adaptive {
+            (
+                quote!(capacity.max(__YARTE_SIZE.with(|v| v.get()))),
+                quote! {
+                    __YARTE_SIZE.with(|v| v.set(#parent::adapt_size(v.get(), #buf.len())));
+                },
+                quote!(__YARTE_SIZE.with(|v| v.get())),
+            )
+        } else {
+            (quote!(capacity), quote!(), quote!(#size_hint))
+        };
+        let template = self.s.implement_head(
             quote!(#parent::TemplateBytesTrait),
             &quote!(
                 fn call<B: #parent::Buffer>(&self, capacity: usize) -> B::Freeze {
                     use #parent::*;
-                    let mut #buf = B::with_capacity(capacity);
+                    let mut #buf = B::with_capacity(#capacity);
                     macro_rules! buf_ref {
                         ($b:expr) => { &mut $b };
                     }
                     #nodes
+                    #record
                     #buf.freeze()
                 }
 
                 fn ccall<B: #parent::Buffer>(self, capacity: usize) -> B::Freeze {
                     use #parent::*;
-                    let mut #buf = B::with_capacity(capacity);
+                    let mut #buf = B::with_capacity(#capacity);
                     macro_rules! buf_ref {
                         ($b:expr) => { &mut $b };
                     }
                     #nodes
+                    #record
                     #buf.freeze()
                 }
 
@@ -71,7 +93,27 @@ impl<'a, T: CodeGen> BytesCodeGen<'a, T> {
                     }
                     #nodes
                 }
+
+                fn size_hint() -> usize {
+                    #hint
+                }
             ),
+        );
+        if self.s.adaptive {
+            tokens.extend(quote! {
+                const _: () = {
+                    thread_local! {
+                        static __YARTE_SIZE: std::cell::Cell<usize> = std::cell::Cell::new(#size_hint);
+                    }
+                    #template
+                };
+            });
+        } else {
+            tokens.extend(template);
+        }
+        #[cfg(any(feature = "axum", feature = "actix-web", feature = "hyper"))]
+        tokens.extend(crate::responses::responses(
+            self.s, parent, self.text, size_hint,
         ));
     }
 }
@@ -84,6 +126,10 @@ impl<'a, T: CodeGen> CodeGen for BytesCodeGen<'a, T> {
 
         tokens
     }
+
+    fn take_errors(&mut self) -> Vec<String> {
+        self.codegen.take_errors()
+    }
 }
 
 pub struct TextBytesCodeGen<'a> {
@@ -117,6 +163,11 @@ impl<'a> CodeGen for TextBytesCodeGen<'a> {
                 }
                 Each(a) => self.gen_each(*a),
                 IfElse(a) => self.gen_if_else(*a),
+                Cache(a) => {
+                    let buf = self.buf;
+                    cache(self, *a, &quote!(#buf))
+                }
+                Component(_) => unreachable!("only applications lower components"),
             });
         }
         tokens
@@ -137,12 +188,82 @@ where
             Expr(a) => quote!(&(#a).__render_itb(buf_ref!(#buf));),
             Each(a) => codegen.gen_each(*a),
             IfElse(a) => codegen.gen_if_else(*a),
+            Cache(a) => cache(codegen, *a, &buf),
+            Component(a) => component(*a, &buf),
         })
     }
 
     quote! {{ #tokens }}
 }
 
+/// Looks up the fragment before render its body, the body is rendered
+/// in a `String` that shadows the buffer
+fn cache<C: CodeGen>(
+    codegen: &mut C,
+    Cache {
+        path,
+        start: (line, column),
+        key,
+        ttl,
+        body,
+    }: Cache,
+    buf: &TokenStream,
+) -> TokenStream {
+    let capacity = size_hint(&body, &[]);
+    let body = codegen.gen(body);
+    let ttl = match ttl {
+        Some(ttl) => quote!(Some(std::time::Duration::from_secs(#ttl))),
+        None => quote!(None),
+    };
+    quote! {{
+        let __fragment_key = yarte::FragmentKey::new(
+            #path,
+            (#line, #column),
+            std::any::type_name::<Self>(),
+            &(#key),
+        );
+        match yarte::fragment_cache().get(&__fragment_key) {
+            Some(__fragment) => yarte::Buffer::extend(buf_ref!(#buf), &__fragment),
+            None => {
+                let __fragment = {
+                    let mut #buf = String::with_capacity(#capacity);
+                    {
+                        macro_rules! buf_ref {
+                            ($b:expr) => { &mut $b };
+                        }
+                        #body
+                    }
+                    #buf
+                };
+                yarte::Buffer::extend(buf_ref!(#buf), &__fragment);
+                yarte::fragment_cache().insert(__fragment_key, __fragment.into(), #ttl);
+            }
+        }
+    }}
+}
+
+/// Renders the initial state of the nested application with its props
+fn component(
+    Component {
+        mut path, props, ..
+    }: Component,
+    buf: &TokenStream,
+) -> TokenStream {
+    if let Some(last) = path.segments.last_mut() {
+        last.ident = format_ident!("{}InitialState", last.ident);
+    }
+    let (fields, exprs): (Vec<_>, Vec<_>) = props.into_iter().unzip();
+    quote! {{
+        // Props can be all the fields
+        #[allow(clippy::needless_update)]
+        let __state = #path {
+            #(#fields: (#exprs).clone(),)*
+            ..Default::default()
+        };
+        yarte::TemplateBytesTrait::write_ccall(__state, buf_ref!(#buf));
+    }}
+}
+
 fn literal(a: String, buf: &TokenStream) -> TokenStream {
     let len = a.len();
     let b = a.as_bytes();
@@ -206,14 +327,21 @@ impl<'a> CodeGen for HTMLBytesCodeGen<'a> {
 pub mod html_min {
     use super::*;
     use yarte_dom::DOMFmt;
+    use yarte_helpers::config::MinifyOption;
 
     pub struct HTMLMinBytesCodeGen<'a> {
         buf: &'a syn::Expr,
+        minify: MinifyOption,
+        errors: Vec<String>,
     }
 
     impl<'a> HTMLMinBytesCodeGen<'a> {
-        pub fn new(buf: &syn::Expr) -> HTMLMinBytesCodeGen {
-            HTMLMinBytesCodeGen { buf }
+        pub fn new(buf: &syn::Expr, minify: MinifyOption) -> HTMLMinBytesCodeGen {
+            HTMLMinBytesCodeGen {
+                buf,
+                minify,
+                errors: vec![],
+            }
         }
     }
 
@@ -222,9 +350,18 @@ pub mod html_min {
 
     impl<'a> CodeGen for HTMLMinBytesCodeGen<'a> {
         fn gen(&mut self, v: Vec<HIR>) -> TokenStream {
-            let dom: DOMFmt = v.into();
             let buf = self.buf;
-            gen(self, dom.0, quote!(#buf))
+            match DOMFmt::new(v, self.minify) {
+                Ok(dom) => gen(self, dom.0, quote!(#buf)),
+                Err(e) => {
+                    self.errors.extend(e.iter().map(ToString::to_string));
+                    TokenStream::new()
+                }
+            }
+        }
+
+        fn take_errors(&mut self) -> Vec<String> {
+            std::mem::take(&mut self.errors)
         }
     }
 }
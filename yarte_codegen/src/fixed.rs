@@ -1,9 +1,14 @@
 use proc_macro2::{Ident, TokenStream};
 use quote::{format_ident, quote};
 
-use yarte_hir::{Struct, HIR};
+use syn::{Expr, ExprPath, ExprTuple, ExprUnary, Field, Type, TypeArray, UnOp};
 
-use crate::{CodeGen, EachCodeGen, IfElseCodeGen};
+use yarte_hir::{Each, IfElse, Struct, HIR};
+
+use crate::{
+    size_hint::{each_args, field_type, iterations, type_width},
+    CodeGen, EachCodeGen, IfElseCodeGen,
+};
 
 pub struct FixedCodeGen<'a, T: CodeGen> {
     codegen: T,
@@ -22,87 +27,154 @@ impl<'a, T: CodeGen> FixedCodeGen<'a, T> {
 
     #[inline]
     fn template(&mut self, nodes: Vec<HIR>, tokens: &mut TokenStream) {
+        let max_len = match max_len(&nodes, &self.s.fields, &[]) {
+            Some(len) => quote!(Some(#len)),
+            None => quote!(None),
+        };
         let nodes = self.codegen.gen(nodes);
         let parent = &self.parent;
         tokens.extend(self.s.implement_head(
             quote!(yarte::TemplateFixedTrait),
             &quote!(
-                unsafe fn call<'call>(&self, buf: &'call mut [std::mem::MaybeUninit<u8>]) -> Option<&'call [u8]> {
-                    unsafe {
-                    #[allow(unused_import)]
-                    use #parent::*;
-                    macro_rules! buf_ptr {
-                        () => { buf as *mut _ as * mut u8 };
-                    }
-                    macro_rules! len {
-                        () => { buf.len() };
-                    }
+                const MAX_LEN: Option<usize> = #max_len;
+
+                fn render_into<'call>(&self, buf: &'call mut [core::mem::MaybeUninit<u8>]) -> core::result::Result<&'call [u8], #parent::Overflow> {
                     let mut buf_cur = 0;
+                    let mut needed_at_least = 0;
+                    // `?` at template expressions stops rendering
+                    let written = (|| -> Option<()> {
+                        unsafe {
+                        #[allow(unused_import)]
+                        use #parent::*;
+                        macro_rules! buf_ptr {
+                            () => { buf as *mut _ as * mut u8 };
+                        }
+                        macro_rules! len {
+                            () => { buf.len() };
+                        }
 
-                    #[allow(unused_macros)]
-                    macro_rules! __yarte_check_write {
-                        ($len:expr, $write:block) => {
-                            if len!() < buf_cur + $len {
-                                return None;
-                            } else $write
-                        };
-                    }
-                    #[allow(unused_macros)]
-                    macro_rules! __yarte_write_bytes_long {
-                        ($b:expr) => {
-                            __yarte_check_write!($b.len(), {
-                                // Not use copy_from_slice for elide double checked
-                                std::ptr::copy_nonoverlapping((&$b as *const _ as *const u8), buf_ptr!().add(buf_cur), $b.len());
-                                buf_cur += $b.len();
-                            })
-                        };
-                    }
+                        #[allow(unused_macros)]
+                        macro_rules! __yarte_check_write {
+                            ($len:expr, $write:block) => {
+                                if len!() < buf_cur + $len {
+                                    needed_at_least = buf_cur + $len;
+                                    return None;
+                                } else $write
+                            };
+                        }
+                        #[allow(unused_macros)]
+                        macro_rules! __yarte_check_render {
+                            ($len:expr) => {
+                                match $len {
+                                    Some(len) => len,
+                                    None => {
+                                        needed_at_least = buf_cur + 1;
+                                        return None;
+                                    }
+                                }
+                            };
+                        }
+                        #[allow(unused_macros)]
+                        macro_rules! __yarte_write_bytes_long {
+                            ($b:expr) => {
+                                __yarte_check_write!($b.len(), {
+                                    // Not use copy_from_slice for elide double checked
+                                    core::ptr::copy_nonoverlapping((&$b as *const _ as *const u8), buf_ptr!().add(buf_cur), $b.len());
+                                    buf_cur += $b.len();
+                                })
+                            };
+                        }
 
-                    #nodes
-                    Some(std::slice::from_raw_parts(buf as *const _ as *const u8, buf_cur))
-                    }
-                }
+                        #nodes
+                        Some(())
+                        }
+                    })();
 
-                unsafe fn ccall(self, buf: &mut [std::mem::MaybeUninit<u8>]) -> Option<&[u8]> {
-                    unsafe {
-                    #[allow(unused_import)]
-                    use #parent::*;
-                    macro_rules! buf_ptr {
-                        () => { buf as *mut _ as * mut u8 };
-                    }
-                    macro_rules! len {
-                        () => { buf.len() };
+                    match written {
+                        Some(()) => Ok(unsafe { core::slice::from_raw_parts(buf as *const _ as *const u8, buf_cur) }),
+                        None => Err(#parent::Overflow { needed_at_least }),
                     }
-                    let mut buf_cur = 0;
+                }
 
-                    #[allow(unused_macros)]
-                    macro_rules! __yarte_check_write {
-                        ($len:expr, $write:block) => {
-                            if len!() < buf_cur + $len {
-                                return None;
-                            } else $write
-                        };
-                    }
-                    #[allow(unused_macros)]
-                    macro_rules! __yarte_write_bytes_long {
-                        ($b:expr) => {
-                            __yarte_check_write!($b.len(), {
-                                // Not use copy_from_slice for elide double checked
-                                std::ptr::copy_nonoverlapping((&$b as *const _ as *const u8), buf_ptr!().add(buf_cur), $b.len());
-                                buf_cur += $b.len();
-                            })
-                        };
-                    }
+                unsafe fn call<'call>(&self, buf: &'call mut [core::mem::MaybeUninit<u8>]) -> Option<&'call [u8]> {
+                    #parent::TemplateFixedTrait::render_into(self, buf).ok()
+                }
 
-                    #nodes
-                    Some(std::slice::from_raw_parts(buf as *const _ as *const u8, buf_cur))
-                    }
+                unsafe fn ccall(self, buf: &mut [core::mem::MaybeUninit<u8>]) -> Option<&[u8]> {
+                    #parent::TemplateFixedTrait::render_into(&self, buf).ok()
                 }
             ),
         ));
     }
 }
 
+/// Upper bound of the rendered length, expressions are bounded by the type of `self` fields
+/// and the items of `[T; N]` fields
+fn max_len(nodes: &[HIR], fields: &[Field], items: &[(&Expr, &Type)]) -> Option<usize> {
+    nodes.iter().try_fold(0usize, |acc, x| {
+        let len = match x {
+            HIR::Lit(a) => a.len(),
+            HIR::Local(_) => 0,
+            HIR::Safe(a) | HIR::Expr(a) => expr_max_len(a, fields, items)?,
+            HIR::IfElse(a) => {
+                let IfElse { ifs, if_else, els } = &**a;
+                let mut len = max_len(&ifs.1, fields, items)?;
+                for (_, block) in if_else {
+                    len = len.max(max_len(block, fields, items)?);
+                }
+                if let Some(els) = els {
+                    len = len.max(max_len(els, fields, items)?);
+                }
+                len
+            }
+            HIR::Cache(a) => max_len(&a.body, fields, items)?,
+            HIR::Component(_) => unreachable!("only applications lower components"),
+            HIR::Each(a) => {
+                let Each {
+                    args, body, expr, ..
+                } = &**a;
+                let mut items = items.to_vec();
+                if let Some(Type::Array(TypeArray { elem, .. })) =
+                    field_type(each_args(args), fields)
+                {
+                    // Item of `(index, item)`
+                    let item = match expr {
+                        Expr::Tuple(ExprTuple { elems, .. }) => elems.last(),
+                        _ => Some(expr),
+                    };
+                    items.extend(item.map(|x| (x, &**elem)));
+                }
+                match max_len(body, fields, &items)? {
+                    0 => 0,
+                    len => len.checked_mul(iterations(args, fields)?)?,
+                }
+            }
+        };
+        acc.checked_add(len)
+    })
+}
+
+fn expr_max_len(expr: &Expr, fields: &[Field], items: &[(&Expr, &Type)]) -> Option<usize> {
+    let mut item = expr;
+    while let Expr::Unary(ExprUnary {
+        op: UnOp::Deref(_),
+        expr,
+        ..
+    }) = item
+    {
+        item = expr;
+    }
+    let ty = match item {
+        Expr::Path(ExprPath { path, .. }) => items
+            .iter()
+            .find(|(x, _)| matches!(x, Expr::Path(x) if x.path == *path))
+            .map(|(_, ty)| *ty),
+        _ => None,
+    };
+
+    type_width(ty.or_else(|| field_type(expr, fields))?)?.1
+}
+
 fn literal(a: String, parent: &Ident) -> TokenStream {
     let len = a.len();
     let b = a.as_bytes();
@@ -158,6 +230,10 @@ impl<'a, T: CodeGen> CodeGen for FixedCodeGen<'a, T> {
 
         tokens
     }
+
+    fn take_errors(&mut self) -> Vec<String> {
+        self.codegen.take_errors()
+    }
 }
 
 pub struct TextFixedCodeGen(pub &'static str);
@@ -175,10 +251,12 @@ impl CodeGen for TextFixedCodeGen {
                 Local(a) => quote!(#a),
                 Lit(a) => literal(a, &parent),
                 Safe(a) | Expr(a) => {
-                    quote!(buf_cur += &(#a).__render_it_safe(&mut buf[buf_cur..])?;)
+                    quote!(buf_cur += __yarte_check_render!(&(#a).__render_it_safe(&mut buf[buf_cur..]));)
                 }
                 Each(a) => self.gen_each(*a),
                 IfElse(a) => self.gen_if_else(*a),
+                Cache(a) => self.gen(a.body),
+                Component(_) => unreachable!("only applications lower components"),
             });
         }
         tokens
@@ -196,10 +274,16 @@ where
         tokens.extend(match i {
             Local(a) => quote!(#a),
             Lit(a) => literal(a, &parent),
-            Safe(a) => quote!(buf_cur += &(#a).__render_it_safe(&mut buf[buf_cur..])?;),
-            Expr(a) => quote!(buf_cur += &(#a).__render_it(&mut buf[buf_cur..])?;),
+            Safe(a) => {
+                quote!(buf_cur += __yarte_check_render!(&(#a).__render_it_safe(&mut buf[buf_cur..]));)
+            }
+            Expr(a) => {
+                quote!(buf_cur += __yarte_check_render!(&(#a).__render_it(&mut buf[buf_cur..]));)
+            }
             Each(a) => codegen.gen_each(*a),
             IfElse(a) => codegen.gen_if_else(*a),
+            Cache(a) => codegen.gen(a.body),
+            Component(_) => unreachable!("only applications lower components"),
         })
     }
     tokens
@@ -221,16 +305,41 @@ impl CodeGen for HTMLFixedCodeGen {
 pub mod html_min {
     use super::*;
     use yarte_dom::DOMFmt;
+    use yarte_helpers::config::MinifyOption;
+
+    pub struct HTMLMinFixedCodeGen {
+        parent: &'static str,
+        minify: MinifyOption,
+        errors: Vec<String>,
+    }
+
+    impl HTMLMinFixedCodeGen {
+        pub fn new(parent: &'static str, minify: MinifyOption) -> HTMLMinFixedCodeGen {
+            HTMLMinFixedCodeGen {
+                parent,
+                minify,
+                errors: vec![],
+            }
+        }
+    }
 
-    pub struct HTMLMinFixedCodeGen(pub &'static str);
     impl EachCodeGen for HTMLMinFixedCodeGen {}
     impl IfElseCodeGen for HTMLMinFixedCodeGen {}
 
     impl CodeGen for HTMLMinFixedCodeGen {
         fn gen(&mut self, v: Vec<HIR>) -> TokenStream {
-            let parent = self.0;
-            let dom: DOMFmt = v.into();
-            gen(self, dom.0, parent)
+            let parent = self.parent;
+            match DOMFmt::new(v, self.minify) {
+                Ok(dom) => gen(self, dom.0, parent),
+                Err(e) => {
+                    self.errors.extend(e.iter().map(ToString::to_string));
+                    TokenStream::new()
+                }
+            }
+        }
+
+        fn take_errors(&mut self) -> Vec<String> {
+            std::mem::take(&mut self.errors)
         }
     }
 }
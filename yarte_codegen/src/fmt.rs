@@ -3,7 +3,7 @@ use quote::{format_ident, quote};
 
 use yarte_hir::{Struct, HIR};
 
-use crate::CodeGen;
+use crate::{size_hint::size_hint, CodeGen};
 
 pub struct FmtCodeGen<'a, T: CodeGen> {
     codegen: T,
@@ -22,19 +22,41 @@ impl<'a, T: CodeGen> FmtCodeGen<'a, T> {
 
     #[inline]
     fn template(&self, size_hint: usize, tokens: &mut TokenStream) {
-        tokens.extend(self.s.implement_head(
-            quote!(yarte::TemplateTrait),
-            &quote!(
-            fn size_hint() -> usize {
-                #size_hint
-            }),
-        ));
+        if self.s.adaptive {
+            let template = self.s.implement_head(
+                quote!(yarte::TemplateTrait),
+                &quote!(
+                    fn size_hint() -> usize {
+                        __YARTE_SIZE.with(|v| v.get())
+                    }
+
+                    fn record_size(len: usize) {
+                        __YARTE_SIZE.with(|v| v.set(yarte::adapt_size(v.get(), len)))
+                    }
+                ),
+            );
+            tokens.extend(quote! {
+                const _: () = {
+                    thread_local! {
+                        static __YARTE_SIZE: std::cell::Cell<usize> = std::cell::Cell::new(#size_hint);
+                    }
+                    #template
+                };
+            });
+        } else {
+            tokens.extend(self.s.implement_head(
+                quote!(yarte::TemplateTrait),
+                &quote!(
+                fn size_hint() -> usize {
+                    #size_hint
+                }),
+            ));
+        }
     }
 
     fn display(&mut self, nodes: Vec<HIR>, tokens: &mut TokenStream) -> usize {
+        let size_hint = size_hint(&nodes, &self.s.fields);
         let nodes = self.codegen.gen(nodes);
-        // heuristic based on https://github.com/lfairy/maud
-        let size_hint = nodes.to_string().len();
         let parent = &self.parent;
         let func = quote!(
             fn fmt(&self, _fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -62,4 +84,8 @@ impl<'a, T: CodeGen> CodeGen for FmtCodeGen<'a, T> {
 
         tokens
     }
+
+    fn take_errors(&mut self) -> Vec<String> {
+        self.codegen.take_errors()
+    }
 }
@@ -17,6 +17,8 @@ where
             Expr(a) => quote!(&(#a).__renders_it(_fmt)?;),
             Each(a) => codegen.gen_each(*a),
             IfElse(a) => codegen.gen_if_else(*a),
+            Cache(a) => codegen.gen(a.body),
+            Component(_) => unreachable!("only applications lower components"),
         })
     }
     tokens
@@ -37,15 +39,38 @@ impl CodeGen for HTMLCodeGen {
 pub mod html_min {
     use super::*;
     use yarte_dom::DOMFmt;
+    use yarte_helpers::config::MinifyOption;
+
+    pub struct HTMLMinCodeGen {
+        minify: MinifyOption,
+        errors: Vec<String>,
+    }
+
+    impl HTMLMinCodeGen {
+        pub fn new(minify: MinifyOption) -> HTMLMinCodeGen {
+            HTMLMinCodeGen {
+                minify,
+                errors: vec![],
+            }
+        }
+    }
 
-    pub struct HTMLMinCodeGen;
     impl EachCodeGen for HTMLMinCodeGen {}
     impl IfElseCodeGen for HTMLMinCodeGen {}
 
     impl CodeGen for HTMLMinCodeGen {
         fn gen(&mut self, v: Vec<HIR>) -> TokenStream {
-            let dom: DOMFmt = v.into();
-            gen(self, dom.0)
+            match DOMFmt::new(v, self.minify) {
+                Ok(dom) => gen(self, dom.0),
+                Err(e) => {
+                    self.errors.extend(e.iter().map(ToString::to_string));
+                    TokenStream::new()
+                }
+            }
+        }
+
+        fn take_errors(&mut self) -> Vec<String> {
+            std::mem::take(&mut self.errors)
         }
     }
 }
@@ -13,6 +13,9 @@ mod fixed;
 mod fmt;
 mod fn_fmt;
 mod html;
+#[cfg(any(feature = "axum", feature = "actix-web", feature = "hyper"))]
+mod responses;
+mod size_hint;
 mod text;
 pub mod wasm;
 #[cfg(feature = "bytes-buf")]
@@ -40,10 +43,20 @@ pub use self::write_b::WriteBCodeGen;
 
 pub trait CodeGen {
     fn gen(&mut self, v: Vec<HIR>) -> TokenStream;
+
+    /// Html errors found in `gen`, reported by the caller with the template source
+    fn take_errors(&mut self) -> Vec<String> {
+        vec![]
+    }
 }
 
 pub trait EachCodeGen: CodeGen {
-    fn gen_each(&mut self, Each { args, body, expr }: Each) -> TokenStream {
+    fn gen_each(
+        &mut self,
+        Each {
+            args, body, expr, ..
+        }: Each,
+    ) -> TokenStream {
         let body = self.gen(body);
         quote!(for #expr in #args { #body })
     }
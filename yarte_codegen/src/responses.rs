@@ -0,0 +1,90 @@
+use std::path::Path;
+
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+
+use yarte_hir::Struct;
+
+/// Content type of the inner extension, `page.json.hbs`, or of the escape mode
+fn content_type(s: &Struct, text: bool) -> String {
+    let guess = s
+        .path
+        .file_stem()
+        .map(Path::new)
+        .and_then(Path::extension)
+        .and_then(|ext| mime_guess::from_ext(ext.to_str()?).first_raw());
+    match guess {
+        Some(mime) if mime.starts_with("text/") => format!("{}; charset=utf-8", mime),
+        Some(mime) => mime.into(),
+        None if text || s.mode.is_text() => "text/plain; charset=utf-8".into(),
+        None => "text/html; charset=utf-8".into(),
+    }
+}
+
+/// Response implementations of the enabled web frameworks
+pub(crate) fn responses(s: &Struct, parent: &Ident, text: bool, size_hint: usize) -> TokenStream {
+    let content_type = content_type(s, text);
+    let mut tokens = TokenStream::new();
+
+    #[cfg(feature = "axum")]
+    tokens.extend(s.implement_head(
+        quote!(#parent::integrations::axum::response::IntoResponse),
+        &quote! {
+            fn into_response(self) -> #parent::integrations::axum::response::Response {
+                let mut res = #parent::integrations::axum::response::IntoResponse::into_response(
+                    #parent::TemplateBytesTrait::ccall::<#parent::BytesMut>(self, #size_hint),
+                );
+                res.headers_mut().insert(
+                    #parent::integrations::http::header::CONTENT_TYPE,
+                    #parent::integrations::http::HeaderValue::from_static(#content_type),
+                );
+                res
+            }
+        },
+    ));
+
+    #[cfg(feature = "actix-web")]
+    tokens.extend(s.implement_head(
+        quote!(#parent::integrations::actix_web::Responder),
+        &quote! {
+            type Body = #parent::Bytes;
+
+            fn respond_to(
+                self,
+                _req: &#parent::integrations::actix_web::HttpRequest,
+            ) -> #parent::integrations::actix_web::HttpResponse<Self::Body> {
+                let mut res = #parent::integrations::actix_web::HttpResponse::with_body(
+                    #parent::integrations::actix_web::http::StatusCode::OK,
+                    #parent::TemplateBytesTrait::ccall::<#parent::BytesMut>(self, #size_hint),
+                );
+                res.headers_mut().insert(
+                    #parent::integrations::actix_web::http::header::CONTENT_TYPE,
+                    #parent::integrations::actix_web::http::header::HeaderValue::from_static(
+                        #content_type,
+                    ),
+                );
+                res
+            }
+        },
+    ));
+
+    #[cfg(feature = "hyper")]
+    tokens.extend(s.implement_from(
+        quote!(#parent::integrations::http::Response<
+            #parent::integrations::http_body_util::Full<#parent::Bytes>
+        >),
+        &quote! {
+            let mut res = #parent::integrations::http::Response::new(
+                #parent::integrations::http_body_util::Full::new(
+                    #parent::TemplateBytesTrait::ccall::<#parent::BytesMut>(template, #size_hint),
+                ),
+            );
+            res.headers_mut().insert(
+                #parent::integrations::http::header::CONTENT_TYPE,
+                #parent::integrations::http::HeaderValue::from_static(#content_type),
+            );
+            res
+        },
+    ));
+    tokens
+}
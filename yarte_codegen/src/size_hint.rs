@@ -0,0 +1,219 @@
+use syn::{
+    Expr, ExprField, ExprLit, ExprMethodCall, ExprParen, ExprPath, ExprRange, ExprReference, Field,
+    Lit, Member, RangeLimits, Type, TypeArray, TypePath, TypeReference,
+};
+
+use yarte_hir::{Each, IfElse, HIR};
+
+/// Width of an expression of unknown type
+const EXPR_WIDTH: usize = 16;
+/// Iterations of an `each` of unknown length
+const EACH_ITERATIONS: usize = 8;
+/// Typical and maximum width of the primitive types
+///
+/// Floats have no maximum and `char` is escaped as `&#x27;` at most
+const WIDTHS: &[(&str, usize, Option<usize>)] = &[
+    ("u8", 3, Some(3)),
+    ("i8", 3, Some(4)),
+    ("u16", 5, Some(5)),
+    ("i16", 5, Some(6)),
+    ("u32", 10, Some(10)),
+    ("i32", 10, Some(11)),
+    ("u64", 10, Some(20)),
+    ("i64", 10, Some(20)),
+    ("usize", 10, Some(20)),
+    ("isize", 10, Some(20)),
+    ("u128", 10, Some(39)),
+    ("i128", 10, Some(40)),
+    ("f32", 10, None),
+    ("f64", 10, None),
+    ("bool", 5, Some(5)),
+    ("char", 1, Some(6)),
+];
+
+/// Static estimate of the rendered length
+///
+/// Literals are exact, expressions use a typical width of the `self` field type,
+/// `each` blocks multiply its body by the length of literal ranges and arrays
+/// and conditionals take its biggest branch
+pub fn size_hint(nodes: &[HIR], fields: &[Field]) -> usize {
+    nodes.iter().fold(0usize, |acc, x| {
+        let len = match x {
+            HIR::Lit(a) => a.len(),
+            HIR::Local(_) => 0,
+            HIR::Safe(a) | HIR::Expr(a) => expr_width(a, fields),
+            HIR::IfElse(a) => {
+                let IfElse { ifs, if_else, els } = &**a;
+                if_else
+                    .iter()
+                    .map(|(_, block)| block)
+                    .chain(els)
+                    .fold(size_hint(&ifs.1, fields), |len, block| {
+                        len.max(size_hint(block, fields))
+                    })
+            }
+            HIR::Cache(a) => size_hint(&a.body, fields),
+            // Nested applications grow the buffer
+            HIR::Component(_) => 0,
+            HIR::Each(a) => {
+                let Each { args, body, .. } = &**a;
+                size_hint(body, fields)
+                    .saturating_mul(iterations(args, fields).unwrap_or(EACH_ITERATIONS))
+            }
+        };
+        acc.saturating_add(len)
+    })
+}
+
+/// Type of a `self` field expression without references
+pub fn field_type<'a>(expr: &Expr, fields: &'a [Field]) -> Option<&'a Type> {
+    let member = match expr {
+        Expr::Field(ExprField {
+            base,
+            member: Member::Named(member),
+            ..
+        }) => match &**base {
+            Expr::Path(ExprPath { path, .. }) if path.is_ident("self") => member,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    // Field tokens and template tokens can come from different proc macro backends
+    let member = member.to_string();
+    let mut ty = &fields
+        .iter()
+        .find(|x| matches!(&x.ident, Some(x) if *x == member))?
+        .ty;
+    while let Type::Reference(TypeReference { elem, .. }) = ty {
+        ty = elem;
+    }
+    Some(ty)
+}
+
+/// Ident of a path type, `u8` or `String`
+pub fn type_ident(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Path(TypePath { qself: None, path }) => path.get_ident().map(ToString::to_string),
+        _ => None,
+    }
+}
+
+/// Typical and maximum width of a primitive type
+pub fn type_width(ty: &Type) -> Option<(usize, Option<usize>)> {
+    let ident = type_ident(ty)?;
+    WIDTHS
+        .iter()
+        .find(|(x, ..)| *x == ident)
+        .map(|(_, width, max)| (*width, *max))
+}
+
+fn expr_width(expr: &Expr, fields: &[Field]) -> usize {
+    field_type(expr, fields)
+        .and_then(type_width)
+        .map_or(EXPR_WIDTH, |(width, _)| width)
+}
+
+/// Iterated expression of the `each` arguments without the iterator adaptors
+pub fn each_args(mut args: &Expr) -> &Expr {
+    loop {
+        args = match args {
+            Expr::Paren(ExprParen { expr, .. }) | Expr::Reference(ExprReference { expr, .. }) => {
+                expr
+            }
+            Expr::MethodCall(ExprMethodCall {
+                receiver, method, ..
+            }) if method == "__into_citer" || method == "enumerate" => receiver,
+            _ => return args,
+        }
+    }
+}
+
+/// Exact iterations of literal ranges and `[T; N]` fields
+pub fn iterations(args: &Expr, fields: &[Field]) -> Option<usize> {
+    let args = each_args(args);
+    if let Expr::Range(ExprRange {
+        from: Some(from),
+        to: Some(to),
+        limits,
+        ..
+    }) = args
+    {
+        let (from, to) = (int_lit(from)?, int_lit(to)?);
+        let inclusive = matches!(limits, RangeLimits::Closed(_)) as usize;
+        return Some(to.saturating_sub(from).saturating_add(inclusive));
+    }
+    match field_type(args, fields)? {
+        Type::Array(TypeArray { len, .. }) => int_lit(len),
+        _ => None,
+    }
+}
+
+fn int_lit(expr: &Expr) -> Option<usize> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(a), ..
+        }) => a.base10_parse().ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use syn::parse_quote;
+
+    #[test]
+    fn test_size_hint() {
+        let fields: syn::FieldsNamed = parse_quote!({ n: u8, name: &'a str, list: [u16; 4] });
+        let fields: Vec<Field> = fields.named.into_iter().collect();
+        let lit = |a: &str| HIR::Lit(a.into());
+
+        assert_eq!(size_hint(&[lit("Hello, "), lit("World!")], &fields), 13);
+        assert_eq!(
+            size_hint(
+                &[
+                    HIR::Expr(Box::new(parse_quote!(self.n))),
+                    HIR::Safe(Box::new(parse_quote!(self.name))),
+                ],
+                &fields,
+            ),
+            3 + EXPR_WIDTH
+        );
+        assert_eq!(
+            size_hint(
+                &[HIR::IfElse(Box::new(IfElse {
+                    ifs: (parse_quote!(self.n), vec![lit("foo")]),
+                    if_else: vec![(parse_quote!(self.n), vec![lit("foobar")])],
+                    els: Some(vec![]),
+                }))],
+                &fields,
+            ),
+            6
+        );
+
+        let each = |args: Expr| {
+            HIR::Each(Box::new(Each {
+                args,
+                body: vec![lit("<li>")],
+                expr: parse_quote!(_),
+                key: None,
+            }))
+        };
+        assert_eq!(size_hint(&[each(parse_quote!(0..10))], &fields), 40);
+        assert_eq!(size_hint(&[each(parse_quote!(1..=10))], &fields), 40);
+        assert_eq!(size_hint(&[each(parse_quote!(self.list))], &fields), 16);
+        assert_eq!(
+            size_hint(
+                &[each(parse_quote!(
+                    ((&(self.list)).__into_citer().enumerate())
+                ))],
+                &fields
+            ),
+            16
+        );
+        assert_eq!(
+            size_hint(&[each(parse_quote!(self.name))], &fields),
+            4 * EACH_ITERATIONS
+        );
+    }
+}
@@ -19,6 +19,8 @@ impl CodeGen for TextCodeGen {
                 Safe(a) | Expr(a) => quote!(&(#a).fmt(_fmt)?;),
                 Each(a) => self.gen_each(*a),
                 IfElse(a) => self.gen_if_else(*a),
+                Cache(a) => self.gen(a.body),
+                Component(_) => unreachable!("only applications lower components"),
             });
         }
         tokens
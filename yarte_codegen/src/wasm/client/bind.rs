@@ -0,0 +1,157 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse2, AttrStyle, Attribute, Variant};
+
+use yarte_dom::dom::ExprId;
+
+use super::{state::BlackBox, Parent, WASMCodeGen};
+
+/// Two-way binding of a form property, `bind:value="{{ field }}"` and `bind:checked="{{ field }}"`
+///
+/// Each binding generates a hidden message variant that sets the field,
+/// marks its dirty bits when it changes and is sent by the `input` or `change` listener.
+/// The property is updated in render when the field changes, except the
+/// value of a focused input
+pub struct Bind {
+    /// Generated message variant
+    pub variant: Variant,
+    /// Generated message handler
+    pub handler: TokenStream,
+}
+
+impl<'a> WASMCodeGen<'a> {
+    pub(super) fn write_bind(&mut self, id: ExprId, prop: &str, expr: &syn::Expr) {
+        if !matches!(self.cur().id, Parent::Body) {
+            self.errors.push(syn::Error::new_spanned(
+                expr,
+                "`bind:` attributes are only supported outside `each` and `if` blocks",
+            ));
+            return;
+        }
+        let field_error = || {
+            syn::Error::new_spanned(
+                expr,
+                format!("`bind:{}` attribute needs a field expression", prop),
+            )
+        };
+        if !matches!(expr, syn::Expr::Field(_)) {
+            self.errors.push(field_error());
+            return;
+        }
+        let var = match self
+            .solver
+            .expr_inner_var(&id)
+            .iter()
+            .collect::<Vec<_>>()
+            .as_slice()
+        {
+            [var] => **var,
+            _ => {
+                self.errors.push(field_error());
+                return;
+            }
+        };
+
+        let number = self
+            .dirty_number(self.solver.var_ident(&var))
+            .expect("registered field");
+
+        let bb = self.global_bb_ident();
+        // Only marks dirty when the field changes
+        let set = quote! {
+            if std::mem::replace(&mut #expr, __value) != #expr {
+                self.#bb.t_root |= #number;
+            }
+        };
+        let (event, ty, arg, set, update) = match prop {
+            "value" => (
+                "input",
+                quote!(String),
+                quote!(value),
+                quote! {
+                    if let Ok(__value) = __value.parse() {
+                        #set
+                    }
+                },
+                // Doesn't overwrite the value while the user is editing it
+                quote! {
+                    let __value__ = (#expr).to_string();
+                    let __focused__ = yarte_wasm_app::web::window()
+                        .and_then(|x| x.document())
+                        .and_then(|x| x.active_element())
+                        .map_or(false, |x| x.is_same_node(Some(__node__)));
+                    if !__focused__ && __node__.value() != __value__ {
+                        __node__.set_value(&__value__);
+                    }
+                },
+            ),
+            "checked" => (
+                "change",
+                quote!(bool),
+                quote!(checked),
+                set,
+                quote!(__node__.set_checked(#expr);),
+            ),
+            _ => {
+                self.errors.push(syn::Error::new_spanned(
+                    expr,
+                    format!("unknown bind attribute `bind:{}`", prop),
+                ));
+                return;
+            }
+        };
+
+        let n = self.binds.len();
+        let variant = format_ident!("__YarteBind{}", n);
+        let handler = format_ident!("__yarte_bind_{}", n);
+        let msgs = &self
+            .s
+            .msgs
+            .as_ref()
+            .expect("Need define messages for application")
+            .ident;
+        // Message handler path as the variant attribute
+        let mut msg: Variant = parse2(quote!(#variant(#ty))).unwrap();
+        msg.attrs.push(Attribute {
+            pound_token: Default::default(),
+            style: AttrStyle::Outer,
+            bracket_token: Default::default(),
+            path: parse2(quote!(Self::#handler)).unwrap(),
+            tokens: TokenStream::new(),
+        });
+        self.binds.push(Bind {
+            variant: msg,
+            handler: quote! {
+                #[doc(hidden)]
+                #[inline]
+                fn #handler(&mut self, __value: #ty, __addr: &'static yarte_wasm_app::Addr<Self>) {
+                    #set
+                }
+            },
+        });
+
+        self.write_bind_event(event, &parse2(quote!(#msgs::#variant(#arg))).unwrap());
+
+        let name = self.current_node_ident(0);
+        let cur = self.cur_mut();
+        cur.buff_render.push((
+            [var].iter().copied().collect(),
+            quote! {
+                {
+                    let __node__ = yarte_wasm_app::JsCast::unchecked_ref::<
+                        yarte_wasm_app::web::HtmlInputElement
+                    >(&self.#bb.#name);
+                    #update
+                }
+            },
+        ));
+        if !cur.path_nodes.iter().any(|(x, _)| *x == name) {
+            cur.path_nodes.push((name.clone(), cur.steps.clone()));
+            cur.black_box.push(BlackBox {
+                doc: "Yarte Node element".into(),
+                name,
+                ty: parse2(quote!(yarte_wasm_app::web::Element)).unwrap(),
+            });
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::parse2;
+
+use yarte_dom::dom::{Component, ExprId};
+
+use super::{
+    state::BlackBox,
+    utils::{get_self_id, get_t_root_number, is_state},
+    Parent, WASMCodeGen,
+};
+
+/// Nested application, `{{> @Path field = expr, forward = path }}`
+///
+/// The application has `mount` attribute, is the only child of its mount element
+/// and hydrates its own subtree with the address of the parent, `forward: fn(&Child::Message) -> Option<Parent::Message>`
+/// maps child messages to the parent.
+/// Each field is set by a hidden setter of the child when the variables of its
+/// expression change.
+/// The mount element and the state script of a component have fixed ids,
+/// so each component type is mounted once per application
+impl<'a> WASMCodeGen<'a> {
+    pub(super) fn write_component(
+        &mut self,
+        id: ExprId,
+        Component {
+            path,
+            props,
+            forward,
+        }: Component,
+    ) {
+        if !matches!(self.cur().id, Parent::Body) {
+            self.errors.push(syn::Error::new_spanned(
+                &path,
+                "components are only supported outside `each` and `if` blocks",
+            ));
+            return;
+        }
+        if !self.components.insert(quote!(#path).to_string()) {
+            self.errors.push(syn::Error::new_spanned(
+                &path,
+                "a component type can only be mounted once per application, \
+                 its mount element and state script have fixed ids",
+            ));
+            return;
+        }
+
+        let name = format_ident!("__component__{}", id);
+        let bb = self.global_bb_ident();
+        let forward = match forward {
+            Some(forward) => quote!(#forward),
+            None => quote!(|_| None),
+        };
+        let cur = self.cur_mut();
+        cur.buff_hydrate.push(quote! {
+            self.#bb.#name = Some(yarte_wasm_app::Addr::<#path>::run_child(__addr, #forward));
+        });
+        for (field, expr, vars) in props {
+            let setter = format_ident!("__set_{}", field);
+            let vars = vars.into_iter().filter(|x| self.solver.is_var(x)).collect();
+            self.cur_mut().buff_render.push((
+                vars,
+                quote! {
+                    if let Some(__component__) = self.#bb.#name {
+                        let __value__ = (#expr).clone();
+                        __component__.update(move |__app__| __app__.#setter(__value__));
+                    }
+                },
+            ));
+        }
+        self.cur_mut().black_box.push(BlackBox {
+            doc: "Yarte nested application".into(),
+            name,
+            ty: parse2(quote!(Option<&'static yarte_wasm_app::Addr<#path>>)).unwrap(),
+        });
+    }
+
+    /// Setters of the state fields used by the parent applications
+    pub(super) fn setters(&self) -> Vec<TokenStream> {
+        let bb = self.global_bb_ident();
+        self.s
+            .fields
+            .iter()
+            .filter(|x| is_state(x))
+            .map(|x| {
+                let field = x.ident.as_ref().expect("Named fields");
+                let ty = &x.ty;
+                let setter = format_ident!("__set_{}", field);
+                let dirty = self.dirty_number(&quote!(self.#field).to_string());
+                let dirty = dirty.map(|number| quote!(self.#bb.t_root |= #number;));
+                quote! {
+                    #[doc(hidden)]
+                    #[inline]
+                    pub fn #setter(&mut self, __value: #ty) {
+                        self.#field = __value;
+                        #dirty
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Dirty bits of the field and its members, `None` if isn't in the template
+    pub(super) fn dirty_number(&self, ident: &str) -> Option<TokenStream> {
+        let group = self.solver.group(&get_self_id());
+        let mut positions = group
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| {
+                let x = self.solver.var_ident(x);
+                x == ident || x.starts_with(&format!("{} .", ident))
+            })
+            .map(|(i, _)| i)
+            .peekable();
+        positions.peek()?;
+        Some(get_t_root_number(positions, group.len(), self.s.ident))
+    }
+}
@@ -71,8 +71,20 @@ impl<'a, 'b> ComponentBuilder<'a, 'b> {
                 }
                 _ => todo!("no node element"),
             }
+        } else if doc
+            .iter()
+            .all(|x| matches!(x, Node::Elem(Element::Node { .. })))
+        {
+            // Multiple roots are cloned from a container
+            let id = self.get_ident();
+            self.tokens.extend(quote! {
+                let #id = doc.create_element("div").unwrap_throw();
+            });
+            self.step(doc.into_iter(), &id);
+
+            self.tokens.extend(quote!(#id))
         } else {
-            todo!("len +1")
+            unreachable!("roots are checked at each")
         }
 
         let tokens = self.tokens.to_string();
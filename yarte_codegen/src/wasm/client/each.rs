@@ -1,10 +1,10 @@
-use std::{collections::HashSet, iter};
+use std::collections::HashSet;
 
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
 use syn::{parse2, Expr, Ident};
 
-use yarte_dom::dom::{Each, ExprId, VarId};
+use yarte_dom::dom::{Each, Element, ExprId, Node, VarId};
 
 use super::{
     component::get_component,
@@ -23,11 +23,20 @@ impl<'a> WASMCodeGen<'a> {
             body,
             expr,
             var,
+            key: each_key,
         }: Each,
         fragment: bool,
         last: bool,
         insert_point: &[InsertPath],
     ) {
+        if !all_elements(&body) {
+            self.errors.push(syn::Error::new_spanned(
+                &args,
+                "roots of each body must be elements in wasm applications",
+            ));
+            return;
+        }
+
         // Get current state
         let current_bb = self.current_bb();
 
@@ -58,6 +67,15 @@ impl<'a> WASMCodeGen<'a> {
             ..Default::default()
         });
 
+        // Root elements of each row
+        let roots: Vec<Ident> = (0..body
+            .iter()
+            .filter(|x| matches!(x, Node::Elem(Element::Node { .. })))
+            .count())
+            .map(get_field_root_n_ident)
+            .collect();
+        self.each_roots.insert(id, roots.len());
+
         // TODO: component build
         let component = get_component(id, body.iter(), self);
         self.cur_mut().component = Some(component);
@@ -79,45 +97,58 @@ impl<'a> WASMCodeGen<'a> {
         let (base, _) = self.bb_t_root(var_id.into_iter());
         curr.add_t_root(base);
 
-        // TODO: Multiple root
-        curr.black_box.push(BlackBox {
-            doc: "root dom element".to_string(),
-            name: get_field_root_ident(),
-            ty: parse2(quote!(yarte_wasm_app::web::Element)).unwrap(),
-        });
+        for root in &roots {
+            curr.black_box.push(BlackBox {
+                doc: "root dom element".to_string(),
+                name: root.clone(),
+                ty: parse2(quote!(yarte_wasm_app::web::Element)).unwrap(),
+            });
+        }
+        if each_key.is_some() {
+            curr.black_box.push(BlackBox {
+                doc: "Each key".to_string(),
+                name: get_field_key_ident(),
+                ty: parse2(quote!(Box<dyn std::any::Any>)).unwrap(),
+            });
+            curr.black_box.push(BlackBox {
+                doc: "Each key hash".to_string(),
+                name: get_field_key_hash_ident(),
+                ty: parse2(quote!(u64)).unwrap(),
+            });
+            if !self.keyed {
+                self.keyed = true;
+                self.helpers.extend(get_keyed_stable());
+                self.helpers.extend(get_key_eq());
+            }
+        }
 
         // Write component
         self.helpers.extend(curr.get_black_box(&component_ty));
         self.helpers
-            .extend(get_drop(&component_ty, iter::once(get_field_root_ident())));
+            .extend(get_drop(&component_ty, roots.iter().cloned()));
 
-        // TODO
+        // Paths start in the first root
         for (_, path) in curr
             .path_nodes
             .iter_mut()
             .chain(curr.path_events.iter_mut())
         {
-            if path.starts_with(&[Step::FirstChild, Step::FirstChild]) {
-                // Remove marker
-                path.remove(0);
-            } else {
-                todo!("multi node expressions");
-            }
+            debug_assert_eq!(path.first(), Some(&Step::FirstChild), "element roots");
+            path.remove(0);
         }
 
         let current_bb = &curr.current_bb;
 
-        // TODO: remove self
-        let build_args: TokenStream = quote!(#args)
-            .to_string()
-            .replace("self .", "")
-            .parse()
-            .unwrap();
-        let build = Self::build_each(
+        // State fields are locals at build
+        let build_args = strip_self(&args);
+        let build_key = each_key.as_ref().map(strip_self);
+        let build = self.build_each(
             &curr,
             build_args,
+            build_key,
             &expr,
             &component_ty,
+            &roots,
             &insert_point,
             &vdom,
             &table,
@@ -132,36 +163,59 @@ impl<'a> WASMCodeGen<'a> {
             Parent::Body | Parent::Head => quote!(#current_bb.#table_dom),
         };
         let (new, cached) = self.new_each(
+            id,
             &curr,
             curr.component.as_ref().expect("some component"),
             &component_ty,
+            &roots,
             last,
             insert_point,
             &vdom,
             quote!(#current_bb.#table_dom),
             Some(parent),
+            each_key.as_ref(),
         );
-        let render = self.render_each(
-            &curr,
-            new,
-            cached,
-            &args,
-            &expr,
-            fragment,
-            &vdom,
-            quote!(#current_bb.#table),
-            quote!(#current_bb.#table_dom),
-            key,
-        );
+        let render = if let Some(each_key) = &each_key {
+            self.render_each_keyed(
+                &curr,
+                new,
+                cached,
+                &args,
+                &expr,
+                fragment,
+                &vdom,
+                quote!(#current_bb.#table),
+                quote!(#current_bb.#table_dom),
+                &roots,
+                each_key,
+                key,
+            )
+        } else {
+            self.render_each(
+                &curr,
+                new,
+                cached,
+                &args,
+                &expr,
+                fragment,
+                &vdom,
+                quote!(#current_bb.#table),
+                quote!(#current_bb.#table_dom),
+                key,
+            )
+        };
         let (new, cached) = self.new_each(
+            id,
             &curr,
             curr.component.as_ref().expect("some component"),
             &component_ty,
+            &roots,
             last,
             &insert_point,
             &vdom,
             quote!(#table_dom),
             None,
+            each_key.as_ref(),
         );
 
         let mut vars = self.solver.expr_inner_var(&id).clone();
@@ -224,58 +278,82 @@ impl<'a> WASMCodeGen<'a> {
 
     fn new_each(
         &self,
+        id: ExprId,
         curr: &State,
         component: &Ident,
         component_ty: &Ident,
+        roots: &[Ident],
         last: bool,
         insert_point: &[InsertPath],
         vdom: &Ident,
         table_dom: TokenStream,
         parent: Option<TokenStream>,
+        key: Option<&Expr>,
     ) -> (TokenStream, Option<TokenStream>) {
         let bb = self.global_bb_ident();
         let tmp = format_ident!("__tmp__");
-        let froot = get_field_root_ident();
+        let fkey = get_field_key_ident();
         let steps = get_steps(
             curr.path_nodes.iter().chain(curr.path_events.iter()),
             quote!(#tmp),
         );
         let fields = curr.get_black_box_fields(&tmp, false);
 
-        let (insert_point, cached) = if last {
-            (
-                quote!(#table_dom.append_child(&#vdom.#froot).unwrap_throw();),
-                None,
-            )
+        let cached = if last {
+            None
         } else {
             let len: Len = insert_point.into();
             let base = len.base as u32 + 1;
             let mut tokens = quote!(#base);
             for i in &len.expr {
                 let ident = get_table_ident(*i);
-                if let Some(parent) = &parent {
-                    tokens.extend(quote!(+ #parent.#ident.len() as u32))
+                let len = if let Some(parent) = &parent {
+                    quote!(#parent.#ident.len() as u32)
                 } else {
-                    tokens.extend(quote!(+ #ident.len() as u32))
-                }
+                    quote!(#ident.len() as u32)
+                };
+                let len = self.roots_len(*i, len);
+                tokens.extend(quote!(+ #len))
             }
 
-            (
-                quote!(#table_dom.insert_before(&#vdom.#froot, __cached__.as_ref()).unwrap_throw();),
-                Some(if parent.is_some() {
-                    quote!(#table_dom.children().item(#tokens + __dom_len__ as u32).map(yarte_wasm_app::JsCast::unchecked_into::<yarte_wasm_app::web::Node>))
-                } else {
-                    quote!(#table_dom.children().item(#tokens).map(yarte_wasm_app::JsCast::unchecked_into::<yarte_wasm_app::web::Node>))
-                }),
-            )
+            Some(if parent.is_some() {
+                let dom_len = self.roots_len(id, quote!(__dom_len__ as u32));
+                quote!(#table_dom.children().item(#tokens + #dom_len).map(yarte_wasm_app::JsCast::unchecked_into::<yarte_wasm_app::web::Node>))
+            } else {
+                quote!(#table_dom.children().item(#tokens).map(yarte_wasm_app::JsCast::unchecked_into::<yarte_wasm_app::web::Node>))
+            })
         };
+        // Keyed render inserts before the next row
+        let keyed = key.is_some() && parent.is_some();
+        let insert_point: TokenStream = roots
+            .iter()
+            .map(|root| {
+                if keyed {
+                    quote!(#table_dom.insert_before(&#vdom.#root, __next__.as_ref()).unwrap_throw();)
+                } else if last {
+                    quote!(#table_dom.append_child(&#vdom.#root).unwrap_throw();)
+                } else {
+                    quote!(#table_dom.insert_before(&#vdom.#root, __cached__.as_ref()).unwrap_throw();)
+                }
+            })
+            .collect();
 
         let build = &curr.buff_new;
+        let key = key.map(get_key_fields);
+        let first_root = if roots.len() == 1 {
+            quote!()
+        } else {
+            quote!(let #tmp = #tmp.first_element_child().unwrap_throw();)
+        };
+        let next_roots = get_next_roots(roots, &tmp);
         (
             quote! {
+                 #key
                  let #tmp = yarte_wasm_app::JsCast::unchecked_into::<yarte_wasm_app::web::Element>(self.#bb.#component
                      .clone_node_with_deep(true)
                      .unwrap_throw());
+                 #first_root
+                 #next_roots
                  #steps
                  #(#build)*
                  let #vdom = #component_ty { #fields };
@@ -288,16 +366,19 @@ impl<'a> WASMCodeGen<'a> {
 
     #[inline]
     fn build_each(
+        &self,
         curr: &State,
         args: TokenStream,
+        key: Option<TokenStream>,
         expr: &Expr,
         component_ty: &Ident,
+        roots: &[Ident],
         insert_point: &[InsertPath],
         vdom: &Ident,
         table: &Ident,
         table_dom: &Ident,
     ) -> TokenStream {
-        let froot = get_field_root_ident();
+        let last_root = roots.last().expect("one root");
         let steps = get_steps(curr.path_nodes.iter(), quote!(#vdom));
         let fields = curr.get_black_box_fields(vdom, true);
         let build = &curr.buff_build;
@@ -308,16 +389,22 @@ impl<'a> WASMCodeGen<'a> {
             let mut tokens = quote!(#base);
             for i in &len.expr {
                 let ident = get_table_ident(*i);
-                tokens.extend(quote!(+ #ident.len() as u32))
+                let len = self.roots_len(*i, quote!(#ident.len() as u32));
+                tokens.extend(quote!(+ #len))
             }
 
             quote!(#table_dom.children().item(#tokens).unwrap_throw())
         };
 
+        let next_roots = get_next_roots(roots, vdom);
+        let key = key.map(|key| get_key_fields(&parse2(key).unwrap()));
+
         quote! {
             let mut #table: Vec<#component_ty> = vec![];
             for #expr in #args {
-                let #vdom = #table.last().map(|__x__| __x__.#froot.next_element_sibling().unwrap_throw()).unwrap_or_else(|| #insert_point);
+                #key
+                let #vdom = #table.last().map(|__x__| __x__.#last_root.next_element_sibling().unwrap_throw()).unwrap_or_else(|| #insert_point);
+                #next_roots
                 #steps
                 #(#build)*
                 #table.push(#component_ty { #fields });
@@ -427,4 +514,148 @@ impl<'a> WASMCodeGen<'a> {
             }
         }
     }
+
+    /// Reuse rows by key, new rows are created and stable rows aren't moved
+    #[inline]
+    fn render_each_keyed(
+        &self,
+        curr: &State,
+        new: TokenStream,
+        cached: Option<TokenStream>,
+        args: &Expr,
+        expr: &Expr,
+        fragment: bool,
+        vdom: &Ident,
+        table: TokenStream,
+        table_dom: TokenStream,
+        roots: &[Ident],
+        key: &Expr,
+        each_base: VarId,
+    ) -> TokenStream {
+        let froot = get_field_root_ident();
+        let fkey = get_field_key_ident();
+        let fhash = get_field_key_hash_ident();
+        let stable = get_keyed_stable_ident();
+        let eq = get_key_eq_ident();
+        let value = get_key_value(key);
+
+        let render = if curr.buff_render.is_empty() {
+            quote!()
+        } else {
+            let parents = curr
+                .get_render_hash()
+                .into_iter()
+                .any(|(i, _)| i.iter().any(|j| self.solver.var_base(j) != each_base));
+
+            let render = self.render(curr);
+            assert!(!render.is_empty());
+            if parents {
+                quote! {
+                    #render
+                    #vdom.t_root = yarte_wasm_app::YNumber::zero();
+                }
+            } else {
+                quote! {
+                    if yarte_wasm_app::YNumber::neq_zero(#vdom.t_root) {
+                        #render
+                        #vdom.t_root = yarte_wasm_app::YNumber::zero();
+                    }
+                }
+            }
+        };
+        let reused = if render.is_empty() {
+            quote!(#vdom)
+        } else {
+            quote!(mut #vdom)
+        };
+        let (dom_len, next) = if let Some(cached) = cached {
+            (quote!(let __dom_len__ = #table.len();), cached)
+        } else {
+            (quote!(), quote!(None))
+        };
+        let body = quote! {
+            let mut __next__: Option<yarte_wasm_app::web::Node> = #next;
+            // Old rows by key hash, a row is reused once and repeated keys build new rows
+            let mut __old_keys__: std::collections::HashMap<u64, Vec<usize>> =
+                std::collections::HashMap::new();
+            for (__i__, __x__) in #table.iter().enumerate() {
+                __old_keys__.entry(__x__.#fhash).or_default().push(__i__);
+            }
+            let mut __old__: Vec<Option<_>> = #table.drain(..).map(Some).collect();
+            let __sources__: Vec<usize> = __items__
+                .iter()
+                .copied()
+                .map(|#expr| {
+                    #value
+                    let __rows__ = __old_keys__.get_mut(&#fhash)?;
+                    let __j__ = __rows__.iter().position(|__j__| {
+                        __old__[*__j__]
+                            .as_ref()
+                            .map_or(false, |__x__| #eq(&*__x__.#fkey, &__key_value__))
+                    })?;
+                    Some(__rows__.remove(__j__))
+                })
+                .map(|__j__| __j__.unwrap_or(usize::MAX))
+                .collect();
+            let __stable__ = #stable(&__sources__);
+            for (__i__, #expr) in __items__.into_iter().enumerate().rev() {
+                let #vdom = if let Some(#reused) = __old__
+                    .get_mut(__sources__[__i__])
+                    .and_then(Option::take)
+                {
+                    #render
+                    if !__stable__[__i__] {
+                        #(#table_dom.insert_before(&#vdom.#roots, __next__.as_ref()).unwrap_throw();)*
+                    }
+                    #vdom
+                } else {
+                    #new
+                };
+                __next__ = Some(yarte_wasm_app::JsCast::unchecked_into::<yarte_wasm_app::web::Node>(#vdom.#froot.clone()));
+                #table.push(#vdom);
+            }
+            #table.reverse();
+        };
+
+        if fragment {
+            quote! {
+                #dom_len
+                let __items__: Vec<_> = #args.collect();
+                #body
+            }
+        } else {
+            quote! {
+                #dom_len
+                let __items__: Vec<_> = #args.collect();
+                if __items__.is_empty() {
+                    #table_dom.set_text_content(None);
+                    #table.clear()
+                } else { #body }
+            }
+        }
+    }
+}
+
+impl<'a> WASMCodeGen<'a> {
+    /// Number of elements in a table of each rows
+    fn roots_len(&self, id: ExprId, len: TokenStream) -> TokenStream {
+        match self.each_roots.get(&id).copied().unwrap_or(1) {
+            1 => len,
+            n => {
+                let n = n as u32;
+                quote!(#len * #n)
+            }
+        }
+    }
+}
+
+/// Siblings of the first root
+fn get_next_roots(roots: &[Ident], first: &Ident) -> TokenStream {
+    let mut tokens = TokenStream::new();
+    let mut prev = first;
+    for root in &roots[1..] {
+        tokens.extend(quote!(let #root = #prev.next_element_sibling().unwrap_throw();));
+        prev = root;
+    }
+    tokens
 }
@@ -1,56 +1,275 @@
+use std::collections::BTreeSet;
+
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
-use syn::{parse2, punctuated::Punctuated, ExprCall, ExprPath, ExprStruct, Ident, Token};
+use syn::{
+    parse2, punctuated::Punctuated, visit::Visit, ExprCall, ExprPath, ExprStruct, FieldValue, Token,
+};
 
-use yarte_dom::dom::ExprId;
+use yarte_dom::dom::{ExprId, VarId};
+use yarte_hir::EVENT_ARGS;
 
 use super::{utils::get_vdom_ident, BlackBox, Parent, WASMCodeGen};
 
-// TODO: attribute prevent default, event and event type
-#[allow(unused_variables)]
-fn get_closure(msg: &syn::Expr) -> (TokenStream, TokenStream) {
+/// Event attribute modifiers, `onclick.stop.once`
+#[derive(Default)]
+struct Modifiers {
+    prevent: bool,
+    stop: bool,
+    capture: bool,
+    passive: bool,
+    once: bool,
+}
+
+impl Modifiers {
+    /// Default action is prevented unless `.passive` or `.default` are given
+    fn new<'a, I: Iterator<Item = &'a str>>(modifiers: I) -> Result<Modifiers, String> {
+        let mut m = Modifiers::default();
+        let mut prevent = false;
+        let mut default = false;
+        for modifier in modifiers {
+            match modifier {
+                "prevent" => prevent = true,
+                "default" => default = true,
+                "stop" => m.stop = true,
+                "capture" => m.capture = true,
+                "passive" => m.passive = true,
+                "once" => m.once = true,
+                _ => return Err(format!("unknown event modifier `{}`", modifier)),
+            }
+        }
+        if prevent && m.passive {
+            return Err("passive event listeners can't prevent default".into());
+        }
+        if prevent && default {
+            return Err("`prevent` and `default` event modifiers are exclusive".into());
+        }
+        m.prevent = !(m.passive || default);
+
+        Ok(m)
+    }
+
+    fn add_listener(&self, node: TokenStream, event: &str, closure: TokenStream) -> TokenStream {
+        let Modifiers {
+            capture,
+            passive,
+            once,
+            ..
+        } = self;
+        if *capture || *passive || *once {
+            quote! {
+                #node
+                    .add_event_listener_with_callback_and_add_event_listener_options(
+                        #event,
+                        yarte_wasm_app::JsCast::unchecked_ref(#closure),
+                        yarte_wasm_app::web::AddEventListenerOptions::new()
+                            .capture(#capture)
+                            .passive(#passive)
+                            .once(#once),
+                    )
+                .unwrap_throw();
+            }
+        } else {
+            quote! {
+                #node
+                    .add_event_listener_with_callback(#event, yarte_wasm_app::JsCast::unchecked_ref(#closure))
+                .unwrap_throw();
+            }
+        }
+    }
+
+    fn remove_listener(&self, node: TokenStream, event: &str, closure: TokenStream) -> TokenStream {
+        if self.capture {
+            quote! {
+                #node
+                .remove_event_listener_with_callback_and_bool(
+                    #event,
+                    yarte_wasm_app::JsCast::unchecked_ref(#closure),
+                    true,
+                )
+                .unwrap_throw();
+            }
+        } else {
+            quote! {
+                #node
+                .remove_event_listener_with_callback(
+                    #event,
+                    yarte_wasm_app::JsCast::unchecked_ref(#closure),
+                )
+                .unwrap_throw();
+            }
+        }
+    }
+}
+
+/// Event values used in an expression
+#[derive(Default)]
+struct EventArgs(Vec<&'static str>);
+
+impl<'a> Visit<'a> for EventArgs {
+    fn visit_expr_path(&mut self, i: &'a ExprPath) {
+        if let Some(ident) = i.path.get_ident() {
+            if let Some(arg) = EVENT_ARGS.iter().find(|x| ident == *x) {
+                if !self.0.contains(arg) {
+                    self.0.push(arg);
+                }
+            }
+        }
+    }
+}
+
+fn event_args(expr: &syn::Expr) -> EventArgs {
+    let mut args = EventArgs::default();
+    args.visit_expr(expr);
+    args
+}
+
+impl EventArgs {
+    fn into_tokens(self) -> TokenStream {
+        let mut tokens = TokenStream::new();
+        for arg in self.0 {
+            let ident = format_ident!("{}", arg);
+            let value = match arg {
+                // Argument of the closure
+                "__event" => continue,
+                "value" => quote! {
+                    yarte_wasm_app::JsCast::unchecked_ref::<yarte_wasm_app::web::HtmlInputElement>(
+                        &__event.target().unwrap_throw(),
+                    )
+                    .value()
+                },
+                "checked" => quote! {
+                    yarte_wasm_app::JsCast::unchecked_ref::<yarte_wasm_app::web::HtmlInputElement>(
+                        &__event.target().unwrap_throw(),
+                    )
+                    .checked()
+                },
+                "key" => quote! {
+                    yarte_wasm_app::JsCast::unchecked_ref::<yarte_wasm_app::web::KeyboardEvent>(&__event).key()
+                },
+                "key_code" => quote! {
+                    yarte_wasm_app::JsCast::unchecked_ref::<yarte_wasm_app::web::KeyboardEvent>(&__event).key_code()
+                },
+                _ => unreachable!(),
+            };
+            tokens.extend(quote!(let #ident = #value;));
+        }
+
+        tokens
+    }
+}
+
+/// Clone message arguments out of the closure, event values are read inside
+fn clone_arg(count: usize, arg: &syn::Expr, cloned: &mut TokenStream) -> syn::Expr {
+    if event_args(arg).0.is_empty() {
+        let ident = format_ident!("__cloned__{}", count);
+        cloned.extend(quote!(let #ident = (#arg).clone();));
+        parse2(quote!(#ident)).unwrap()
+    } else {
+        arg.clone()
+    }
+}
+
+fn get_closure(msg: &syn::Expr, modifiers: &Modifiers) -> syn::Result<(TokenStream, TokenStream)> {
     use syn::Expr::*;
     let (msg, cloned) = match msg {
-        Path(ExprPath { attrs, qself, path }) => (quote!(#msg), quote!()),
-        Call(ExprCall {
-            attrs, func, args, ..
-        }) => {
-            let mut new: Punctuated<Ident, Token![,]> = Punctuated::new();
+        Path(_) => (quote!(#msg), quote!()),
+        Call(ExprCall { func, args, .. }) => {
+            let mut new: Punctuated<syn::Expr, Token![,]> = Punctuated::new();
             let mut cloned = TokenStream::new();
             for (count, arg) in args.iter().enumerate() {
-                let ident = format_ident!("__cloned__{}", count);
-                cloned.extend(quote!(let #ident = (#arg).clone();));
-                new.push(ident);
+                new.push(clone_arg(count, arg, &mut cloned));
             }
             (quote!(#func(#new)), cloned)
         }
         Struct(ExprStruct {
-            attrs,
-            path,
-            fields,
-            dot2_token,
-            rest,
-            ..
-        }) => todo!("message struct"),
-        _ => panic!("no valid expression at `on` attribute"),
+            path, fields, rest, ..
+        }) => {
+            if let Some(rest) = rest {
+                return Err(syn::Error::new_spanned(
+                    rest,
+                    "no valid struct update syntax at `on` attribute",
+                ));
+            }
+            let mut new: Punctuated<FieldValue, Token![,]> = Punctuated::new();
+            let mut cloned = TokenStream::new();
+            for (count, field) in fields.iter().enumerate() {
+                let mut field = field.clone();
+                field.expr = clone_arg(count, &field.expr, &mut cloned);
+                field.colon_token = Some(<Token![:]>::default());
+                new.push(field);
+            }
+            (quote!(#path { #new }), cloned)
+        }
+        _ => {
+            return Err(syn::Error::new_spanned(
+                msg,
+                "no valid expression at `on` attribute, use a path, call or struct message",
+            ))
+        }
     };
-    (
+    let prevent = if modifiers.prevent {
+        quote!(__event.prevent_default();)
+    } else {
+        quote!()
+    };
+    let stop = if modifiers.stop {
+        quote!(__event.stop_propagation();)
+    } else {
+        quote!()
+    };
+    let args = event_args(&syn::parse2(msg.clone()).unwrap()).into_tokens();
+    Ok((
         quote! {
             Closure::wrap(Box::new(move |__event: yarte_wasm_app::web::Event| {
-                    __event.prevent_default();
+                    #prevent
+                    #stop
+                    #args
                     __addr.send(#msg);
                 }) as Box<dyn Fn(yarte_wasm_app::web::Event)>)
         },
         cloned,
-    )
+    ))
 }
 
 impl<'a> WASMCodeGen<'a> {
     pub(super) fn write_event(&mut self, id: ExprId, event: &str, msg: &syn::Expr) {
-        let name = self.current_node_ident(0);
         assert_eq!(&event[..2], "on");
-        let event = &event[2..];
-        let vars = self.solver.expr_inner_var(&id);
+        let mut event = event[2..].split('.');
+        let modifiers = match Modifiers::new(event.clone().skip(1)) {
+            Ok(modifiers) => modifiers,
+            Err(message) => {
+                self.errors.push(syn::Error::new_spanned(msg, message));
+                return;
+            }
+        };
+        let event = event.next().expect("event name");
+        let vars = self.solver.expr_inner_var(&id).clone();
+        self.add_listener(event, &modifiers, vars, msg);
+    }
+
+    /// Listener of a `bind:` attribute, default action isn't prevented
+    pub(super) fn write_bind_event(&mut self, event: &str, msg: &syn::Expr) {
+        self.add_listener(event, &Modifiers::default(), BTreeSet::new(), msg);
+    }
+
+    fn add_listener(
+        &mut self,
+        event: &str,
+        modifiers: &Modifiers,
+        vars: BTreeSet<VarId>,
+        msg: &syn::Expr,
+    ) {
+        // Make closure expression
+        let (closure_expr, clones) = match get_closure(msg, modifiers) {
+            Ok(closure) => closure,
+            Err(e) => {
+                self.errors.push(e);
+                return;
+            }
+        };
+        let name = self.current_node_ident(0);
+        self.register_tag(&name);
 
         let (forget, dom) = match self.cur().id {
             Parent::Body => {
@@ -61,27 +280,45 @@ impl<'a> WASMCodeGen<'a> {
                 let ident = get_vdom_ident(i);
                 (false, quote!(#ident))
             }
-            Parent::Head => todo!(),
+            Parent::Head => {
+                if !vars.is_empty() {
+                    self.errors.push(syn::Error::new_spanned(
+                        msg,
+                        "event messages in head can't use state variables",
+                    ));
+                    return;
+                }
+                (true, quote!())
+            }
         };
 
-        // Make closure expression
-        let (closure_expr, clones) = get_closure(msg);
-
         if forget {
+            let add_hydrate =
+                modifiers.add_listener(quote!(#name), event, quote!(__closure__.as_ref()));
             let cur = self.cur_mut();
             cur.buff_hydrate.push(quote! {
                 #clones
                 let __cloned__ = __addr.clone();
                 let __closure__ = #closure_expr;
-                #name
-                    .add_event_listener_with_callback(#event, yarte_wasm_app::JsCast::unchecked_ref(__closure__.as_ref()))
-                .unwrap_throw();
+                #add_hydrate
                 __closure__.forget();
             });
             cur.path_events.push((name, cur.steps.clone()));
         } else {
             let closure = format_ident!("__closure__{}", self.count);
             self.count += 1;
+            let add_new = modifiers.add_listener(
+                quote!(#name),
+                event,
+                quote!(#closure.as_ref().unwrap().as_ref()),
+            );
+            let add_hydrate =
+                modifiers.add_listener(quote!(#dom.#name), event, quote!(__closure__.as_ref()));
+            let remove = modifiers.remove_listener(
+                quote!(#dom.#name),
+                event,
+                quote!(#dom.#closure.as_ref().unwrap_throw().as_ref()),
+            );
             let current = self.stack.last_mut();
             current.black_box.push(BlackBox {
                 doc: "".to_string(),
@@ -89,36 +326,23 @@ impl<'a> WASMCodeGen<'a> {
                 ty: parse2(quote!(Option<Closure<dyn Fn(yarte_wasm_app::web::Event)>>)).unwrap(),
             });
             current.buff_new.push(quote! {
-                    #clones
-                    let #closure = Some(#closure_expr);
-                    #name
-                        .add_event_listener_with_callback(#event, yarte_wasm_app::JsCast::unchecked_ref(#closure.as_ref().unwrap().as_ref()))
-                    .unwrap_throw();
-                });
+                #clones
+                let #closure = Some(#closure_expr);
+                #add_new
+            });
             current
                 .path_events
                 .push((name.clone(), current.steps.clone()));
             current.buff_hydrate.push(quote! {
-                    #clones
-                    let __closure__ = #closure_expr;
-                    #dom.#name
-                        .add_event_listener_with_callback(#event, yarte_wasm_app::JsCast::unchecked_ref(__closure__.as_ref()))
-                    .unwrap_throw();
-                    #dom.#closure.replace(__closure__);
-                });
+                #clones
+                let __closure__ = #closure_expr;
+                #add_hydrate
+                #dom.#closure.replace(__closure__);
+            });
             current.buff_render.push((
-                vars.clone(),
+                vars,
                 quote! {
-                    #dom.#name
-                    .remove_event_listener_with_callback(
-                        #event,
-                        yarte_wasm_app::JsCast::unchecked_ref(#dom
-                            .#closure
-                            .as_ref()
-                            .unwrap_throw()
-                            .as_ref()),
-                    )
-                    .unwrap_throw();
+                    #remove
                     #clones
                     #dom.#closure.replace(#closure_expr);
                 },
@@ -1,10 +1,10 @@
-use std::collections::BTreeSet;
+use std::{collections::BTreeSet, mem};
 
 use proc_macro2::TokenStream;
 use quote::quote;
-use syn::{punctuated::Punctuated, Expr, Token};
+use syn::{parse2, punctuated::Punctuated, Expr, Token};
 
-use yarte_dom::dom::{Document, Element, Expression, Node, VarId};
+use yarte_dom::dom::{Document, Each, Element, Expression, IfBlock, IfElse, Node, VarId};
 
 use crate::wasm::client::solver::Solver;
 
@@ -15,8 +15,22 @@ pub fn get_leaf_text(children: Document, solver: &Solver) -> (BTreeSet<VarId>, T
 struct LeafTextBuilder<'a> {
     solver: &'a Solver,
     buff: BTreeSet<VarId>,
-    buff_expr: String,
-    buff_args: Punctuated<Expr, Token![,]>,
+    /// Variables declared inside the text
+    locals: BTreeSet<VarId>,
+}
+
+/// Text between locals
+#[derive(Default)]
+struct Segment {
+    expr: String,
+    args: Punctuated<Expr, Token![,]>,
+}
+
+impl Segment {
+    fn into_format(self) -> TokenStream {
+        let Segment { expr, args } = self;
+        quote!(format!(#expr, #args))
+    }
 }
 
 // TODO: #[str] alone expression for no reallocate string
@@ -25,39 +39,121 @@ impl<'a> LeafTextBuilder<'a> {
         LeafTextBuilder {
             solver,
             buff: Default::default(),
-            buff_expr: Default::default(),
-            buff_args: Default::default(),
+            locals: Default::default(),
         }
     }
 
     fn build(mut self, children: Document) -> (BTreeSet<VarId>, TokenStream) {
-        self.init(children);
+        let tokens = self.block(children);
 
-        let args = self.buff_args;
-        let expr = self.buff_expr;
-        (self.buff, quote!(format!(#expr, #args)))
+        let LeafTextBuilder {
+            solver,
+            buff,
+            locals,
+        } = self;
+        let vars = buff
+            .into_iter()
+            .filter(|x| solver.is_var(x) && !locals.contains(&solver.var_base(x)))
+            .collect();
+        (vars, tokens)
     }
 
-    fn init(&mut self, children: Document) {
+    /// Expression of type `String`
+    fn block(&mut self, children: Document) -> TokenStream {
+        let mut segment = Segment::default();
+        let mut stmts = vec![];
         for child in children {
             match child {
-                Node::Elem(Element::Text(t)) => self
-                    .buff_expr
+                Node::Elem(Element::Text(t)) => segment
+                    .expr
                     .push_str(&t.replace("{", "{{").replace("}", "}}")),
                 Node::Expr(e) => match e {
-                    // TODO
                     Expression::Safe(id, e) | Expression::Unsafe(id, e) => {
                         let vars = self.solver.expr_inner_var(&id);
                         self.buff.extend(vars);
-                        self.buff_expr.push_str("{}");
-                        self.buff_args.push(*e);
+                        segment.expr.push_str("{}");
+                        segment.args.push(*e);
                     }
-                    Expression::Each(_id, _e) => todo!(),
-                    Expression::IfElse(_id, _e) => todo!(),
-                    Expression::Local(..) => todo!(),
+                    Expression::Each(id, each) => {
+                        self.buff.extend(self.solver.expr_inner_var(&id));
+                        let each = self.each(*each);
+                        segment.expr.push_str("{}");
+                        segment.args.push(parse2(each).unwrap());
+                    }
+                    Expression::IfElse(id, if_else) => {
+                        self.buff.extend(self.solver.expr_inner_var(&id));
+                        let if_else = self.if_else(*if_else);
+                        segment.expr.push_str("{}");
+                        segment.args.push(parse2(if_else).unwrap());
+                    }
+                    Expression::Local(id, var, local) => {
+                        self.buff.extend(self.solver.expr_inner_var(&id));
+                        self.locals.insert(var);
+                        if !segment.expr.is_empty() {
+                            let format = mem::take(&mut segment).into_format();
+                            stmts.push(quote!(__text__.push_str(&#format);));
+                        }
+                        stmts.push(quote!(#local));
+                    }
+                    Expression::Component(..) => unreachable!("components aren't inline"),
                 },
                 _ => unreachable!(),
             }
         }
+
+        if stmts.is_empty() {
+            segment.into_format()
+        } else {
+            let last = segment.into_format();
+            quote! {{
+                let mut __text__ = String::new();
+                #(#stmts)*
+                __text__.push_str(&#last);
+                __text__
+            }}
+        }
+    }
+
+    fn each(
+        &mut self,
+        Each {
+            var,
+            args,
+            body,
+            expr,
+            ..
+        }: Each,
+    ) -> TokenStream {
+        self.locals.insert(var.0);
+        if let Some(index) = var.1 {
+            self.locals.insert(index);
+        }
+        let body = self.block(body);
+
+        quote! {{
+            let mut __text__ = String::new();
+            for #expr in #args {
+                __text__.push_str(&#body);
+            }
+            __text__
+        }}
+    }
+
+    fn if_else(&mut self, IfElse { ifs, if_else, els }: IfElse) -> TokenStream {
+        let mut tokens = self.if_block(ifs);
+        for b in if_else {
+            let b = self.if_block(b);
+            tokens.extend(quote!(else #b));
+        }
+        let els = els.map_or_else(|| quote!(String::new()), |els| self.block(els));
+
+        quote!(#tokens else { #els })
+    }
+
+    fn if_block(&mut self, IfBlock { vars, expr, block }: IfBlock) -> TokenStream {
+        self.locals.extend(vars);
+        let block = self.block(block);
+
+        quote!(if #expr { #block })
     }
 }
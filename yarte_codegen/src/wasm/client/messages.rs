@@ -2,13 +2,19 @@ use heck::SnakeCase;
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote, ToTokens};
 use syn::{
-    parse_str, punctuated::Punctuated, visit_mut::VisitMut, Fields, FieldsNamed, FieldsUnnamed,
-    Ident, ItemEnum, Path, Token, Variant,
+    parse_quote, parse_str, punctuated::Punctuated, visit_mut::VisitMut, Fields, FieldsNamed,
+    FieldsUnnamed, Ident, ItemEnum, Path, Token, Variant,
 };
 
-pub fn gen_messages(e: &ItemEnum) -> (TokenStream, TokenStream) {
+/// Dispatch and messages enum with the generated variants appended
+pub fn gen_messages(e: &ItemEnum, generated: Vec<Variant>) -> (TokenStream, TokenStream) {
     let mut e = e.clone();
+    let len = e.variants.len();
+    e.variants.extend(generated);
     let msgs = MsgBuilder::default().build(&mut e);
+    for variant in e.variants.iter_mut().skip(len) {
+        variant.attrs.push(parse_quote!(#[doc(hidden)]));
+    }
     let i = &e.ident;
     (
         quote! {
@@ -2,7 +2,7 @@
 #![allow(clippy::too_many_arguments)]
 
 use std::{
-    collections::{BTreeMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashSet},
     iter, mem,
 };
 
@@ -14,12 +14,15 @@ use syn::{
 };
 
 use yarte_dom::dom::{
-    Attribute, Document, Element, ExprId, ExprOrText, Expression, IfBlock, IfElse, Node, VarId, DOM,
+    Attribute, Document, Element, ExprId, ExprOrText, Expression, Node, VarId, DOM,
 };
+use yarte_dom::dom_fmt::template_hash;
 use yarte_hir::{Struct, HIR};
 
 use crate::CodeGen;
 
+mod bind;
+mod child;
 mod component;
 mod each;
 mod events;
@@ -33,10 +36,11 @@ mod utils;
 mod tests;
 
 use self::{
+    bind::Bind,
     component::clean,
     leaf_text::get_leaf_text,
     solver::Solver,
-    state::{BlackBox, PAttr, Parent, Stack, State, Step},
+    state::{BlackBox, PAttr, Parent, PathNode, Stack, State, Step},
     utils::*,
 };
 
@@ -70,6 +74,24 @@ pub struct WASMCodeGen<'a> {
     helpers: TokenStream,
     /// Components buffer
     component: Vec<(Ident, TokenStream)>,
+    /// Types of the nested applications
+    components: BTreeSet<String>,
+    /// Keyed each helper is written
+    keyed: bool,
+    /// Root elements of each rows
+    each_roots: BTreeMap<ExprId, usize>,
+    /// Event listeners in head
+    head_hydrate: TokenStream,
+    /// Tag names of the open elements
+    tags: Vec<String>,
+    /// Tag names of the nodes found by path
+    node_tags: BTreeMap<Ident, String>,
+    /// Two-way bindings
+    binds: Vec<Bind>,
+    /// Words of the difference trees defined by the application
+    dirty: BTreeSet<usize>,
+    /// Unsupported template constructions
+    errors: Vec<syn::Error>,
 }
 
 impl<'a> WASMCodeGen<'a> {
@@ -79,8 +101,17 @@ impl<'a> WASMCodeGen<'a> {
         let state = State::new(bases);
         WASMCodeGen {
             component: vec![],
+            components: BTreeSet::new(),
             count: 0,
             helpers: TokenStream::new(),
+            keyed: false,
+            each_roots: BTreeMap::new(),
+            head_hydrate: TokenStream::new(),
+            tags: vec![],
+            node_tags: BTreeMap::new(),
+            binds: vec![],
+            dirty: BTreeSet::new(),
+            errors: vec![],
             s,
             stack: Stack::new(state),
             solver: Default::default(),
@@ -96,10 +127,14 @@ impl<'a> WASMCodeGen<'a> {
         self.stack.last_mut()
     }
 
-    fn bb_t_root<I: Iterator<Item = VarId>>(&self, parents: I) -> (TokenStream, usize) {
+    fn bb_t_root<I: Iterator<Item = VarId>>(&mut self, parents: I) -> (TokenStream, usize) {
         let len = parents.fold(0, |acc, x| acc + self.solver.group(&x).len());
 
-        get_t_root_type(len)
+        let (ty, len) = get_t_root_type(len, self.s.ident);
+        if 256 < len && self.dirty.insert(len / 32) {
+            self.helpers.extend(get_dirty_type(self.s.ident, len / 32));
+        }
+        (ty, len)
     }
 
     #[inline]
@@ -149,9 +184,16 @@ impl<'a> WASMCodeGen<'a> {
             });
 
         let name = format_ident!("{}InitialState", self.s.ident);
+        // Server side rendering of the state, see `yarte_derive::app`
+        let ssr = if cfg!(feature = "wasm-server") && self.s.is_server_rendered() {
+            quote!(#[cfg_attr(not(target_arch = "wasm32"), derive(yarte::Serialize))])
+        } else {
+            quote!()
+        };
         quote! {
             #[derive(Default, serde::Deserialize)]
-            struct #name {
+            #ssr
+            pub struct #name {
                 #fields
             }
         }
@@ -243,38 +285,7 @@ impl<'a> WASMCodeGen<'a> {
                 )
                 .into_iter()
                 .map(|(i, (x, len))| {
-                    let (t_root, len) = get_t_root_type(len);
-                    let mut bits = vec![false; len];
-                    for i in x {
-                        bits[i] = true;
-                    }
-                    let number = match len {
-                        8 => {
-                            let number = get_number_u8(bits);
-                            quote!(#number)
-                        }
-                        16 => {
-                            let number = get_number_u16(bits);
-                            quote!(#number)
-                        }
-                        32 => {
-                            let number = get_number_u32(&bits);
-                            quote!(#number)
-                        }
-                        64 => {
-                            let tokens = get_split_32(&bits);
-                            quote!(yarte_wasm_app::U64([#tokens]))
-                        }
-                        128 => {
-                            let tokens = get_split_32(&bits);
-                            quote!(yarte_wasm_app::U128([#tokens]))
-                        }
-                        256 => {
-                            let tokens = get_split_32(&bits);
-                            quote!(yarte_wasm_app::U256([#tokens]))
-                        }
-                        _ => todo!("more than 256 variables per context"),
-                    };
+                    let number = get_t_root_number(x, len, self.s.ident);
 
                     let vdom = if let Some(i) = i {
                         let ident = get_vdom_ident(i);
@@ -338,37 +349,77 @@ impl<'a> WASMCodeGen<'a> {
         tokens
     }
 
+    /// Body or `mount` element of fragment templates
+    fn root_element(&self, doc: TokenStream) -> TokenStream {
+        match &self.s.bootstrap.mount {
+            Some(id) => quote!(#doc.get_element_by_id(#id).unwrap_throw()),
+            None => quote!(#doc.body().unwrap_throw()),
+        }
+    }
+
     // Inits
+    /// Reads the state written by the server, a missing or different template hash
+    /// and a state that doesn't parse are hydration mismatches
     #[inline]
-    // TODO
-    fn init_build(&self, build: TokenStream) -> TokenStream {
+    fn init_build(&self, build: TokenStream, hash: u64) -> TokenStream {
         let ident = format_ident!("{}InitialState", self.s.ident);
         let args = self.state_fields();
+        let hash = format!("{:x}", hash);
+        let state_id = self.s.bootstrap.state_id();
+        // Without server rendering the page can start from the default state
+        let missing = if self.s.is_server_rendered() {
+            quote! {
+                panic!("yarte: hydration mismatch, state script `{}` not found", #state_id)
+            }
+        } else {
+            quote!(Default::default())
+        };
 
         quote! {
-            let #ident { #args } = yarte_wasm_app::from_str(&get_state()).unwrap_or_default();
             let doc = yarte_wasm_app::web::window().unwrap_throw().document().unwrap_throw();
+            let #ident { #args } = match doc.get_element_by_id(#state_id) {
+                Some(__state__) => {
+                    match __state__.get_attribute("data-yarte-hash") {
+                        Some(__hash__) if __hash__ == #hash => (),
+                        Some(__hash__) => panic!(
+                            "yarte: hydration mismatch, server template hash {} isn't client template hash {}",
+                            __hash__,
+                            #hash
+                        ),
+                        None => panic!(
+                            "yarte: hydration mismatch, state script `{}` without template hash",
+                            #state_id
+                        ),
+                    }
+                    let __text__ = __state__.text_content().unwrap_or_default();
+                    yarte_wasm_app::from_str(&__text__).unwrap_or_else(|e| {
+                        panic!("yarte: hydration mismatch, state script `{}`: {}", #state_id, e)
+                    })
+                }
+                None => #missing,
+            };
             #build
         }
     }
 
     #[inline]
     // TODO
-    fn init_hydrate(cur: &mut State) -> TokenStream {
+    fn init_hydrate(&self, cur: &mut State) -> TokenStream {
         if cur.buff_hydrate.is_empty() {
             quote!()
         } else {
             let body = get_body_ident();
-            let mut hydrate = quote! {
-                let #body = yarte_wasm_app::web::window().unwrap_throw()
-                    .document().unwrap_throw()
-                    .body().unwrap_throw();
-            };
+            let root = self.root_element(quote! {
+                yarte_wasm_app::web::window().unwrap_throw().document().unwrap_throw()
+            });
+            let mut hydrate = quote!(let #body = #root;);
             // Get step for events
             let steps = get_steps(cur.path_events.iter(), quote!(#body));
+            let checks = self.tag_checks(cur.path_events.iter());
 
             // Ended 'hydrate' buffer
             hydrate.extend(steps);
+            hydrate.extend(checks);
             hydrate.extend(cur.buff_hydrate.drain(..).flatten());
             hydrate
         }
@@ -376,7 +427,7 @@ impl<'a> WASMCodeGen<'a> {
 
     #[inline]
     // TODO
-    fn init_render(&self, cur: &mut State) -> TokenStream {
+    fn init_render(&mut self, cur: &mut State) -> TokenStream {
         let name = self.global_bb_ident();
         let (base, _) = self.bb_t_root(iter::once(get_self_id()));
         let render = self.render(cur);
@@ -394,9 +445,19 @@ impl<'a> WASMCodeGen<'a> {
     #[inline]
     fn init(&mut self, mut dom: DOM) -> TokenStream {
         self.solver.init(dom.tree_map, dom.var_map);
-        let mut build = TokenStream::new();
 
-        assert_eq!(dom.doc.len(), 1);
+        let is_document = match dom.doc.as_slice() {
+            [Node::Elem(Element::Node { name, .. })] => name.1 == ExprOrText::Text("html".into()),
+            _ => false,
+        };
+        if !is_document {
+            assert!(
+                self.s.bootstrap.mount.is_some(),
+                "Need html at root or `mount` attribute"
+            );
+            return self.init_body(dom.doc);
+        }
+
         match dom.doc.remove(0) {
             Node::Elem(Element::Node { name, children, .. }) => {
                 assert_eq!(ExprOrText::Text("html".into()), name.1);
@@ -425,29 +486,53 @@ impl<'a> WASMCodeGen<'a> {
                     if !self.cur().path_nodes.is_empty() {
                         todo!("in head expressions")
                     }
-                }
-                if let Some(body) = body {
-                    self.cur_mut().id = Parent::Body;
-                    if all_children_text(body.iter()) {
-                        self.write_leaf_text(body);
-                    } else {
-                        self.step(body);
+                    if !self.cur().buff_hydrate.is_empty() {
+                        let ident = format_ident!("__head");
+                        let steps = get_steps(self.cur().path_events.iter(), quote!(#ident));
+                        let checks = self.tag_checks(self.cur().path_events.iter());
+                        self.head_hydrate.extend(quote! {
+                            let #ident = yarte_wasm_app::web::window().unwrap_throw()
+                                .document().unwrap_throw()
+                                .head().unwrap_throw();
+                            #steps
+                            #checks
+                        });
+                        let cur = self.stack.last_mut();
+                        self.head_hydrate
+                            .extend(cur.buff_hydrate.drain(..).flatten());
+                        cur.path_events.clear();
                     }
-                    if !self.cur().path_nodes.is_empty() {
-                        let ident = get_body_ident();
-                        let cur = self.cur_mut();
-                        let tokens = get_steps(cur.path_nodes.iter(), quote!(#ident));
-                        build.extend(quote!(let #ident = doc.body().unwrap_throw();));
-                        build.extend(tokens);
-                        build.extend(mem::take(&mut cur.buff_build).into_iter().flatten());
-                        cur.path_nodes.clear();
-                    }
-                } else {
-                    panic!("Need <body> tag")
+                }
+                match body {
+                    Some(body) => self.init_body(body),
+                    None => panic!("Need <body> tag"),
                 }
             }
             _ => panic!("Need html at root"),
         }
+    }
+
+    /// Children of body or `mount` element
+    fn init_body(&mut self, body: Document) -> TokenStream {
+        let mut build = TokenStream::new();
+        self.cur_mut().id = Parent::Body;
+        if all_children_text(body.iter()) {
+            self.write_leaf_text(body);
+        } else {
+            self.step(body);
+        }
+        if !self.cur().path_nodes.is_empty() {
+            let ident = get_body_ident();
+            let root = self.root_element(quote!(doc));
+            let checks = self.tag_checks(self.cur().path_nodes.iter());
+            let cur = self.cur_mut();
+            let tokens = get_steps(cur.path_nodes.iter(), quote!(#ident));
+            build.extend(quote!(let #ident = #root;));
+            build.extend(tokens);
+            build.extend(checks);
+            build.extend(mem::take(&mut cur.buff_build).into_iter().flatten());
+            cur.path_nodes.clear();
+        }
 
         build
     }
@@ -475,40 +560,58 @@ impl<'a> WASMCodeGen<'a> {
         for (i, node) in nodes {
             match node {
                 Node::Elem(Element::Node {
-                    children, attrs, ..
+                    name,
+                    children,
+                    attrs,
                 }) => {
                     self.cur_mut().steps.push(if i == 0 {
                         Step::FirstChild
                     } else {
                         Step::NextSibling
                     });
+                    self.tags.push(match name.1 {
+                        ExprOrText::Text(s) => s,
+                        ExprOrText::Expr(_) => String::new(),
+                    });
                     for attr in attrs {
                         self.resolve_attr(attr);
                     }
 
-                    if all_children_text(children.iter()) {
+                    // Nested applications hydrate their own subtree
+                    if is_mount(&children) {
+                        if let Some(Node::Expr(Expression::Component(id, component))) =
+                            children.into_iter().find(|x| matches!(x, Node::Expr(_)))
+                        {
+                            self.write_component(id, *component);
+                        }
+                    } else if all_children_text(children.iter()) {
                         self.write_leaf_text(children);
                     } else {
                         self.step(children);
                     }
+                    self.tags.pop();
                 }
                 Node::Expr(e) => match e {
                     Expression::Each(id, each) => {
                         self.gen_each(id, *each, len != 1, i == len, insert_point.split_at(i).0);
                         self.cur_mut().steps.push(Step::Each(id));
                     }
-                    Expression::IfElse(id, if_else) => {
-                        let IfElse { ifs, if_else, els } = *if_else;
-
-                        self.resolve_if_block(ifs, id);
-                        for b in if_else {
-                            self.resolve_if_block(b, id);
-                        }
-                        if let Some(body) = els {
-                            todo!("resolve if else block expresion");
-                        }
+                    Expression::IfElse(_, if_else) => self.errors.push(syn::Error::new_spanned(
+                        &if_else.ifs.expr,
+                        "`{{#if}}` blocks between elements are not supported in applications, \
+                             use them inside the text of an element",
+                    )),
+                    Expression::Local(_, _, local) => self.errors.push(syn::Error::new_spanned(
+                        &*local,
+                        "`{{ let }}` between elements is not supported in applications, \
+                         use it inside the text of an element",
+                    )),
+                    Expression::Component(_, component) => {
+                        self.errors.push(syn::Error::new_spanned(
+                            &component.path,
+                            "components must be the only child of an element",
+                        ))
                     }
-                    Expression::Local(..) => todo!("resolve local expression"),
                     Expression::Safe(id, _) | Expression::Unsafe(id, _) => unreachable!(),
                 },
                 Node::Elem(Element::Text(_)) => (),
@@ -529,6 +632,16 @@ impl<'a> WASMCodeGen<'a> {
                 ),
             };
             self.write_event(*id, event, msg);
+        } else if let Some(prop) = is_bind_attr(&attr) {
+            let (id, expr) = match attr.value.as_slice() {
+                [ExprOrText::Expr(Expression::Unsafe(id, expr))]
+                | [ExprOrText::Expr(Expression::Safe(id, expr))] => (id, &**expr),
+                _ => panic!(
+                    "{}",
+                    "only use a field expression `{{ .. }}` in `bind:` attributes"
+                ),
+            };
+            self.write_bind(*id, prop, expr);
         } else {
             match attr.name {
                 ExprOrText::Expr(_) => todo!("name attribute expression"),
@@ -542,9 +655,6 @@ impl<'a> WASMCodeGen<'a> {
         }
     }
 
-    #[inline]
-    fn resolve_if_block(&mut self, IfBlock { block, .. }: IfBlock, id: ExprId) {}
-
     // Clear buffer and return it
     // TODO: empty helpers
     #[inline]
@@ -570,6 +680,7 @@ impl<'a> WASMCodeGen<'a> {
     fn write_leaf_text(&mut self, children: Document) {
         let (t, e) = get_leaf_text(children, &self.solver);
         let name = self.current_node_ident(0);
+        self.register_tag(&name);
 
         let dom = match self.cur().id {
             Parent::Body => {
@@ -605,6 +716,38 @@ impl<'a> WASMCodeGen<'a> {
         })
     }
 
+    fn register_tag(&mut self, name: &Ident) {
+        if let Some(tag) = self.tags.last().filter(|x| !x.is_empty()) {
+            self.node_tags.insert(name.clone(), tag.clone());
+        }
+    }
+
+    /// Structural comparison of the nodes found by path in debug builds
+    fn tag_checks<'b, I: Iterator<Item = &'b PathNode>>(&self, nodes: I) -> TokenStream {
+        let (idents, tags): (Vec<_>, Vec<_>) = nodes
+            .filter_map(|(ident, _)| self.node_tags.get(ident).map(|tag| (ident, tag)))
+            .unzip();
+        if idents.is_empty() {
+            return quote!();
+        }
+
+        quote! {
+            #[cfg(debug_assertions)]
+            {
+                let __tags__: &[(&yarte_wasm_app::web::Element, &str)] = &[#((&#idents, #tags)),*];
+                for (__node__, __tag__) in __tags__ {
+                    if !__node__.tag_name().eq_ignore_ascii_case(__tag__) {
+                        panic!(
+                            "yarte: hydration mismatch, expected <{}> found <{}>",
+                            __tag__,
+                            __node__.tag_name().to_lowercase()
+                        );
+                    }
+                }
+            }
+        }
+    }
+
     fn find_current_node(&self, init: usize) -> Option<Ident> {
         let cur = self.cur();
         let path = &cur.steps[init..];
@@ -623,12 +766,23 @@ impl<'a> WASMCodeGen<'a> {
 
 impl<'a> CodeGen for WASMCodeGen<'a> {
     fn gen(&mut self, ir: Vec<HIR>) -> TokenStream {
+        let hash = template_hash(&ir);
         let build = self.init(ir.into());
+        if !self.errors.is_empty() {
+            // Multi app compilation
+            clean();
+            return self
+                .errors
+                .iter()
+                .map(syn::Error::to_compile_error)
+                .collect();
+        }
 
         let mut cur = self.stack.pop();
 
         // Ended 'hydrate' buffer
-        let hydrate = Self::init_hydrate(&mut cur);
+        let mut hydrate = mem::take(&mut self.head_hydrate);
+        hydrate.extend(self.init_hydrate(&mut cur));
 
         // Black box ident and type
         let bb_ident = self.global_bb_ident();
@@ -669,7 +823,7 @@ impl<'a> CodeGen for WASMCodeGen<'a> {
             #bb_ident: #bb_type { #bb_field_value }
         });
 
-        let mut build = self.init_build(build);
+        let mut build = self.init_build(build, hash);
         build.extend(quote! {
             Self { #(#fields),* }
         });
@@ -688,7 +842,25 @@ impl<'a> CodeGen for WASMCodeGen<'a> {
             .as_ref()
             .expect("Need define messages for application");
         let msgs_type = &msgs.ident;
-        let (dispatch, msgs) = messages::gen_messages(msgs);
+        let (variants, handlers): (Vec<_>, Vec<_>) = self
+            .binds
+            .drain(..)
+            .map(|Bind { variant, handler }| (variant, handler))
+            .unzip();
+        let mut handlers = handlers;
+        // Mounted applications are nested by its parents
+        if cfg!(feature = "wasm-component") && self.s.bootstrap.mount.is_some() {
+            handlers.extend(self.setters());
+        }
+        let (dispatch, msgs) = messages::gen_messages(msgs, variants);
+        // Server output is checked against it at hydration
+        let hash = format!("{:x}", hash);
+        let handlers = self.s.implement_inherent(&quote! {
+            #[doc(hidden)]
+            pub const __YARTE_HASH: &'static str = #hash;
+
+            #(#handlers)*
+        });
 
         // Make App trait body
         let app = quote! {
@@ -719,13 +891,10 @@ impl<'a> CodeGen for WASMCodeGen<'a> {
         quote! {
             #[allow(unused_imports)]
             use yarte_wasm_app::*;
-            #[wasm_bindgen]
-            extern "C" {
-                fn get_state() -> String;
-            }
 
             #app
             #msgs
+            #handlers
             #initial_state
             #black_box
             #helpers
@@ -39,9 +39,8 @@ impl Solver {
             })
             .collect();
 
-        if grouped.get(&get_self_id()).is_none() {
-            todo!("need any field in struct of application")
-        }
+        // Stateless applications
+        grouped.entry(get_self_id()).or_default();
         self.grouped_map = grouped;
         self.tree_map = tree_map;
         self.var_map = var_map;
@@ -65,6 +64,11 @@ impl Solver {
             .unwrap_or_else(|| panic!("unregistered variable: {}", id))
     }
 
+    /// Locals aren't registered
+    pub fn is_var(&self, id: &VarId) -> bool {
+        self.var_map.contains_key(id)
+    }
+
     pub fn var_base(&self, id: &VarId) -> VarId {
         self.var_inner(id).base
     }
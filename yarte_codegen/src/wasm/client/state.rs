@@ -178,7 +178,10 @@ impl State {
                         colon_token: Some(<Token![:]>::default()),
                         expr: parse2(quote!(#dom)).unwrap(),
                     });
-                } else if on_build && x.name.to_string().starts_with("__closure__") {
+                } else if on_build
+                    && (x.name.to_string().starts_with("__closure__")
+                        || x.name.to_string().starts_with("__component__"))
+                {
                     acc.push(FieldValue {
                         attrs: vec![],
                         member: Member::Named(x.name.clone()),
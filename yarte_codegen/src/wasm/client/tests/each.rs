@@ -16,10 +16,6 @@ fn test() {
     let expected = quote! {
 #[allow(unused_imports)]
 use yarte_wasm_app::*;
-#[wasm_bindgen]
-extern "C" {
-    fn get_state() -> String;
-}
 impl yarte_wasm_app::App for Test {
     type BlackBox = TestBlackBox;
     type Message = Msg;
@@ -96,8 +92,13 @@ impl yarte_wasm_app::App for Test {
     }
 }
 pub enum Msg {}
-#[derive(Default, serde :: Deserialize)]
-struct TestInitialState {}
+impl Test {
+            #[doc(hidden)]
+            pub const __YARTE_HASH: &'static str = "0";
+        }
+
+        #[derive(Default, serde :: Deserialize)]
+pub struct TestInitialState {}
 #[doc = "Internal elements and difference tree"]
 pub struct TestBlackBox {
     #[doc = "Each Virtual DOM node"]
@@ -127,8 +128,28 @@ impl Drop for YComponent0 {
 }
 impl std::default::Default for Test {
     fn default() -> Self {
-        let TestInitialState {} = yarte_wasm_app::from_str(&get_state()).unwrap_or_default();
         let doc = yarte_wasm_app::web::window().unwrap_throw().document().unwrap_throw();
+        let TestInitialState {} = match doc.get_element_by_id("yarte-state") {
+            Some(__state__) => {
+                match __state__.get_attribute("data-yarte-hash") {
+                    Some(__hash__) if __hash__ == "0" => (),
+                    Some(__hash__) => panic!(
+                        "yarte: hydration mismatch, server template hash {} isn't client template hash {}",
+                        __hash__,
+                        "0"
+                    ),
+                    None => panic!(
+                        "yarte: hydration mismatch, state script `{}` without template hash",
+                        "yarte-state"
+                    ),
+                }
+                let __text__ = __state__.text_content().unwrap_or_default();
+                yarte_wasm_app::from_str(&__text__).unwrap_or_else(|e| {
+                    panic!("yarte: hydration mismatch, state script `{}`: {}", "yarte-state", e)
+                })
+            }
+            None => Default::default(),
+        };
         let __ybody = doc.body().unwrap_throw();
         let __ytable_dom__0 = __ybody.first_element_child().unwrap_throw();
         let mut __ytable__0: Vec<YComponent0> = vec![];
@@ -169,3 +190,91 @@ impl std::default::Default for Test {
     let c = tokens(der, false);
     assert_eq!(c, expected)
 }
+
+#[test]
+fn test_keyed() {
+    let src = r#"
+    <!doctype html><html><body>
+    <table>{{#each fortunes key = id }}<tr><td>{{ id }}</td><td>{{ message }}</td></tr>{{/each}}</table>
+    </body></html>"#;
+    let der = quote! {
+        #[derive(App)]
+        #[template(src = #src)]
+        #[msg(pub enum Msg {})]
+        pub struct Test {
+            black_box: <Self as App>::BlackBox,
+        }
+    };
+
+    let res = tokens(der, false);
+    for part in &[
+        "fn __ykeyed_stable__ (sources : & [usize]) -> Vec < bool >",
+        "# [doc = \"Each key\"] pub __key : Box < dyn std :: any :: Any >",
+        "# [doc = \"Each key hash\"] pub __key_hash : u64",
+        "fn __ykey_eq__ < K : Eq + 'static > (old : & dyn std :: any :: Any , new : & K) -> bool",
+        "let __items__ : Vec < _ > = ((& (self . fortunes)) . __into_citer ()) . collect () ;",
+        "let __stable__ = __ykeyed_stable__ (& __sources__) ;",
+        "self . black_box . __ytable__0 . reverse () ;",
+    ] {
+        assert!(res.contains(part), "{}\n{}", part, res);
+    }
+    assert_eq!(res.matches("fn __ykeyed_stable__").count(), 1);
+}
+
+#[test]
+fn test_multi_root() {
+    let src = r#"
+    <!doctype html><html><body>
+    <table>{{#each fortunes }}<tr><td>{{ id }}</td></tr><tr><td>{{ message }}</td></tr>{{/each}}</table>
+    </body></html>"#;
+    let der = quote! {
+        #[derive(App)]
+        #[template(src = #src)]
+        #[msg(pub enum Msg {})]
+        pub struct Test {
+            black_box: <Self as App>::BlackBox,
+        }
+    };
+
+    let res = tokens(der, false);
+    for part in &[
+        "# [doc = \"root dom element\"] pub __root1 : yarte_wasm_app :: web :: Element",
+        "self . __root . remove () ; self . __root1 . remove () ;",
+        "let __tmp__ = __tmp__ . first_element_child () . unwrap_throw () ; let __root1 = __tmp__ . next_element_sibling () . unwrap_throw () ;",
+        "let __ynode__1 = __dom__0 . next_element_sibling () . unwrap_throw () . first_element_child () . unwrap_throw () ;",
+        "item (1u32 + __dom_len__ as u32 * 2u32)",
+    ] {
+        assert!(res.contains(part), "{}\n{}", part, res);
+    }
+}
+
+#[test]
+fn test_no_element_roots() {
+    for body in &[
+        "{{ name }}<li>{{ name }}</li>",
+        "<li>{{ name }}</li>{{ name }}",
+        "text<li>{{ name }}</li>",
+    ] {
+        let src = format!(
+            "<!doctype html><html><body><ul>{{{{#each items}}}}{}{{{{/each}}}}</ul></body></html>",
+            body
+        );
+        let der = quote! {
+            #[derive(App)]
+            #[template(src = #src)]
+            #[msg(pub enum Msg {})]
+            pub struct Test {
+                items: Vec<Item>,
+                black_box: <Self as App>::BlackBox,
+            }
+        };
+
+        let res = tokens(der, false);
+        assert!(
+            res.contains("roots of each body must be elements in wasm applications"),
+            "{}\n{}",
+            body,
+            res
+        );
+    }
+}
@@ -16,10 +16,6 @@ fn test() {
     let expected = quote! {
 #[allow(unused_imports)]
 use yarte_wasm_app::*;
-#[wasm_bindgen]
-extern "C" {
-    fn get_state() -> String;
-}
 impl yarte_wasm_app::App for Test {
     type BlackBox = TestBlackBox;
     type Message = Msg;
@@ -43,8 +39,13 @@ impl yarte_wasm_app::App for Test {
     }
 }
 pub enum Msg {}
-#[derive(Default, serde :: Deserialize)]
-struct TestInitialState {}
+impl Test {
+            #[doc(hidden)]
+            pub const __YARTE_HASH: &'static str = "0";
+        }
+
+        #[derive(Default, serde :: Deserialize)]
+pub struct TestInitialState {}
 #[doc = "Internal elements and difference tree"]
 pub struct TestBlackBox {
     #[doc = "Each Virtual DOM node"]
@@ -54,8 +55,28 @@ pub struct TestBlackBox {
 }
 impl std::default::Default for Test {
     fn default() -> Self {
-        let TestInitialState {} = yarte_wasm_app::from_str(&get_state()).unwrap_or_default();
         let doc = yarte_wasm_app::web::window().unwrap_throw().document().unwrap_throw();
+        let TestInitialState {} = match doc.get_element_by_id("yarte-state") {
+            Some(__state__) => {
+                match __state__.get_attribute("data-yarte-hash") {
+                    Some(__hash__) if __hash__ == "0" => (),
+                    Some(__hash__) => panic!(
+                        "yarte: hydration mismatch, server template hash {} isn't client template hash {}",
+                        __hash__,
+                        "0"
+                    ),
+                    None => panic!(
+                        "yarte: hydration mismatch, state script `{}` without template hash",
+                        "yarte-state"
+                    ),
+                }
+                let __text__ = __state__.text_content().unwrap_or_default();
+                yarte_wasm_app::from_str(&__text__).unwrap_or_else(|e| {
+                    panic!("yarte: hydration mismatch, state script `{}`: {}", "yarte-state", e)
+                })
+            }
+            None => Default::default(),
+        };
         let __ybody = doc.body().unwrap_throw();
         Self {
             black_box: TestBlackBox {
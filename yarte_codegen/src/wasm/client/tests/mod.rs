@@ -4,8 +4,9 @@ use proc_macro2::TokenStream;
 use quote::quote;
 use syn::parse2;
 
+use yarte_dom::dom_fmt::template_hash;
 use yarte_helpers::{config::Config, logger::log};
-use yarte_hir::{generate, visit_derive};
+use yarte_hir::{generate, visit_derive, HIROptions};
 use yarte_parser::{
     emitter, parse,
     source_map::{clean, get_cursor},
@@ -29,11 +30,20 @@ fn tokens(i: TokenStream, wlog: bool) -> String {
     let mut ctx = BTreeMap::new();
     ctx.insert(&s.path, sources);
 
-    let ir = generate(config, &s, &ctx, Default::default())
-        .unwrap_or_else(|e| emitter(&src, config, e.into_iter()));
+    let opt = HIROptions {
+        components: true,
+        ..Default::default()
+    };
+    let ir =
+        generate(config, &s, &ctx, opt).unwrap_or_else(|e| emitter(&src, config, e.into_iter()));
     clean();
 
-    let res = WASMCodeGen::new(&s).gen(ir).to_string();
+    // Hash value depends on the std hasher
+    let hash = format!("\"{:x}\"", template_hash(&ir));
+    let res = WASMCodeGen::new(&s)
+        .gen(ir)
+        .to_string()
+        .replace(&hash, "\"0\"");
     if wlog {
         log(&res, "Test".into(), &config.debug);
     }
@@ -60,10 +70,6 @@ fn test() {
     let expected = quote! {
         #[allow(unused_imports)]
         use yarte_wasm_app::*;
-        #[wasm_bindgen]
-        extern "C" {
-            fn get_state() -> String;
-        }
 
         impl yarte_wasm_app::App for Test {
             type BlackBox = TestBlackBox;
@@ -100,8 +106,13 @@ fn test() {
             Foo,
         }
 
+        impl Test {
+            #[doc(hidden)]
+            pub const __YARTE_HASH: &'static str = "0";
+        }
+
         #[derive(Default, serde::Deserialize)]
-        struct TestInitialState { }
+        pub struct TestInitialState { }
 
         #[doc = "Internal elements and difference tree"]
         pub struct TestBlackBox {
@@ -113,10 +124,43 @@ fn test() {
 
         impl std::default::Default for Test {
             fn default() -> Self {
-                let TestInitialState { } = yarte_wasm_app::from_str(&get_state()).unwrap_or_default();
                 let doc = yarte_wasm_app::web::window().unwrap_throw().document().unwrap_throw();
+                let TestInitialState {} = match doc.get_element_by_id("yarte-state") {
+            Some(__state__) => {
+                match __state__.get_attribute("data-yarte-hash") {
+                    Some(__hash__) if __hash__ == "0" => (),
+                    Some(__hash__) => panic!(
+                        "yarte: hydration mismatch, server template hash {} isn't client template hash {}",
+                        __hash__,
+                        "0"
+                    ),
+                    None => panic!(
+                        "yarte: hydration mismatch, state script `{}` without template hash",
+                        "yarte-state"
+                    ),
+                }
+                let __text__ = __state__.text_content().unwrap_or_default();
+                yarte_wasm_app::from_str(&__text__).unwrap_or_else(|e| {
+                    panic!("yarte: hydration mismatch, state script `{}`: {}", "yarte-state", e)
+                })
+            }
+            None => Default::default(),
+        };
                 let __ybody = doc.body().unwrap_throw();
                 let __ynode__0 = __ybody.first_element_child().unwrap_throw();
+                #[cfg(debug_assertions)]
+                {
+                    let __tags__: &[(&yarte_wasm_app::web::Element, &str)] = &[(&__ynode__0, "div")];
+                    for (__node__, __tag__) in __tags__ {
+                        if !__node__.tag_name().eq_ignore_ascii_case(__tag__) {
+                            panic!(
+                                "yarte: hydration mismatch, expected <{}> found <{}>",
+                                __tag__,
+                                __node__.tag_name().to_lowercase()
+                            );
+                        }
+                    }
+                }
                 Self {
                     black_box: TestBlackBox {
                         __ynode__0: __ynode__0,
@@ -130,3 +174,269 @@ fn test() {
 
     assert_eq!(tokens(der, false), expected)
 }
+
+#[test]
+fn test_many_vars() {
+    let vars: String = (0..300)
+        .map(|i| format!("<p>{{{{ f{} }}}}</p>", i))
+        .collect();
+    let src = format!("<!doctype html><html><body>{}</body></html>", vars);
+    let der = quote! {
+        #[derive(App)]
+        #[template(src = #src)]
+        #[msg(pub enum Msg {})]
+        pub struct Test {
+            black_box: <Self as App>::BlackBox,
+        }
+    };
+
+    let res = tokens(der, false);
+    for part in &[
+        "pub t_root : TestDirty10",
+        "pub struct TestDirty10 { groups : [u32 ; 1] , words : [u32 ; 10] , }",
+        "yarte_wasm_app :: YNumber :: neq_zero (self . black_box . t_root & TestDirty10 { groups : [512u32] , words : [0u32 , 0u32 , 0u32 , 0u32 , 0u32 , 0u32 , 0u32 , 0u32 , 0u32 , 2048u32] })",
+    ] {
+        assert!(res.contains(part), "{}\n{}", part, res);
+    }
+}
+
+#[test]
+fn test_leaf_text_control_flow() {
+    let src = r#"
+    <!doctype html><html><body>
+    <p>{{#if show }}yes{{else}}no{{/if}} {{ let n = count + 1 }}{{ n }}{{#each items }}{{ this }},{{/each}}</p>
+    </body></html>"#;
+    let der = quote! {
+        #[derive(App)]
+        #[template(src = #src)]
+        #[msg(pub enum Msg {})]
+        pub struct Test {
+            black_box: <Self as App>::BlackBox,
+        }
+    };
+
+    let res = tokens(der, false);
+    for part in &[
+        "if yarte_wasm_app :: YNumber :: neq_zero (self . black_box . t_root & 7u8)",
+        "if self . show { format ! (\"yes\" ,) } else { format ! (\"no\" ,) }",
+        "let n__0x00000000 = self . count + 1 ;",
+        "for __key___0x00000001 in ((& (self . items)) . __into_citer ()) { __text__ . push_str (& format ! (\"{},\" , __key___0x00000001)) ; }",
+    ] {
+        assert!(res.contains(part), "{}\n{}", part, res);
+    }
+}
+
+#[test]
+fn test_element_blocks_errors() {
+    for (src, message) in &[
+        (
+            "<div>{{#if show }}<p>yes</p>{{else}}<p>no</p>{{/if}}<p>!</p></div>",
+            "`{{#if}}` blocks between elements are not supported in applications",
+        ),
+        (
+            "<div>{{#if show }}<p>yes</p>{{/if}}<p>!</p></div>",
+            "`{{#if}}` blocks between elements are not supported in applications",
+        ),
+        (
+            "<div>{{ let n = count + 1 }}<p>{{ n }}</p></div>",
+            "`{{ let }}` between elements is not supported in applications",
+        ),
+    ] {
+        let src = format!("<!doctype html><html><body>{}</body></html>", src);
+        let der = quote! {
+            #[derive(App)]
+            #[template(src = #src)]
+            #[msg(pub enum Msg {})]
+            pub struct Test {
+                show: bool,
+                count: usize,
+                black_box: <Self as App>::BlackBox,
+            }
+        };
+
+        let res = tokens(der, false);
+        assert!(res.contains(message), "{}\n{}", message, res);
+    }
+}
+
+#[test]
+fn test_event_modifiers() {
+    let src = r#"
+    <!doctype html><html><body>
+    <input oninput.stop.default="{{? Msg::Input(value) }}" onkeydown.once.passive="{{? Msg::Key(key_code) }}">
+    <button onclick.stop="{{? Msg::Click(self.value) }}"></button>
+    <p>{{ name }}</p>
+    </body></html>"#;
+    let der = quote! {
+        #[derive(App)]
+        #[template(src = #src)]
+        #[msg(pub enum Msg { Input(String), Key(u32), Click(String) })]
+        pub struct Test {
+            name: String,
+            value: String,
+            black_box: <Self as App>::BlackBox,
+        }
+    };
+
+    let res = tokens(der, false);
+    for part in &[
+        "| __event : yarte_wasm_app :: web :: Event | { __event . stop_propagation () ; let value = yarte_wasm_app :: JsCast :: unchecked_ref :: < yarte_wasm_app :: web :: HtmlInputElement > (& __event . target () . unwrap_throw () ,) . value () ; __addr . send (Msg :: Input (value)) ;",
+        "| __event : yarte_wasm_app :: web :: Event | { let key_code = yarte_wasm_app :: JsCast :: unchecked_ref :: < yarte_wasm_app :: web :: KeyboardEvent > (& __event) . key_code () ; __addr . send (Msg :: Key (key_code)) ;",
+        "let __cloned__0 = (self . value) . clone () ;",
+        "| __event : yarte_wasm_app :: web :: Event | { __event . prevent_default () ; __event . stop_propagation () ; __addr . send (Msg :: Click (__cloned__0)) ;",
+        "add_event_listener_with_callback_and_add_event_listener_options (\"keydown\" , yarte_wasm_app :: JsCast :: unchecked_ref (__closure__ . as_ref ()) , yarte_wasm_app :: web :: AddEventListenerOptions :: new () . capture (false) . passive (true) . once (true) ,)",
+        "add_event_listener_with_callback (\"input\" ,",
+    ] {
+        assert!(res.contains(part), "{}\n{}", part, res);
+    }
+    assert_eq!(res.matches("prevent_default").count(), 2, "{}", res);
+}
+
+#[test]
+fn test_event_modifiers_errors() {
+    for (modifiers, message) in &[
+        ("click.over", "unknown event modifier `over`"),
+        (
+            "click.prevent.passive",
+            "passive event listeners can't prevent default",
+        ),
+        (
+            "click.prevent.default",
+            "`prevent` and `default` event modifiers are exclusive",
+        ),
+    ] {
+        let src = format!(
+            "<!doctype html><html><body><button on{}=\"{{{{? Msg::Click }}}}\"></button></body></html>",
+            modifiers
+        );
+        let der = quote! {
+            #[derive(App)]
+            #[template(src = #src)]
+            #[msg(pub enum Msg { Click })]
+            pub struct Test {
+                black_box: <Self as App>::BlackBox,
+            }
+        };
+
+        let res = tokens(der, false);
+        assert!(res.contains(message), "{}\n{}", message, res);
+    }
+}
+
+#[test]
+fn test_event_message_errors() {
+    let src = "<!doctype html><html><body>\
+    <button onclick=\"{{? Msg::from(key_code).into() }}\"></button>\
+    </body></html>";
+    let der = quote! {
+        #[derive(App)]
+        #[template(src = #src)]
+        #[msg(pub enum Msg { Enter })]
+        pub struct Test {
+            black_box: <Self as App>::BlackBox,
+        }
+    };
+
+    let res = tokens(der, false);
+    let message = "no valid expression at `on` attribute";
+    assert!(res.contains(message), "{}\n{}", message, res);
+}
+
+#[test]
+fn test_component() {
+    let src = "<!doctype html><html><body>\
+    <div><h1>{{ title }}</h1><div id=\"counter\">{{> @counter::Counter count = total }}</div></div>\
+    </body></html>";
+    let der = quote! {
+        #[derive(App)]
+        #[template(src = #src)]
+        #[msg(pub enum Msg {})]
+        pub struct Test {
+            title: String,
+            total: usize,
+            black_box: <Self as App>::BlackBox,
+        }
+    };
+
+    let res = tokens(der, false);
+    // Props are set when its variables change
+    let render = quote! {
+        if yarte_wasm_app::YNumber::neq_zero(self.black_box.t_root & 1u8) {
+            if let Some(__component__) = self.black_box.__component__1 {
+                let __value__ = (self.total).clone();
+                __component__.update(move |__app__| __app__.__set_count(__value__));
+            }
+        }
+    };
+    let hydrate = quote! {
+        self.black_box.__component__1 =
+            Some(yarte_wasm_app::Addr::<counter::Counter>::run_child(__addr, |_| None));
+    };
+    let field = quote! {
+        pub __component__1: Option<&'static yarte_wasm_app::Addr<counter::Counter> >,
+    };
+    for expected in &[render, hydrate, field] {
+        assert!(res.contains(&expected.to_string()), "{}\n{}", expected, res);
+    }
+}
+
+#[test]
+fn test_component_errors() {
+    for (src, message) in &[
+        (
+            "<div><p>{{> @Counter }}</p><p>{{> @Counter }}</p><span>{{> @Counter }} !</span></div>",
+            "components must be the only child of an element",
+        ),
+        (
+            "<div>{{#each items}}<p>{{> @Counter count = this }}</p>{{/each}}</div>",
+            "components are only supported outside `each` and `if` blocks",
+        ),
+        (
+            "<div><p>{{> @Counter }}</p><p>{{> @Counter count = 1 }}</p></div>",
+            "a component type can only be mounted once per application",
+        ),
+    ] {
+        let src = format!("<!doctype html><html><body>{}</body></html>", src);
+        let der = quote! {
+            #[derive(App)]
+            #[template(src = #src)]
+            #[msg(pub enum Msg {})]
+            pub struct Test {
+                items: Vec<usize>,
+                black_box: <Self as App>::BlackBox,
+            }
+        };
+
+        let res = tokens(der, false);
+        assert!(res.contains(message), "{}\n{}", message, res);
+    }
+}
+
+#[test]
+fn test_bind_errors() {
+    for (src, message) in &[
+        (
+            "<div><input bind:value=\"{{ self.count + 1 }}\"></div>",
+            "`bind:value` attribute needs a field expression",
+        ),
+        (
+            "<div>{{#each items}}<input bind:value=\"{{ self.count }}\">{{/each}}</div>",
+            "`bind:` attributes are only supported outside `each` and `if` blocks",
+        ),
+    ] {
+        let src = format!("<!doctype html><html><body>{}</body></html>", src);
+        let der = quote! {
+            #[derive(App)]
+            #[template(src = #src)]
+            #[msg(pub enum Msg {})]
+            pub struct Test {
+                count: usize,
+                items: Vec<usize>,
+                black_box: <Self as App>::BlackBox,
+            }
+        };
+
+        let res = tokens(der, false);
+        assert!(res.contains(message), "{}\n{}", message, res);
+    }
+}
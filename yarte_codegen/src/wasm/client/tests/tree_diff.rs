@@ -23,10 +23,6 @@ fn test_diff_u16() {
     let expected = quote! {
         #[allow(unused_imports)]
         use yarte_wasm_app::*;
-        #[wasm_bindgen]
-        extern "C" {
-            fn get_state() -> String;
-        }
 
         impl yarte_wasm_app::App for Test {
             type BlackBox = TestBlackBox;
@@ -63,8 +59,13 @@ fn test_diff_u16() {
             Foo,
         }
 
+        impl Test {
+            #[doc(hidden)]
+            pub const __YARTE_HASH: &'static str = "0";
+        }
+
         #[derive(Default, serde::Deserialize)]
-        struct TestInitialState { }
+        pub struct TestInitialState { }
 
         #[doc = "Internal elements and difference tree"]
         pub struct TestBlackBox {
@@ -76,10 +77,43 @@ fn test_diff_u16() {
 
         impl std::default::Default for Test {
             fn default() -> Self {
-                let TestInitialState { } = yarte_wasm_app::from_str(&get_state()).unwrap_or_default();
                 let doc = yarte_wasm_app::web::window().unwrap_throw().document().unwrap_throw();
+                let TestInitialState {} = match doc.get_element_by_id("yarte-state") {
+            Some(__state__) => {
+                match __state__.get_attribute("data-yarte-hash") {
+                    Some(__hash__) if __hash__ == "0" => (),
+                    Some(__hash__) => panic!(
+                        "yarte: hydration mismatch, server template hash {} isn't client template hash {}",
+                        __hash__,
+                        "0"
+                    ),
+                    None => panic!(
+                        "yarte: hydration mismatch, state script `{}` without template hash",
+                        "yarte-state"
+                    ),
+                }
+                let __text__ = __state__.text_content().unwrap_or_default();
+                yarte_wasm_app::from_str(&__text__).unwrap_or_else(|e| {
+                    panic!("yarte: hydration mismatch, state script `{}`: {}", "yarte-state", e)
+                })
+            }
+            None => Default::default(),
+        };
                 let __ybody = doc.body().unwrap_throw();
                 let __ynode__0 = __ybody.first_element_child().unwrap_throw();
+                #[cfg(debug_assertions)]
+                {
+                    let __tags__: &[(&yarte_wasm_app::web::Element, &str)] = &[(&__ynode__0, "div")];
+                    for (__node__, __tag__) in __tags__ {
+                        if !__node__.tag_name().eq_ignore_ascii_case(__tag__) {
+                            panic!(
+                                "yarte: hydration mismatch, expected <{}> found <{}>",
+                                __tag__,
+                                __node__.tag_name().to_lowercase()
+                            );
+                        }
+                    }
+                }
                 Self {
                     black_box: TestBlackBox {
                         __ynode__0: __ynode__0,
@@ -118,10 +152,6 @@ fn test_diff_u16_1() {
     let expected = quote! {
         #[allow(unused_imports)]
         use yarte_wasm_app::*;
-        #[wasm_bindgen]
-        extern "C" {
-            fn get_state() -> String;
-        }
 
         impl yarte_wasm_app::App for Test {
             type BlackBox = TestBlackBox;
@@ -162,8 +192,13 @@ fn test_diff_u16_1() {
             Foo,
         }
 
+        impl Test {
+            #[doc(hidden)]
+            pub const __YARTE_HASH: &'static str = "0";
+        }
+
         #[derive(Default, serde::Deserialize)]
-        struct TestInitialState { }
+        pub struct TestInitialState { }
 
         #[doc = "Internal elements and difference tree"]
         pub struct TestBlackBox {
@@ -177,11 +212,44 @@ fn test_diff_u16_1() {
 
         impl std::default::Default for Test {
             fn default() -> Self {
-                let TestInitialState { } = yarte_wasm_app::from_str(&get_state()).unwrap_or_default();
                 let doc = yarte_wasm_app::web::window().unwrap_throw().document().unwrap_throw();
+                let TestInitialState {} = match doc.get_element_by_id("yarte-state") {
+            Some(__state__) => {
+                match __state__.get_attribute("data-yarte-hash") {
+                    Some(__hash__) if __hash__ == "0" => (),
+                    Some(__hash__) => panic!(
+                        "yarte: hydration mismatch, server template hash {} isn't client template hash {}",
+                        __hash__,
+                        "0"
+                    ),
+                    None => panic!(
+                        "yarte: hydration mismatch, state script `{}` without template hash",
+                        "yarte-state"
+                    ),
+                }
+                let __text__ = __state__.text_content().unwrap_or_default();
+                yarte_wasm_app::from_str(&__text__).unwrap_or_else(|e| {
+                    panic!("yarte: hydration mismatch, state script `{}`: {}", "yarte-state", e)
+                })
+            }
+            None => Default::default(),
+        };
                 let __ybody = doc.body().unwrap_throw();
                 let __ynode__0 = __ybody.first_element_child().unwrap_throw();
                 let __ynode__1 = __ynode__0.next_element_sibling().unwrap_throw();
+                #[cfg(debug_assertions)]
+                {
+                    let __tags__: &[(&yarte_wasm_app::web::Element, &str)] = &[(&__ynode__0, "div"), (&__ynode__1, "div")];
+                    for (__node__, __tag__) in __tags__ {
+                        if !__node__.tag_name().eq_ignore_ascii_case(__tag__) {
+                            panic!(
+                                "yarte: hydration mismatch, expected <{}> found <{}>",
+                                __tag__,
+                                __node__.tag_name().to_lowercase()
+                            );
+                        }
+                    }
+                }
                 Self {
                     black_box: TestBlackBox {
                         __ynode__0: __ynode__0,
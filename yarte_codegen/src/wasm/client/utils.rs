@@ -1,8 +1,11 @@
 #![allow(clippy::unnested_or_patterns)]
 
-use proc_macro2::TokenStream;
+use proc_macro2::{Literal, TokenStream};
 use quote::{format_ident, quote};
-use syn::{parse2, punctuated::Punctuated, Field, Ident, Token, Type};
+use syn::{
+    parse2, punctuated::Punctuated, visit_mut::VisitMut, Expr, ExprField, ExprPath, Field, Ident,
+    Member, Token, Type,
+};
 
 use yarte_dom::dom::{
     Attribute, Each, Element, ExprId, ExprOrText, Expression, IfBlock, IfElse, Node,
@@ -12,7 +15,6 @@ use yarte_helpers::calculate_hash;
 use super::state::{InsertPath, PathNode, PathStep};
 
 thread_local! {
-    static BB_TYPE: Type = parse2(quote!(<Self as App>::BlackBox)).unwrap();
     static SELF_ID: u64 = calculate_hash(&"self");
 }
 
@@ -23,7 +25,8 @@ pub fn get_self_id() -> u64 {
 
 #[inline]
 pub fn is_black_box(ty: &Type) -> bool {
-    BB_TYPE.with(|black| ty.eq(&black))
+    // Spans can't outlive the macro invocation, so the type isn't cached
+    ty.eq(&parse2::<Type>(quote!(<Self as App>::BlackBox)).unwrap())
 }
 
 #[inline]
@@ -43,35 +46,65 @@ pub fn is_on_attr(attr: &Attribute) -> Option<&str> {
     }
 }
 
-pub fn all_children_text<'a, I: Iterator<Item = &'a Node> + Clone>(mut doc: I) -> bool {
+/// Property of `bind:value` and `bind:checked` attributes
+pub fn is_bind_attr(attr: &Attribute) -> Option<&str> {
+    match &attr.name {
+        ExprOrText::Text(s) => s.strip_prefix("bind:"),
+        _ => None,
+    }
+}
+
+pub fn all_children_text<'a, I: Iterator<Item = &'a Node> + Clone>(doc: I) -> bool {
     !doc.clone()
         .all(|x| matches!(x, Node::Elem(Element::Text(_))))
-        && doc.all(|x| match x {
-            Node::Elem(Element::Text(_)) => true,
-            Node::Expr(e) => match e {
-                Expression::IfElse(_, block) => {
-                    let IfElse { ifs, if_else, els } = &**block;
-                    all_if_block_text(ifs)
-                        && if_else.iter().all(|x| all_if_block_text(x))
-                        && els
-                            .as_ref()
-                            .map(|x| all_children_text(x.iter()))
-                            .unwrap_or(true)
-                }
-                Expression::Each(_, block) => {
-                    let Each { body, .. } = &**block;
-                    all_children_text(body.iter())
-                }
-                Expression::Local(..) => false,
-                _ => true,
-            },
-            _ => false,
-        })
+        && all_inline(doc)
+}
+
+/// Text, expressions, locals and control flow with text bodies
+pub fn all_inline<'a, I: Iterator<Item = &'a Node>>(mut doc: I) -> bool {
+    doc.all(|x| match x {
+        Node::Elem(Element::Text(_)) => true,
+        Node::Expr(e) => match e {
+            Expression::IfElse(_, block) => {
+                let IfElse { ifs, if_else, els } = &**block;
+                all_if_block_text(ifs)
+                    && if_else.iter().all(|x| all_if_block_text(x))
+                    && els.as_ref().map(|x| all_inline(x.iter())).unwrap_or(true)
+            }
+            Expression::Each(_, block) => {
+                let Each { body, .. } = &**block;
+                all_inline(body.iter())
+            }
+            Expression::Component(..) => false,
+            _ => true,
+        },
+        _ => false,
+    })
 }
 
 #[inline]
 pub fn all_if_block_text(IfBlock { block, .. }: &IfBlock) -> bool {
-    all_children_text(block.iter())
+    all_inline(block.iter())
+}
+
+/// Nested application between white spaces
+pub fn is_mount(doc: &[Node]) -> bool {
+    let mut nodes = doc.iter().filter(
+        |x| !matches!(x, Node::Elem(Element::Text(t)) if t.chars().all(|x| x.is_whitespace())),
+    );
+    matches!(
+        (nodes.next(), nodes.next()),
+        (Some(Node::Expr(Expression::Component(..))), None)
+    )
+}
+
+/// Elements between white spaces
+pub fn all_elements(doc: &[Node]) -> bool {
+    doc.iter().all(|x| match x {
+        Node::Elem(Element::Node { .. }) => true,
+        Node::Elem(Element::Text(t)) => t.chars().all(|x| x.is_whitespace()),
+        _ => false,
+    })
 }
 
 pub fn check_attr_is_text(attr: Attribute) -> bool {
@@ -98,6 +131,56 @@ pub fn get_insert_point<'b, I: Iterator<Item = &'b Node>>(nodes: I) -> Vec<Inser
     insert
 }
 
+/// `t_root` number with the bits at `positions` of a group of `len` variables
+pub fn get_t_root_number<I: IntoIterator<Item = usize>>(
+    positions: I,
+    len: usize,
+    app: &Ident,
+) -> TokenStream {
+    let (ty, len) = get_t_root_type(len, app);
+    let mut bits = vec![false; len];
+    for i in positions {
+        bits[i] = true;
+    }
+    match len {
+        8 => {
+            let number = get_number_u8(bits);
+            quote!(#number)
+        }
+        16 => {
+            let number = get_number_u16(bits);
+            quote!(#number)
+        }
+        32 => {
+            let number = get_number_u32(&bits);
+            quote!(#number)
+        }
+        64 => {
+            let tokens = get_split_32(&bits);
+            quote!(yarte_wasm_app::U64([#tokens]))
+        }
+        128 => {
+            let tokens = get_split_32(&bits);
+            quote!(yarte_wasm_app::U128([#tokens]))
+        }
+        256 => {
+            let tokens = get_split_32(&bits);
+            quote!(yarte_wasm_app::U256([#tokens]))
+        }
+        _ => {
+            let groups: Vec<bool> = bits.chunks(32).map(|x| x.contains(&true)).collect();
+            let groups = get_split_32(&pad_32(groups));
+            let words = get_split_32(&bits);
+            quote!(#ty { groups: [#groups], words: [#words] })
+        }
+    }
+}
+
+fn pad_32(mut bits: Vec<bool>) -> Vec<bool> {
+    bits.resize(bits.len().div_ceil(32) * 32, false);
+    bits
+}
+
 #[inline]
 pub fn get_t_root_ident() -> Ident {
     const T_ROOT: &str = "t_root";
@@ -123,12 +206,114 @@ pub fn get_vdom_ident(id: ExprId) -> Ident {
 }
 
 #[inline]
-// TODO: multiple roots
 pub fn get_field_root_ident() -> Ident {
     const ROOT: &str = "__root";
     format_ident!("{}", ROOT)
 }
 
+/// Root elements of a multi root component, first one is `__root`
+#[inline]
+pub fn get_field_root_n_ident(n: usize) -> Ident {
+    if n == 0 {
+        get_field_root_ident()
+    } else {
+        format_ident!("{}{}", get_field_root_ident(), n)
+    }
+}
+
+#[inline]
+pub fn get_field_key_ident() -> Ident {
+    const KEY: &str = "__key";
+    format_ident!("{}", KEY)
+}
+
+#[inline]
+pub fn get_keyed_stable_ident() -> Ident {
+    const STABLE: &str = "__ykeyed_stable__";
+    format_ident!("{}", STABLE)
+}
+
+#[inline]
+pub fn get_field_key_hash_ident() -> Ident {
+    const KEY_HASH: &str = "__key_hash";
+    format_ident!("{}", KEY_HASH)
+}
+
+#[inline]
+pub fn get_key_eq_ident() -> Ident {
+    const KEY_EQ: &str = "__ykey_eq__";
+    format_ident!("{}", KEY_EQ)
+}
+
+/// Owned value of each key expression at `__key_value__` and its hash
+pub fn get_key_value(key: &syn::Expr) -> TokenStream {
+    let fhash = get_field_key_hash_ident();
+    quote! {
+        let __key_value__ = std::borrow::ToOwned::to_owned(&(#key));
+        let #fhash: u64 = {
+            let mut __hasher__ = std::collections::hash_map::DefaultHasher::new();
+            std::hash::Hash::hash(&__key_value__, &mut __hasher__);
+            std::hash::Hasher::finish(&__hasher__)
+        };
+    }
+}
+
+/// Key fields of each row, the owned key and its hash
+pub fn get_key_fields(key: &syn::Expr) -> TokenStream {
+    let fkey = get_field_key_ident();
+    let value = get_key_value(key);
+    quote! {
+        #value
+        let #fkey: Box<dyn std::any::Any> = Box::new(__key_value__);
+    }
+}
+
+/// Compares the stored key of a row with a new key
+///
+/// Hashes only select the candidate rows, keys are always compared
+pub fn get_key_eq() -> TokenStream {
+    let eq = get_key_eq_ident();
+    quote! {
+        fn #eq<K: Eq + 'static>(old: &dyn std::any::Any, new: &K) -> bool {
+            old.downcast_ref::<K>().map_or(false, |old| old == new)
+        }
+    }
+}
+
+/// Marks the longest increasing subsequence of reused rows, these rows don't move
+pub fn get_keyed_stable() -> TokenStream {
+    let stable = get_keyed_stable_ident();
+    quote! {
+        fn #stable(sources: &[usize]) -> Vec<bool> {
+            let mut stable = vec![false; sources.len()];
+            let mut prev = vec![usize::MAX; sources.len()];
+            let mut tails: Vec<usize> = vec![];
+            for (i, source) in sources.iter().enumerate() {
+                if *source == usize::MAX {
+                    continue;
+                }
+                let pos = match tails.binary_search_by(|t| sources[*t].cmp(source)) {
+                    Ok(pos) | Err(pos) => pos,
+                };
+                if 0 < pos {
+                    prev[i] = tails[pos - 1];
+                }
+                if pos == tails.len() {
+                    tails.push(i);
+                } else {
+                    tails[pos] = i;
+                }
+            }
+            let mut i = tails.last().copied().unwrap_or(usize::MAX);
+            while i != usize::MAX {
+                stable[i] = true;
+                i = prev[i];
+            }
+            stable
+        }
+    }
+}
+
 #[inline]
 pub fn get_component_ty_ident(id: ExprId) -> Ident {
     const TY: &str = "YComponent";
@@ -188,7 +373,10 @@ pub fn get_split_32(mut bits: &[bool]) -> Punctuated<syn::Expr, Token![,]> {
     buff
 }
 
-pub fn get_t_root_type(len: usize) -> (TokenStream, usize) {
+/// Type of the difference tree of `len` variables
+///
+/// Beyond 256 variables the type is defined by the application, see [`get_dirty_type`]
+pub fn get_t_root_type(len: usize, app: &Ident) -> (TokenStream, usize) {
     match len {
         0..=8 => (quote!(u8), 8),
         9..=16 => (quote!(u16), 16),
@@ -196,7 +384,107 @@ pub fn get_t_root_type(len: usize) -> (TokenStream, usize) {
         33..=64 => (quote!(yarte_wasm_app::U64), 64),
         65..=128 => (quote!(yarte_wasm_app::U128), 128),
         129..=256 => (quote!(yarte_wasm_app::U256), 256),
-        _ => todo!("more than 256 variables per context"),
+        _ => {
+            let words = len.div_ceil(32);
+            let ident = get_dirty_ident(app, words);
+            (quote!(#ident), words * 32)
+        }
+    }
+}
+
+#[inline]
+pub fn get_dirty_ident(app: &Ident, words: usize) -> Ident {
+    format_ident!("{}Dirty{}", app, words)
+}
+
+/// Difference tree of `words` words of 32 bits
+///
+/// Words are grouped by 32, the bit of a group is set when the word is not zero.
+/// So checks only visit the groups and the non zero words of both sides.
+pub fn get_dirty_type(app: &Ident, words: usize) -> TokenStream {
+    let ident = get_dirty_ident(app, words);
+    let groups = Literal::usize_unsuffixed(words.div_ceil(32));
+    let words = Literal::usize_unsuffixed(words);
+    quote! {
+        #[doc = "Difference tree, a bit of `groups` for each non zero word"]
+        #[derive(Clone, Copy)]
+        pub struct #ident {
+            groups: [u32; #groups],
+            words: [u32; #words],
+        }
+
+        impl #ident {
+            #[doc = "Number with the bit `i` set"]
+            pub fn bit(i: usize) -> Self {
+                let mut n = <Self as yarte_wasm_app::YNumber>::zero();
+                n.words[i / 32] = 1 << (i % 32);
+                n.groups[i / 1024] = 1 << (i / 32 % 32);
+                n
+            }
+        }
+
+        impl std::cmp::PartialEq for #ident {
+            fn eq(&self, other: &Self) -> bool {
+                self.groups[..] == other.groups[..] && self.words[..] == other.words[..]
+            }
+        }
+
+        impl yarte_wasm_app::YNumber for #ident {
+            #[inline(always)]
+            fn zero() -> Self {
+                #ident { groups: [0; #groups], words: [0; #words] }
+            }
+
+            #[inline(always)]
+            fn neq_zero(self) -> bool {
+                self.groups.iter().any(|x| *x != 0)
+            }
+        }
+
+        impl std::ops::BitOr for #ident {
+            type Output = Self;
+
+            fn bitor(mut self, rhs: Self) -> Self {
+                self |= rhs;
+                self
+            }
+        }
+
+        impl std::ops::BitOrAssign for #ident {
+            fn bitor_assign(&mut self, rhs: Self) {
+                for (g, group) in rhs.groups.iter().enumerate() {
+                    let mut group = *group;
+                    while group != 0 {
+                        let i = g * 32 + group.trailing_zeros() as usize;
+                        self.words[i] |= rhs.words[i];
+                        group &= group - 1;
+                    }
+                    self.groups[g] |= rhs.groups[g];
+                }
+            }
+        }
+
+        impl std::ops::BitAnd for #ident {
+            type Output = Self;
+
+            fn bitand(self, rhs: Self) -> Self {
+                let mut n = <Self as yarte_wasm_app::YNumber>::zero();
+                for (g, (a, b)) in self.groups.iter().zip(rhs.groups.iter()).enumerate() {
+                    let mut group = *a & *b;
+                    while group != 0 {
+                        let bit = group.trailing_zeros();
+                        let i = g * 32 + bit as usize;
+                        let word = self.words[i] & rhs.words[i];
+                        if word != 0 {
+                            n.words[i] = word;
+                            n.groups[g] |= 1 << bit;
+                        }
+                        group &= group - 1;
+                    }
+                }
+                n
+            }
+        }
     }
 }
 
@@ -233,9 +521,13 @@ pub fn get_steps<'b, I: Iterator<Item = &'b PathNode>>(
 
     let mut tokens = TokenStream::new();
     for (p, i, path) in buff.drain(..) {
-        tokens.extend(quote!(let #i = #p));
-        path.into_tokens(&mut tokens);
-        tokens.extend(quote!(;))
+        let mut steps = TokenStream::new();
+        path.into_tokens(&mut steps);
+        if steps.is_empty() {
+            // Node is the parent
+            steps.extend(quote!(.clone()));
+        }
+        tokens.extend(quote!(let #i = #p #steps;));
     }
 
     tokens
@@ -255,3 +547,31 @@ pub fn get_drop<I: Iterator<Item = Ident>>(component: &Ident, roots: I) -> Token
         }
     }
 }
+
+/// Replace `self.field` by `field`
+pub fn strip_self(expr: &Expr) -> TokenStream {
+    struct StripSelf;
+
+    impl VisitMut for StripSelf {
+        fn visit_expr_mut(&mut self, i: &mut Expr) {
+            if let Expr::Field(ExprField {
+                base,
+                member: Member::Named(ident),
+                ..
+            }) = i
+            {
+                if let Expr::Path(ExprPath { path, .. }) = &**base {
+                    if path.is_ident("self") {
+                        *i = parse2(quote!(#ident)).unwrap();
+                        return;
+                    }
+                }
+            }
+            syn::visit_mut::visit_expr_mut(self, i);
+        }
+    }
+
+    let mut expr = expr.clone();
+    StripSelf.visit_expr_mut(&mut expr);
+    quote!(#expr)
+}
@@ -12,6 +12,7 @@ pub mod server {
     pub struct WASMCodeGen<'a> {
         s: &'a Struct<'a>,
         buf: &'a syn::Expr,
+        errors: Vec<String>,
     }
 
     impl<'a> EachCodeGen for WASMCodeGen<'a> {}
@@ -19,14 +20,31 @@ pub mod server {
 
     impl<'a> WASMCodeGen<'a> {
         pub fn new<'n>(s: &'n Struct<'n>, buf: &'n syn::Expr) -> WASMCodeGen<'n> {
-            WASMCodeGen { s, buf }
+            WASMCodeGen {
+                s,
+                buf,
+                errors: vec![],
+            }
         }
     }
 
     impl<'a> CodeGen for WASMCodeGen<'a> {
         fn gen(&mut self, ir: Vec<HIR>) -> TokenStream {
-            let ir = to_wasmfmt(ir, self.s).expect("html");
-            HTMLMinBytesCodeGen::new(self.buf).gen(ir)
+            let ir = match to_wasmfmt(ir, self.s) {
+                Ok(ir) => ir,
+                Err(e) => {
+                    self.errors.extend(e.iter().map(ToString::to_string));
+                    return TokenStream::new();
+                }
+            };
+            let mut codegen = HTMLMinBytesCodeGen::new(self.buf, Default::default());
+            let tokens = codegen.gen(ir);
+            self.errors.extend(codegen.take_errors());
+            tokens
+        }
+
+        fn take_errors(&mut self) -> Vec<String> {
+            std::mem::take(&mut self.errors)
         }
     }
 }
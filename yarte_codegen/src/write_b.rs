@@ -38,4 +38,8 @@ impl<T: CodeGen> CodeGen for WriteBCodeGen<T> {
             }
         }
     }
+
+    fn take_errors(&mut self) -> Vec<String> {
+        self.codegen.take_errors()
+    }
 }
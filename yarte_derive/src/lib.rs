@@ -1,4 +1,5 @@
 #![allow(unused_imports, dead_code)]
+#![cfg_attr(yarte_tracked_path, feature(proc_macro_tracked_path))]
 use std::{
     collections::{BTreeMap, BTreeSet},
     iter,
@@ -13,11 +14,13 @@ use syn::spanned::Spanned;
 
 use yarte_codegen::{CodeGen, FmtCodeGen, HTMLCodeGen, TextCodeGen};
 use yarte_helpers::{
-    config::{get_source, read_config_file, Config, PrintConfig},
+    config::{config_file_path, get_config, get_source, Config, PrintConfig},
     logger::log,
 };
-use yarte_hir::{generate, visit_derive, HIROptions, Print, Struct};
-use yarte_parser::{emitter, parse, parse_partials, source_map, Partial};
+use yarte_hir::{generate, visit_derive, HIROptions, Print, Struct, HIR};
+use yarte_parser::{
+    emitter, parse, parse_cached, parse_partials, source_map, ErrorMessage, Partial,
+};
 
 #[cfg(feature = "json")]
 mod ser_json;
@@ -25,9 +28,12 @@ mod ser_json;
 type Sources<'a> = &'a BTreeMap<PathBuf, String>;
 
 macro_rules! build {
-    ($i:ident, $codegen:ident, $opt:expr) => {{
-        let config_toml: &str = &read_config_file();
-        let config = &Config::new(config_toml);
+    ($i:ident, $codegen:ident, $opt:expr) => {
+        build!($i, $codegen, $opt, false)
+    };
+    ($i:ident, $codegen:ident, $opt:expr, $validate:expr) => {{
+        let config_file = get_config();
+        let config = config_file.get();
         let s = &match visit_derive($i, config) {
             Ok(s) => s,
             Err(ts) => return ts.into(),
@@ -35,7 +41,7 @@ macro_rules! build {
         proc_macro2::fallback::force();
         let sources = &read(s.path.clone(), s.src.clone(), config);
 
-        sources_to_tokens(sources, config, s, $codegen(s), $opt)
+        sources_to_tokens(sources, config, s, $codegen(s), $opt, $validate, true)
     }};
 }
 
@@ -117,12 +123,15 @@ pub fn template_bytes(input: TokenStream) -> TokenStream {
     let buf_i = format_ident!("bytes_mut");
     let buf: syn::Expr = syn::parse2(quote!(#buf_i)).unwrap();
     let get_codegen = |s| {
-        Box::new(yarte_codegen::BytesCodeGen::new(
-            yarte_codegen::TextBytesCodeGen::new(&buf),
-            s,
-            buf_i,
-            PARENT,
-        ))
+        Box::new(
+            yarte_codegen::BytesCodeGen::new(
+                yarte_codegen::TextBytesCodeGen::new(&buf),
+                s,
+                buf_i,
+                PARENT,
+            )
+            .text(),
+        )
     };
 
     let i = &syn::parse(input).unwrap();
@@ -131,6 +140,7 @@ pub fn template_bytes(input: TokenStream) -> TokenStream {
         get_codegen,
         HIROptions {
             is_text: true,
+            cache: true,
             ..Default::default()
         }
     )
@@ -154,77 +164,195 @@ pub fn template_html_bytes(input: TokenStream) -> TokenStream {
         ))
     };
     let i = &syn::parse(input).unwrap();
-    build!(i, get_codegen, Default::default()).into()
+    build!(
+        i,
+        get_codegen,
+        HIROptions {
+            cache: true,
+            ..Default::default()
+        }
+    )
+    .into()
 }
 
 #[proc_macro_derive(TemplateFixedMin, attributes(template))]
 #[cfg(all(feature = "html-min", feature = "fixed"))]
-/// # Work in Progress
 /// Implements TemplateTrait with html minifier
+///
+/// Output is configured by `[minify]` section of `yarte.toml`
 pub fn template_html_min_ptr(input: TokenStream) -> TokenStream {
     const PARENT: &str = "yarte";
     fn get_codegen<'a>(s: &'a Struct) -> Box<dyn CodeGen + 'a> {
-        Box::new(yarte_codegen::FixedCodeGen::new(
-            yarte_codegen::HTMLMinFixedCodeGen(PARENT),
-            s,
-            PARENT,
-        ))
+        if s.mode.is_minifiable() {
+            Box::new(yarte_codegen::FixedCodeGen::new(
+                yarte_codegen::HTMLMinFixedCodeGen::new(PARENT, s.minify),
+                s,
+                PARENT,
+            ))
+        } else {
+            Box::new(yarte_codegen::FixedCodeGen::new(
+                yarte_codegen::HTMLFixedCodeGen(PARENT),
+                s,
+                PARENT,
+            ))
+        }
     }
     let i = &syn::parse(input).unwrap();
-    build!(i, get_codegen, Default::default()).into()
+    build!(i, get_codegen, Default::default(), true).into()
 }
 
 #[proc_macro_derive(TemplateBytesMin, attributes(template))]
 #[cfg(all(feature = "html-min", feature = "bytes-buf"))]
-/// # Work in Progress
 /// Implements TemplateTrait with html minifier
+///
+/// Output is configured by `[minify]` section of `yarte.toml`
 pub fn template_html_min_bytes(input: TokenStream) -> TokenStream {
     const PARENT: &str = "yarte";
 
     let buf_i = format_ident!("bytes_mut");
     let buf: syn::Expr = syn::parse2(quote!(#buf_i)).unwrap();
-    let get_codegen = |s| {
+    let get_codegen = |s| min_bytes_codegen(s, &buf, buf_i, PARENT);
+    let i = &syn::parse(input).unwrap();
+    build!(
+        i,
+        get_codegen,
+        HIROptions {
+            cache: true,
+            ..Default::default()
+        },
+        true
+    )
+    .into()
+}
+
+#[cfg(all(feature = "html-min", feature = "bytes-buf"))]
+fn min_bytes_codegen<'a>(
+    s: &'a Struct,
+    buf: &'a syn::Expr,
+    buf_i: syn::Ident,
+    parent: &'static str,
+) -> Box<dyn CodeGen + 'a> {
+    if s.mode.is_minifiable() {
         Box::new(yarte_codegen::BytesCodeGen::new(
-            yarte_codegen::HTMLMinBytesCodeGen::new(&buf),
+            yarte_codegen::HTMLMinBytesCodeGen::new(buf, s.minify),
             s,
             buf_i,
-            PARENT,
+            parent,
         ))
-    };
-    let i = &syn::parse(input).unwrap();
-    build!(i, get_codegen, Default::default()).into()
+    } else {
+        Box::new(yarte_codegen::BytesCodeGen::new(
+            yarte_codegen::HTMLBytesCodeGen::new(buf),
+            s,
+            buf_i,
+            parent,
+        ))
+    }
 }
 
 #[proc_macro_derive(TemplateMin, attributes(template))]
 #[cfg(feature = "html-min")]
-/// # Work in Progress
 /// Implements TemplateTrait with html minifier
+///
+/// Output is configured by `[minify]` section of `yarte.toml`
 pub fn template_html_min(input: TokenStream) -> TokenStream {
     fn get_codegen<'a>(s: &'a Struct) -> Box<dyn CodeGen + 'a> {
-        Box::new(FmtCodeGen::new(yarte_codegen::HTMLMinCodeGen, s, "yarte"))
+        if s.mode.is_minifiable() {
+            Box::new(FmtCodeGen::new(
+                yarte_codegen::HTMLMinCodeGen::new(s.minify),
+                s,
+                "yarte",
+            ))
+        } else {
+            Box::new(FmtCodeGen::new(HTMLCodeGen, s, "yarte"))
+        }
     }
     let i = &syn::parse(input).unwrap();
-    build!(i, get_codegen, Default::default()).into()
+    build!(i, get_codegen, Default::default(), true).into()
 }
 
 // TODO:
 #[proc_macro_derive(App, attributes(template, msg, inner))]
 #[cfg(feature = "wasm-app")]
+/// Implements App trait
+///
+/// With `wasm-server` feature and `script` or `mount` attribute the `<App>InitialState`
+/// also implements `TemplateBytesTrait` out of wasm targets, rendering the same template
+///
+/// With `wasm-component` feature `{{> @Path field = expr, forward = path }}` partials
+/// mount the application `Path` in its parent element, the fields are set when
+/// its expressions change and `forward: fn(&Path::Message) -> Option<Message>`
+/// sends the messages of the nested application to the parent
 pub fn app(input: TokenStream) -> TokenStream {
-    fn get_codegen<'a>(s: &'a Struct) -> Box<dyn CodeGen + 'a> {
-        Box::new(yarte_codegen::client::WASMCodeGen::new(s))
-    }
     let i = &syn::parse(input).unwrap();
-    let config_toml: &str = &read_config_file();
-    let config = &Config::new(config_toml);
+    let config_file = get_config();
+    let config = config_file.get();
     let s = &match visit_derive(i, config) {
         Ok(s) => s,
         Err(tt) => return tt.into(),
     };
     // TODO: proc_macro2::fallback::force cause mismatch()
     let sources = &read(s.path.clone(), s.src.clone(), config);
+    let opt = HIROptions {
+        components: cfg!(feature = "wasm-component"),
+        ..Default::default()
+    };
+
+    #[cfg(feature = "wasm-server")]
+    {
+        if s.is_server_rendered() {
+            let state = format_ident!("{}InitialState", s.ident);
+            let state = &s.with_ident(&state);
+            let buf_i = format_ident!("bytes_mut");
+            let buf: syn::Expr = syn::parse2(quote!(#buf_i)).unwrap();
+            let codegen = Box::new(AppCodeGen {
+                client: yarte_codegen::client::WASMCodeGen::new(s),
+                server: yarte_codegen::BytesCodeGen::new(
+                    yarte_codegen::server::WASMCodeGen::new(state, &buf),
+                    state,
+                    buf_i,
+                    "yarte",
+                ),
+            });
+            return sources_to_tokens(sources, config, s, codegen, opt, false, false).into();
+        }
+    }
+
+    sources_to_tokens(
+        sources,
+        config,
+        s,
+        Box::new(yarte_codegen::client::WASMCodeGen::new(s)),
+        opt,
+        false,
+        false,
+    )
+    .into()
+}
 
-    sources_to_tokens(sources, config, s, get_codegen(s), Default::default()).into()
+/// Client application and server side rendering of its initial state
+#[cfg(all(feature = "wasm-app", feature = "wasm-server"))]
+struct AppCodeGen<C: CodeGen, S: CodeGen> {
+    client: C,
+    server: S,
+}
+
+#[cfg(all(feature = "wasm-app", feature = "wasm-server"))]
+impl<C: CodeGen, S: CodeGen> CodeGen for AppCodeGen<C, S> {
+    fn gen(&mut self, ir: Vec<HIR>) -> proc_macro2::TokenStream {
+        let client = self.client.gen(ir.clone());
+        let server = self.server.gen(ir);
+        quote! {
+            #client
+            #[cfg(not(target_arch = "wasm32"))]
+            const _: () = { #server };
+        }
+    }
+
+    fn take_errors(&mut self) -> Vec<String> {
+        let mut errors = self.client.take_errors();
+        errors.extend(self.server.take_errors());
+        errors
+    }
 }
 
 // TODO:
@@ -248,10 +376,10 @@ pub fn template_wasm_server(input: TokenStream) -> TokenStream {
         ))
     };
     let i = &syn::parse(input).unwrap();
-    build!(i, get_codegen, Default::default()).into()
+    build!(i, get_codegen, Default::default(), true).into()
 }
 
-#[proc_macro_derive(Serialize)]
+#[proc_macro_derive(Serialize, attributes(serialize))]
 #[cfg(feature = "json")]
 pub fn serialize_json(i: TokenStream) -> TokenStream {
     let i = syn::parse(i).unwrap();
@@ -308,6 +436,8 @@ pub fn yformat(i: TokenStream) -> TokenStream {
             resolve_to_self: false,
             is_text: true,
             parent: PARENT,
+            cache: false,
+            components: false,
         }
     )
     .into()
@@ -405,6 +535,8 @@ pub fn ywrite(i: TokenStream) -> TokenStream {
             resolve_to_self: false,
             is_text: true,
             parent: PARENT,
+            cache: false,
+            components: false,
         }
     )
     .into()
@@ -481,9 +613,9 @@ pub fn ywrite_min(i: TokenStream) -> TokenStream {
         }
     }
 
-    let get_codegen = |_| {
+    let get_codegen = |s: &Struct| {
         Box::new(yarte_codegen::WriteBCodeGen::new(
-            yarte_codegen::HTMLMinBytesCodeGen::new(&buf),
+            yarte_codegen::HTMLMinBytesCodeGen::new(&buf, s.minify),
             PARENT,
         ))
     };
@@ -501,7 +633,8 @@ pub fn ywrite_min(i: TokenStream) -> TokenStream {
             resolve_to_self: false,
             parent: PARENT,
             ..Default::default()
-        }
+        },
+        true
     )
     .into()
 }
@@ -588,12 +721,29 @@ fn sources_to_tokens<'a>(
     s: &'a Struct<'a>,
     mut codegen: Box<dyn CodeGen + 'a>,
     opt: HIROptions,
+    validate: bool,
+    cached: bool,
 ) -> proc_macro2::TokenStream {
+    if cached {
+        source_map::evict();
+    }
+    // Cached nodes outlive this invocation, only with fallback spans
+    let kept: BTreeMap<_, _> = sources
+        .iter()
+        .filter(|_| cached)
+        .map(|(p, src)| match parse_cached(p, src) {
+            Ok(kept) => (p, kept),
+            Err(e) => emitter(sources, config, iter::once(e)),
+        })
+        .collect();
     let mut parsed = BTreeMap::new();
     for (p, src) in sources {
-        let nodes = match parse(source_map::get_cursor(p, src)) {
-            Ok(n) => n,
-            Err(e) => emitter(sources, config, iter::once(e)),
+        let nodes = match kept.get(p) {
+            Some(kept) => kept.nodes().to_vec(),
+            None => match parse(source_map::get_cursor(p, src)) {
+                Ok(n) => n,
+                Err(e) => emitter(sources, config, iter::once(e)),
+            },
         };
         parsed.insert(p, nodes);
     }
@@ -606,12 +756,43 @@ fn sources_to_tokens<'a>(
         eprintln!("{:?}\n", parsed);
     }
 
+    let is_text = opt.is_text;
+    let is_item = opt.resolve_to_self;
     let hir = generate(config, s, &parsed, opt)
         .unwrap_or_else(|e| emitter(sources, config, e.into_iter()));
-    // when multiple templates
-    source_map::clean();
 
+    if s.mode.is_minifiable() && !is_text && (validate || s.strict) {
+        let errors = yarte_dom::validate(config, s, &parsed);
+        if !errors.is_empty() {
+            emitter(sources, config, errors.into_iter());
+        }
+    }
     let tokens = codegen.gen(hir);
+    let errors = codegen.take_errors();
+    if !errors.is_empty() {
+        // Lowered html has no template spans, point to the start of the template
+        let at = source_map::get_cursor(&s.path, &sources[&s.path]).off;
+        emitter(
+            sources,
+            config,
+            errors.into_iter().map(|message| ErrorMessage {
+                message,
+                span: source_map::Span { lo: at, hi: at },
+            }),
+        );
+    }
+    // when multiple templates
+    if !cached {
+        source_map::clean();
+    }
+
+    let track = track(sources, config);
+    // function-like macros expand to a block expression
+    let tokens = if is_item {
+        quote!(#tokens #track)
+    } else {
+        quote!({ #track #tokens })
+    };
 
     if cfg!(debug_assertions) && config.print_override == PrintConfig::Code
         || config.print_override == PrintConfig::All
@@ -628,24 +809,64 @@ fn sources_to_tokens<'a>(
     tokens
 }
 
+/// Hidden `include_bytes!` of every file read, so the compiler tracks them
+///
+/// Missed paths of the lookups don't exist, only `--cfg yarte_tracked_path` in nightly tracks them
+fn track(sources: Sources, config: &Config) -> proc_macro2::TokenStream {
+    let missed = config.take_missed();
+    #[cfg(yarte_tracked_path)]
+    for path in &missed {
+        proc_macro::tracked::path(path.to_str().unwrap());
+    }
+    #[cfg(not(yarte_tracked_path))]
+    drop(missed);
+
+    let files = sources
+        .keys()
+        .cloned()
+        .chain(config.take_files())
+        .chain(iter::once(config_file_path()))
+        .filter(|path| path.is_file())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(|path| {
+            let path = path.to_str().unwrap();
+            quote!(
+                const _: &[u8] = include_bytes!(#path);
+            )
+        });
+
+    quote!(#(#files)*)
+}
+
 fn read(path: PathBuf, src: String, config: &Config) -> BTreeMap<PathBuf, String> {
     let mut stack = vec![(path, src)];
     let mut visited = BTreeMap::new();
 
     while let Some((path, src)) = stack.pop() {
-        let partials = parse_partials(&src);
+        let partials = parse_partials(source_map::get_cursor(&path, &src));
 
         let partials = match partials {
             Ok(n) => n
                 .iter()
-                .map(|Partial(_, partial, _)| config.resolve_partial(&path, partial.t()))
-                .collect::<BTreeSet<_>>(),
+                // Nested applications aren't files
+                .filter(|Partial(_, partial, _)| !partial.t().starts_with('@'))
+                .map(|Partial(_, partial, _)| {
+                    config
+                        .resolve_partial(&path, partial.t())
+                        .map_err(|message| ErrorMessage {
+                            message,
+                            span: partial.span(),
+                        })
+                })
+                .collect::<Result<BTreeSet<_>, _>>(),
             Err(e) => {
                 visited.insert(path, src);
                 emitter(&visited, config, iter::once(e))
             }
         };
         visited.insert(path, src);
+        let partials = partials.unwrap_or_else(|e| emitter(&visited, config, iter::once(e)));
 
         for partial in partials {
             if !visited.contains_key(&partial) {
@@ -2,7 +2,10 @@
 
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::{quote, ToTokens};
-use syn::{Data, DataStruct, DeriveInput, Fields, FieldsNamed, FieldsUnnamed, Variant};
+use syn::{
+    Attribute, Data, DataStruct, DeriveInput, Fields, FieldsNamed, FieldsUnnamed, Meta, NestedMeta,
+    Variant,
+};
 use v_jsonescape::escape;
 
 struct StrT(String);
@@ -33,6 +36,72 @@ impl ToTokens for StrT {
 }
 
 pub(crate) fn serialize_json(i: syn::DeriveInput) -> TokenStream {
+    let adaptive = match is_adaptive(&i.attrs) {
+        Ok(adaptive) => adaptive,
+        Err(e) => return e.to_compile_error(),
+    };
+    let to_bytes = if adaptive {
+        // Start with the capacity learnt from previous serializations
+        quote! {
+            fn to_bytes<B: yarte::Buffer + Sized>(&self, capacity: usize) -> B::Freeze {
+                let mut buf: B =
+                    yarte::Buffer::with_capacity(capacity.max(__YARTE_SIZE.with(|v| v.get())));
+                self.to_bytes_mut(&mut buf);
+                let len = yarte::Buffer::len(&buf);
+                __YARTE_SIZE.with(|v| v.set(yarte::adapt_size(v.get(), len)));
+                yarte::Buffer::freeze(buf)
+            }
+        }
+    } else {
+        quote!()
+    };
+    let tokens = serialize_impl(i, to_bytes);
+    if adaptive {
+        quote! {
+            const _: () = {
+                thread_local! {
+                    static __YARTE_SIZE: std::cell::Cell<usize> = std::cell::Cell::new(0);
+                }
+                #tokens
+            };
+        }
+    } else {
+        tokens
+    }
+}
+
+/// `#[serialize(adaptive)]`
+fn is_adaptive(attrs: &[Attribute]) -> syn::Result<bool> {
+    let mut adaptive = false;
+    for attr in attrs.iter().filter(|x| x.path.is_ident("serialize")) {
+        match attr.parse_meta()? {
+            Meta::List(list) => {
+                for nested in &list.nested {
+                    match nested {
+                        NestedMeta::Meta(Meta::Path(path)) if path.is_ident("adaptive") => {
+                            adaptive = true
+                        }
+                        _ => {
+                            return Err(syn::Error::new_spanned(
+                                nested,
+                                "invalid attribute, expected `adaptive`",
+                            ))
+                        }
+                    }
+                }
+            }
+            meta => {
+                return Err(syn::Error::new_spanned(
+                    meta,
+                    "expected `#[serialize(adaptive)]`",
+                ))
+            }
+        }
+    }
+    Ok(adaptive)
+}
+
+fn serialize_impl(i: syn::DeriveInput, to_bytes: TokenStream) -> TokenStream {
     match i {
         // Unnamed struct
         DeriveInput {
@@ -48,6 +117,7 @@ pub(crate) fn serialize_json(i: syn::DeriveInput) -> TokenStream {
             if unnamed.len() == 1 {
                 quote! {
                     impl #generics yarte::Serialize for #ident #generics {
+                        #to_bytes
                         fn to_bytes_mut<B: yarte::Buffer>(&self, buf: &mut B) {
                             self.0.to_bytes_mut(buf)
                         }
@@ -62,6 +132,7 @@ pub(crate) fn serialize_json(i: syn::DeriveInput) -> TokenStream {
                     .collect();
                 quote! {
                     impl #generics yarte::Serialize for #ident #generics {
+                        #to_bytes
                         fn to_bytes_mut<B: yarte::Buffer>(&self, buf: &mut B) {
                             use yarte::*;
                             yarte::begin_array(buf);
@@ -105,6 +176,7 @@ pub(crate) fn serialize_json(i: syn::DeriveInput) -> TokenStream {
             let keys = keys.into_iter().map(StrT);
             quote! {
                 impl #generics yarte::Serialize for #ident #generics {
+                    #to_bytes
                     #[inline]
                     fn to_bytes_mut<B: yarte::Buffer>(&self, buf: &mut B) {
                         use yarte::*;
@@ -273,6 +345,7 @@ pub(crate) fn serialize_json(i: syn::DeriveInput) -> TokenStream {
 
             quote! {
                 impl #generics yarte::Serialize for #ident #generics {
+                    #to_bytes
                     #[inline]
                     fn to_bytes_mut<B: yarte::Buffer>(&self, buf: &mut B) {
                         use yarte::*;
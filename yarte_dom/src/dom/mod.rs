@@ -8,7 +8,8 @@ use std::{
 use markup5ever::{namespace_url, ns, LocalName};
 use syn::parse_str;
 
-use yarte_hir::{Each as HEach, IfElse as HIfElse, HIR};
+use yarte_helpers::calculate_hash;
+use yarte_hir::{Component as HComponent, Each as HEach, IfElse as HIfElse, EVENT_ARGS, HIR};
 use yarte_html::{
     interface::{QualName, YName},
     tree_builder::{get_marquee, is_marquee},
@@ -52,6 +53,16 @@ pub enum Expression {
     Each(ExprId, Box<Each>),
     IfElse(ExprId, Box<IfElse>),
     Local(ExprId, VarId, Box<syn::Local>),
+    Component(ExprId, Box<Component>),
+}
+
+/// Nested application
+#[derive(Debug, PartialEq)]
+pub struct Component {
+    pub path: syn::Path,
+    /// Fields of the application with the variables of its expression
+    pub props: Vec<(syn::Ident, syn::Expr, Vec<VarId>)>,
+    pub forward: Option<syn::Expr>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -76,6 +87,7 @@ pub struct Each {
     pub args: syn::Expr,
     pub body: Document,
     pub expr: syn::Expr,
+    pub key: Option<syn::Expr>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -163,7 +175,11 @@ impl DOMBuilder {
 
     fn init(&mut self, ir: Vec<HIR>) -> ParseResult<Document> {
         let (ir, html) = self.generate_html(ir);
-        self.serialize(parse_document(&html)?, ir)
+        let sink = match parse_document(&html) {
+            Ok(sink) => sink,
+            Err(_) => parse_fragment(&html)?,
+        };
+        self.serialize(sink, ir)
     }
 
     fn step(&mut self, ir: Vec<HIR>) -> ParseResult<Document> {
@@ -197,7 +213,7 @@ impl DOMBuilder {
                 }
             }
             Some(ParseElement::Text(s)) => vec![self.resolve_text(s)],
-            None => vec![],
+            Some(ParseElement::Comment(_)) | None => vec![],
         };
 
         assert!(ir.next().is_none());
@@ -262,7 +278,13 @@ impl DOMBuilder {
                 let var = resolve_expr(&msg, self);
                 let id = self.count;
                 self.count += 1;
-                self.tree_map.insert(id, var.into_iter().collect());
+                // Event values aren't variables
+                self.tree_map.insert(
+                    id,
+                    var.into_iter()
+                        .filter(|x| !EVENT_ARGS.iter().any(|a| calculate_hash(a) == *x))
+                        .collect(),
+                );
 
                 return Ok(Attribute {
                     name,
@@ -279,9 +301,10 @@ impl DOMBuilder {
         }
         let mut value = vec![];
         for chunk in chunks {
-            if HASH_LEN < chunk.len() && &chunk[..2] == "0x" {
+            if HASH_LEN <= chunk.len() && &chunk[..2] == "0x" {
                 if let Ok(id) = u32::from_str_radix(&chunk[2..HASH_LEN], 16).map(|x| x as usize) {
-                    if self.tree_map.contains_key(&id) {
+                    // Generated and unresolved marks
+                    if id < self.count && !self.tree_map.contains_key(&id) {
                         value.push(ExprOrText::Expr(self.resolve_expr(id, ir)?));
                         if !&chunk[HASH_LEN..].is_empty() {
                             value.push(ExprOrText::Text(chunk[HASH_LEN..].into()))
@@ -323,7 +346,12 @@ impl DOMBuilder {
             }
             HIR::Each(e) => {
                 let var = resolve_each(&e, id, self);
-                let HEach { args, body, expr } = *e;
+                let HEach {
+                    args,
+                    body,
+                    expr,
+                    key,
+                } = *e;
                 Ok(Expression::Each(
                     id,
                     Box::new(Each {
@@ -331,6 +359,7 @@ impl DOMBuilder {
                         args,
                         body: self.step(body)?,
                         expr,
+                        key,
                     }),
                 ))
             }
@@ -369,7 +398,37 @@ impl DOMBuilder {
                     }),
                 ))
             }
-            HIR::Lit(_) => unreachable!(),
+            HIR::Component(e) => {
+                let HComponent {
+                    path,
+                    props,
+                    forward,
+                } = *e;
+                let props: Vec<_> = props
+                    .into_iter()
+                    .map(|(ident, expr)| {
+                        let vars = resolve_expr(&expr, self);
+                        (ident, expr, vars)
+                    })
+                    .collect();
+                self.tree_map.insert(
+                    id,
+                    props
+                        .iter()
+                        .flat_map(|(_, _, vars)| vars.iter().copied())
+                        .collect(),
+                );
+                Ok(Expression::Component(
+                    id,
+                    Box::new(Component {
+                        path,
+                        props,
+                        forward,
+                    }),
+                ))
+            }
+            // Only bytes templates lower cache blocks
+            HIR::Lit(_) | HIR::Cache(_) => unreachable!(),
         }
     }
 
@@ -419,6 +478,11 @@ impl DOMBuilder {
                     children,
                     ..
                 } => buff.push(self.resolve_node(name, attrs, children, sink, ir)?),
+                ParseElement::Comment(s) => {
+                    if s.contains(MARK) {
+                        panic!("expressions in html comments are not supported")
+                    }
+                }
                 ParseElement::Document(_) => unreachable!(),
             }
         }
@@ -426,3 +490,69 @@ impl DOMBuilder {
         Ok(buff)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn attrs(ir: Vec<HIR>) -> Vec<Attribute> {
+        match DOM::from(ir).doc.pop() {
+            Some(Node::Elem(Element::Node { attrs, .. })) => attrs,
+            _ => panic!("element"),
+        }
+    }
+
+    fn expr(src: &str) -> Box<syn::Expr> {
+        Box::new(parse_str(src).unwrap())
+    }
+
+    #[test]
+    fn test_attribute_expression() {
+        // The mark is the whole value
+        let attrs = attrs(vec![
+            HIR::Lit("<input value=\"".into()),
+            HIR::Safe(expr("self.a")),
+            HIR::Lit("\">".into()),
+        ]);
+        assert_eq!(
+            attrs,
+            vec![Attribute {
+                name: ExprOrText::Text("value".into()),
+                value: vec![ExprOrText::Expr(Expression::Safe(0, expr("self.a")))],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_attribute_expressions() {
+        let attrs = attrs(vec![
+            HIR::Lit("<div class=\"".into()),
+            HIR::Safe(expr("self.a")),
+            HIR::Safe(expr("self.b")),
+            HIR::Lit(" c\"></div>".into()),
+        ]);
+        assert_eq!(
+            attrs[0].value,
+            vec![
+                ExprOrText::Expr(Expression::Safe(0, expr("self.a"))),
+                ExprOrText::Expr(Expression::Safe(1, expr("self.b"))),
+                ExprOrText::Text(" c".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_attribute_literal_mark() {
+        // Marks in the source which weren't generated aren't expressions
+        let attrs = attrs(vec![
+            HIR::Lit("<div class=\"yartehashhtmlexpressionsattt0x00000005\" id=\"".into()),
+            HIR::Safe(expr("self.a")),
+            HIR::Lit("\"></div>".into()),
+        ]);
+        assert!(matches!(attrs[0].value[..], [ExprOrText::Text(_)]));
+        assert_eq!(
+            attrs[1].value,
+            vec![ExprOrText::Expr(Expression::Safe(0, expr("self.a")))]
+        );
+    }
+}
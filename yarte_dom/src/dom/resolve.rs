@@ -120,18 +120,29 @@ impl<'a> ResolveIf<'a> {
     }
 
     fn resolve(mut self, expr: &'a Expr) -> Vec<VarId> {
-        self.visit_expr(expr);
+        if let Expr::Let(_) = expr {
+            self.visit_expr(expr);
+        } else {
+            self.add_condition(expr);
+        }
         self.buff
     }
+
+    /// Conditions of all blocks are registered in the same expression
+    fn add_condition(&mut self, expr: &'a Expr) {
+        let vars = resolve_expr(expr, self.builder);
+        self.builder
+            .tree_map
+            .entry(self.id)
+            .or_default()
+            .extend(vars);
+    }
 }
 
 impl<'a> Visit<'a> for ResolveIf<'a> {
     fn visit_expr_let(&mut self, ExprLet { pat, expr, .. }: &'a ExprLet) {
         self.visit_pat(pat);
-        let vars = resolve_expr(expr, self.builder);
-        self.builder
-            .tree_map
-            .insert(self.id, vars.into_iter().collect());
+        self.add_condition(expr);
     }
 
     fn visit_field_pat(&mut self, FieldPat { pat, .. }: &'a FieldPat) {
@@ -1,7 +1,10 @@
-use markup5ever::{local_name, namespace_url, ns};
+use std::convert::TryFrom;
+
+use markup5ever::{local_name, namespace_url, ns, LocalName};
 use quote::quote;
 use syn::parse2;
 
+use yarte_helpers::{calculate_hash, config::MinifyOption};
 use yarte_hir::{Each as HEach, IfElse as HIfElse, Struct, HIR};
 use yarte_html::{
     interface::{QualName, YName},
@@ -12,19 +15,44 @@ use yarte_html::{
 
 use crate::{
     serialize::serialize,
-    sink::{parse_document, parse_fragment, ParseAttribute, ParseElement, ParseResult, Sink},
+    sink::{
+        parse_document, parse_fragment, ParseAttribute, ParseElement, ParseError, ParseNodeId,
+        ParseResult, Sink,
+    },
 };
 
 pub struct DOMFmt(pub Vec<HIR>);
 
-// TODO: to try from
-impl From<Vec<HIR>> for DOMFmt {
-    fn from(ir: Vec<HIR>) -> Self {
-        DOMFmt(to_domfmt_init(ir).expect("correct html"))
+impl DOMFmt {
+    /// Minify with `[minify]` options
+    pub fn new(ir: Vec<HIR>, minify: MinifyOption) -> ParseResult<Self> {
+        to_domfmt_init(ir, serializer_opt(minify)).map(DOMFmt)
     }
 }
 
-const HASH: &str = "0x00000000";
+impl TryFrom<Vec<HIR>> for DOMFmt {
+    type Error = Vec<ParseError>;
+
+    fn try_from(ir: Vec<HIR>) -> ParseResult<Self> {
+        DOMFmt::new(ir, Default::default())
+    }
+}
+
+fn serializer_opt(minify: MinifyOption) -> SerializerOpt {
+    SerializerOpt {
+        wasm: false,
+        remove_comments: minify.remove_comments,
+        keep_conditional_comments: minify.keep_conditional_comments,
+        omit_closing_tags: minify.omit_closing_tags,
+        remove_attr_quotes: minify.remove_attr_quotes,
+        collapse_boolean_attrs: minify.collapse_boolean_attrs,
+        remove_default_attrs: minify.remove_default_attrs,
+        minify_css: minify.minify_css,
+        minify_js: minify.minify_js,
+    }
+}
+
+pub(crate) const HASH: &str = "0x00000000";
 
 fn get_html(ir: &[HIR]) -> String {
     let mut html = String::new();
@@ -41,106 +69,384 @@ fn get_html(ir: &[HIR]) -> String {
     html
 }
 
+/// Hash of the template structure shared by `TemplateWasmServer` and `App`
+///
+/// Expressions are hashed as marks and `on*` attributes are skipped,
+/// resolve expressions are only lowered for the client
+pub fn template_hash(ir: &[HIR]) -> u64 {
+    let mut skeletons = vec![];
+    template_skeletons(ir, &mut skeletons);
+    calculate_hash(&skeletons)
+}
+
+fn template_skeletons(ir: &[HIR], buf: &mut Vec<String>) {
+    let html = get_html(ir);
+    let sink = match parse_document(&html) {
+        Ok(sink) => Ok(sink),
+        Err(_) => parse_fragment(&html),
+    };
+    buf.push(match sink {
+        Ok(sink) => {
+            let mut writer = Vec::new();
+            let opts = SerializerOpt {
+                wasm: true,
+                ..Default::default()
+            };
+            serialize(&mut writer, sink.into(), opts).expect("some serialize node");
+            String::from_utf8(writer).expect("")
+        }
+        Err(_) => html,
+    });
+
+    for x in ir {
+        match x {
+            HIR::Each(each) => template_skeletons(&each.body, buf),
+            HIR::IfElse(if_else) => {
+                template_skeletons(&if_else.ifs.1, buf);
+                for (_, body) in &if_else.if_else {
+                    template_skeletons(body, buf);
+                }
+                if let Some(els) = &if_else.els {
+                    template_skeletons(els, buf);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
 pub fn to_wasmfmt(mut ir: Vec<HIR>, s: &Struct) -> ParseResult<Vec<HIR>> {
     let html = get_html(&ir);
+    let hash = template_hash(&ir);
     let sink = match parse_document(&html) {
         Ok(mut sink) => {
-            add_scripts(s, &mut sink, &mut ir);
+            resolve_binds(&mut sink, &mut ir)?;
+            add_scripts(s, hash, &mut sink, &mut ir)?;
             sink
         }
-        Err(_) => parse_fragment(&html)?,
+        Err(_) => {
+            let mut sink = parse_fragment(&html)?;
+            resolve_binds(&mut sink, &mut ir)?;
+            if s.bootstrap.mount.is_some() {
+                add_scripts(s, hash, &mut sink, &mut ir)?;
+            }
+            sink
+        }
+    };
+
+    serialize_domfmt(
+        sink,
+        ir,
+        SerializerOpt {
+            wasm: true,
+            ..Default::default()
+        },
+    )
+}
+
+/// Render `bind:value` as `value` and `bind:checked` as a conditional `checked`
+fn resolve_binds(sink: &mut Sink, ir: &mut [HIR]) -> ParseResult<()> {
+    let roots: Vec<ParseNodeId> = match sink.nodes.values().next() {
+        Some(ParseElement::Document(children)) => children.clone(),
+        _ => sink
+            .nodes
+            .iter()
+            .filter_map(|(i, x)| match x {
+                ParseElement::Node { parent: None, .. } => Some(*i),
+                _ => None,
+            })
+            .collect(),
+    };
+    let (mut marks, mut checked) = (0, vec![]);
+    for root in roots {
+        visit_binds(sink, root, &mut marks, &mut checked)?;
+    }
+    if checked.is_empty() {
+        return Ok(());
+    }
+
+    let exprs: Vec<usize> = ir
+        .iter()
+        .enumerate()
+        .filter(|(_, x)| !matches!(x, HIR::Lit(_)))
+        .map(|(i, _)| i)
+        .collect();
+    for i in checked.into_iter().map(|x| exprs[x]) {
+        let expr = match &ir[i] {
+            HIR::Expr(expr) | HIR::Safe(expr) => (**expr).clone(),
+            _ => return Err(vec![bind_checked_error()]),
+        };
+        ir[i] = HIR::IfElse(Box::new(HIfElse {
+            ifs: (expr, vec![HIR::Lit("checked".into())]),
+            if_else: vec![],
+            els: None,
+        }));
+    }
+
+    Ok(())
+}
+
+fn bind_checked_error() -> ParseError {
+    ParseError("only use a field expression in `bind:checked` attribute".into())
+}
+
+/// Renames `bind:` attributes and collects the expression index of `bind:checked` values
+fn visit_binds(
+    sink: &mut Sink,
+    id: ParseNodeId,
+    marks: &mut usize,
+    checked: &mut Vec<usize>,
+) -> ParseResult<()> {
+    let children = match sink.nodes.get_mut(&id) {
+        Some(ParseElement::Node {
+            name,
+            attrs,
+            children,
+            ..
+        }) => {
+            *marks += name.local.to_string().matches(MARK).count();
+            for attr in attrs {
+                let name = attr.name.local.to_string();
+                let count = name.matches(MARK).count() + attr.value.matches(MARK).count();
+                match name.as_str() {
+                    "bind:value" => attr.name.local = YName::Local(LocalName::from("value")),
+                    "bind:checked" => {
+                        if attr.value != format!("{}{}", MARK, HASH) {
+                            return Err(vec![bind_checked_error()]);
+                        }
+                        checked.push(*marks);
+                        // Attribute name is the conditional expression
+                        attr.name.local = YName::Local(LocalName::from(attr.value.as_str()));
+                        attr.value = String::new();
+                    }
+                    _ => (),
+                }
+                *marks += count;
+            }
+            children.clone()
+        }
+        Some(ParseElement::Text(s)) | Some(ParseElement::Comment(s)) => {
+            *marks += s.matches(MARK).count();
+            return Ok(());
+        }
+        _ => return Ok(()),
     };
+    for child in children {
+        visit_binds(sink, child, marks, checked)?;
+    }
 
-    serialize_domfmt(sink, ir, SerializerOpt { wasm: true })
+    Ok(())
 }
 
-fn add_scripts(s: &Struct, sink: &mut Sink, ir: &mut Vec<HIR>) {
-    let mut body: Option<usize> = None;
+/// Append preload links, the state and the module script
+///
+/// Links go to `<head>` and scripts to `<body>` of documents,
+/// fragment templates with `mount` attribute get all at the end.
+/// Without `script` attribute only the state is appended, nested applications
+/// are started by its parent
+fn add_scripts(s: &Struct, hash: u64, sink: &mut Sink, ir: &mut Vec<HIR>) -> ParseResult<()> {
     use ParseElement::*;
-    match sink.nodes.values().next() {
+    let b = &s.bootstrap;
+
+    let (head, body, marks) = match sink.nodes.values().next() {
         Some(Document(children)) => {
-            if let Some(Node { name, children, .. }) = sink.nodes.get(&children[0]) {
+            let (mut head, mut body, mut marks) = (None, None, 0);
+            if let Some(Node {
+                name,
+                children,
+                attrs,
+                ..
+            }) = sink.nodes.get(&children[0])
+            {
                 if let y_name!("html") = name.local {
+                    marks += attrs_marks(attrs);
                     for i in children {
                         if let Some(Node { name, .. }) = sink.nodes.get(i) {
-                            if let y_name!("body") = name.local {
-                                body = Some(*i);
+                            match name.local {
+                                y_name!("head") => {
+                                    head = Some(*i);
+                                    marks += count_marks(sink, *i);
+                                }
+                                y_name!("body") => body = Some(*i),
+                                _ => (),
                             }
                         }
                     }
                 }
             }
+            let body = body.expect("body defined");
+            (head.unwrap_or(body), body, marks)
         }
-        _ => panic!("Need <!doctype html>"),
-    }
+        Some(Node { .. }) => {
+            let root = *sink.nodes.keys().next().unwrap();
+            (root, root, count_marks(sink, root))
+        }
+        _ => {
+            return Err(vec![ParseError(
+                "Need <!doctype html> or `mount` attribute".into(),
+            )])
+        }
+    };
 
-    let mut last = *sink.nodes.keys().last().unwrap() + 1;
-    let get_state = format!(
-        "function get_state(){{return JSON.stringify({}{});}}",
-        MARK, HASH
-    );
+    let mut links = vec![];
+    if let Some(script) = &s.script {
+        // Nonce expression of the preload link is written before body expressions
+        let mut attrs = vec![("rel", "modulepreload".into()), ("href", script.clone())];
+        if let Some(integrity) = &b.integrity {
+            attrs.push(("integrity", integrity.clone()));
+        }
+        if let Some(nonce) = &b.nonce {
+            attrs.push(("nonce", format!("{}{}", MARK, HASH)));
+            let pos = ir
+                .iter()
+                .enumerate()
+                .filter(|(_, x)| !matches!(x, HIR::Lit(_)))
+                .nth(marks)
+                .map_or(ir.len(), |(i, _)| i);
+            ir.insert(pos, HIR::Expr(Box::new(nonce.clone())));
+        }
+        links.push(add_node(sink, "link", attrs, None));
+        if let Some(wasm) = &b.wasm {
+            let mut attrs = vec![
+                ("rel", "preload".into()),
+                ("href", wasm.clone()),
+                ("as", "fetch".into()),
+                ("type", "application/wasm".into()),
+                ("crossorigin", "anonymous".into()),
+            ];
+            if let Some(integrity) = &b.wasm_integrity {
+                attrs.push(("integrity", integrity.clone()));
+            }
+            links.push(add_node(sink, "link", attrs, None));
+        }
+    }
 
     ir.push(HIR::Safe(Box::new(
-        parse2(quote!(yarte::Json(&self))).unwrap(),
+        parse2(quote!(yarte::JsonScript(&self))).unwrap(),
     )));
-
-    let state = Node {
-        name: QualName {
-            prefix: None,
-            ns: ns!(html),
-            local: y_name!("script"),
-        },
-        attrs: vec![],
-        children: vec![last],
-        parent: None,
-    };
-    sink.nodes.insert(last, Text(get_state));
-    last += 1;
-    sink.nodes.insert(last, state);
-    let state = last;
-    last += 1;
-
-    let init_s = format!(
-        "import init from '{}';async function run(){{await init()}}run()",
-        s.script.as_ref().expect("Need `script` attribute")
+    let state = add_node(
+        sink,
+        "script",
+        vec![
+            ("type", "application/json".into()),
+            ("id", b.state_id()),
+            ("data-yarte-hash", format!("{:x}", hash)),
+        ],
+        Some(format!("{}{}", MARK, HASH)),
     );
-    let init = Node {
+
+    let init = s.script.as_ref().map(|script| {
+        let init = match (&b.wasm, &b.wasm_integrity) {
+            (Some(wasm), Some(integrity)) => {
+                format!("init(fetch('{}',{{integrity:'{}'}}))", wasm, integrity)
+            }
+            (Some(wasm), None) => format!("init('{}')", wasm),
+            _ => "init()".into(),
+        };
+        let mut attrs = vec![("type", "module".into())];
+        if let Some(nonce) = &b.nonce {
+            attrs.push(("nonce", format!("{}{}", MARK, HASH)));
+            ir.push(HIR::Expr(Box::new(nonce.clone())));
+        }
+        add_node(
+            sink,
+            "script",
+            attrs,
+            Some(format!(
+                "import init from '{}';async function run(){{await {}}}run()",
+                script, init
+            )),
+        )
+    });
+
+    if let Some(Node { children, .. }) = sink.nodes.get_mut(&head) {
+        children.extend(links);
+    }
+    match sink.nodes.get_mut(&body).unwrap() {
+        Node { children, .. } => {
+            children.push(state);
+            children.extend(init);
+        }
+        _ => unreachable!(),
+    }
+
+    Ok(())
+}
+
+fn add_node(
+    sink: &mut Sink,
+    name: &str,
+    attrs: Vec<(&str, String)>,
+    text: Option<String>,
+) -> ParseNodeId {
+    let mut last = *sink.nodes.keys().last().unwrap() + 1;
+    let mut children = vec![];
+    if let Some(text) = text {
+        sink.nodes.insert(last, ParseElement::Text(text));
+        children.push(last);
+        last += 1;
+    }
+    let node = ParseElement::Node {
         name: QualName {
             prefix: None,
             ns: ns!(html),
-            local: y_name!("script"),
+            local: YName::Local(LocalName::from(name)),
         },
-        attrs: vec![ParseAttribute {
-            name: QualName {
-                prefix: None,
-                ns: ns!(),
-                local: y_name!("type"),
-            },
-            value: "module".to_string(),
-        }],
-        children: vec![last],
+        attrs: attrs
+            .into_iter()
+            .map(|(name, value)| ParseAttribute {
+                name: QualName {
+                    prefix: None,
+                    ns: ns!(),
+                    local: YName::Local(LocalName::from(name)),
+                },
+                value,
+            })
+            .collect(),
+        children,
         parent: None,
     };
-    sink.nodes.insert(last, Text(init_s));
-    last += 1;
-    sink.nodes.insert(last, init);
-    match sink.nodes.get_mut(&body.expect("body defined")).unwrap() {
-        Node { children, .. } => {
-            children.push(state);
-            children.push(last);
+    sink.nodes.insert(last, node);
+
+    last
+}
+
+fn attrs_marks(attrs: &[ParseAttribute]) -> usize {
+    attrs
+        .iter()
+        .map(|x| x.name.local.to_string().matches(MARK).count() + x.value.matches(MARK).count())
+        .sum()
+}
+
+/// Expressions in the subtree
+fn count_marks(sink: &Sink, id: ParseNodeId) -> usize {
+    match sink.nodes.get(&id) {
+        Some(ParseElement::Node {
+            name,
+            attrs,
+            children,
+            ..
+        }) => {
+            name.local.to_string().matches(MARK).count()
+                + attrs_marks(attrs)
+                + children
+                    .iter()
+                    .map(|x| count_marks(sink, *x))
+                    .sum::<usize>()
         }
-        _ => unreachable!(),
+        Some(ParseElement::Text(s)) | Some(ParseElement::Comment(s)) => s.matches(MARK).count(),
+        _ => 0,
     }
 }
 
-fn to_domfmt_init(ir: Vec<HIR>) -> ParseResult<Vec<HIR>> {
+fn to_domfmt_init(ir: Vec<HIR>, opts: SerializerOpt) -> ParseResult<Vec<HIR>> {
     let html = get_html(&ir);
     let sink = match parse_document(&html) {
         Ok(a) => a,
         Err(_) => parse_fragment(&html)?,
     };
 
-    serialize_domfmt(sink, ir, Default::default())
+    serialize_domfmt(sink, ir, opts)
 }
 
 fn to_domfmt(ir: Vec<HIR>, opts: SerializerOpt) -> ParseResult<Vec<HIR>> {
@@ -185,11 +491,17 @@ fn serialize_domfmt(sink: Sink, mut ir: Vec<HIR>, opts: SerializerOpt) -> ParseR
 fn resolve_node(ir: HIR, buff: &mut Vec<HIR>, opts: SerializerOpt) -> ParseResult<()> {
     match ir {
         HIR::Each(each) => {
-            let HEach { args, body, expr } = *each;
+            let HEach {
+                args,
+                body,
+                expr,
+                key,
+            } = *each;
             buff.push(HIR::Each(Box::new(HEach {
                 args,
                 expr,
                 body: to_domfmt(body, opts)?,
+                key,
             })))
         }
         HIR::IfElse(if_else) => {
@@ -209,6 +521,10 @@ fn resolve_node(ir: HIR, buff: &mut Vec<HIR>, opts: SerializerOpt) -> ParseResul
                 els,
             })));
         }
+        HIR::Cache(mut cache) => {
+            cache.body = to_domfmt(cache.body, opts)?;
+            buff.push(HIR::Cache(cache))
+        }
         HIR::Lit(_) => panic!("Need some node"),
         ir => buff.push(ir),
     }
@@ -4,12 +4,22 @@ pub mod dom;
 pub mod dom_fmt;
 mod serialize;
 mod sink;
+mod validate;
 
-pub use self::dom_fmt::DOMFmt;
+pub use self::{
+    dom_fmt::DOMFmt,
+    sink::{ParseError, ParseResult},
+    validate::validate,
+};
 
 #[cfg(test)]
 mod test {
+    use syn::parse_str;
+    use yarte_hir::HIR;
+    use yarte_html::serializer::SerializerOpt;
+
     use crate::{
+        dom_fmt::template_hash,
         serialize::serialize,
         sink::{parse_document, parse_fragment},
     };
@@ -132,4 +142,183 @@ mod test {
 
         assert_eq!(expected, html);
     }
+
+    fn minify(src: &str, opts: SerializerOpt) -> String {
+        let a = match parse_document(src) {
+            Ok(a) => a,
+            Err(_) => parse_fragment(src).unwrap(),
+        };
+        let mut writer = Vec::new();
+        serialize(&mut writer, a.into(), opts).expect("some serialize node");
+
+        String::from_utf8(writer).expect("")
+    }
+
+    #[test]
+    fn test_comments() {
+        let src = "<div>a <!-- b --> c<!--[if IE]>d<![endif]--></div>";
+
+        assert_eq!(
+            minify(src, Default::default()),
+            "<div>a c<!--[if IE]>d<![endif]--></div>"
+        );
+        assert_eq!(
+            minify(
+                src,
+                SerializerOpt {
+                    keep_conditional_comments: false,
+                    ..Default::default()
+                }
+            ),
+            "<div>a c</div>"
+        );
+        assert_eq!(
+            minify(
+                src,
+                SerializerOpt {
+                    remove_comments: false,
+                    ..Default::default()
+                }
+            ),
+            "<div>a <!-- b --> c<!--[if IE]>d<![endif]--></div>"
+        );
+        assert_eq!(
+            minify(
+                "<div><!-- yartehashhtmlexpressionsattt0x00000000 --></div>",
+                Default::default()
+            ),
+            "<div><!-- yartehashhtmlexpressionsattt0x00000000 --></div>"
+        );
+    }
+
+    #[test]
+    fn test_omit_closing_tags() {
+        let opts = SerializerOpt {
+            omit_closing_tags: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            minify("<ul><li>a</li> <li>b</li></ul><p>c</p><div></div>", opts),
+            "<ul><li>a<li>b</ul><p>c<div></div>"
+        );
+        assert_eq!(
+            minify("<p>a</p>b<a><p>c</p></a>", opts),
+            "<p>a</p>b<a><p>c</p></a>"
+        );
+        assert_eq!(
+            minify(
+                "<table><tr><td>a</td><td>b</td></tr><tr><th>c</th></tr></table>",
+                opts
+            ),
+            "<table><tr><td>a<td>b<tr><th>c</table>"
+        );
+        assert_eq!(
+            minify("<html><head></head><body><p>a</p></body></html>", opts),
+            "<html><head><body><p>a"
+        );
+        assert_eq!(minify("<li>a</li>", opts), "<li>a</li>");
+    }
+
+    #[test]
+    fn test_attributes_minify() {
+        let opts = SerializerOpt {
+            remove_attr_quotes: true,
+            collapse_boolean_attrs: true,
+            remove_default_attrs: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            minify(
+                "<input type=\"text\" class=\"a b\" id=\"c\" disabled=\"disabled\" value=\"\">",
+                opts
+            ),
+            "<input class=\"a b\" id=c disabled value>"
+        );
+        assert_eq!(
+            minify(
+                "<form method=\"get\"><button type=\"submit\" \
+                 class=\"yartehashhtmlexpressionsattt0x00000000\">a</button></form>",
+                opts
+            ),
+            "<form><button class=\"yartehashhtmlexpressionsattt0x00000000\">a</button></form>"
+        );
+        assert_eq!(
+            minify("<script type=\"text/javascript\">a</script>", opts),
+            "<script>a</script>"
+        );
+    }
+
+    #[test]
+    fn test_whitespace() {
+        assert_eq!(
+            minify(
+                "<div><pre> a  <b> b </b>\n</pre> <textarea>\n  c </textarea></div>",
+                Default::default()
+            ),
+            "<div><pre> a  <b> b </b>\n</pre><textarea>\n  c </textarea></div>"
+        );
+        assert_eq!(
+            minify(
+                "<div><label>a</label>\n  <input> <button>b</button> <img></div>",
+                Default::default()
+            ),
+            "<div><label>a</label> <input> <button>b</button> <img></div>"
+        );
+    }
+
+    #[test]
+    fn test_raw_minify() {
+        let opts = SerializerOpt {
+            minify_css: true,
+            minify_js: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            minify(
+                "<style>\n  p { color: red; }\n</style><script>\n  var a = 1;\n  // b\n  \
+                 f(a);\n</script><script type=\"text/template\"> a  b </script>",
+                opts
+            ),
+            "<style>p{color:red}</style><script>var a=1;f(a);</script><script \
+             type=\"text/template\">a  b</script>"
+        );
+    }
+
+    #[test]
+    fn test_template_hash() {
+        let ir = |open: &str, close: &str| {
+            vec![
+                HIR::Lit(open.into()),
+                HIR::Expr(Box::new(parse_str("self.foo").unwrap())),
+                HIR::Lit(close.into()),
+            ]
+        };
+        let client = ir(
+            "<html><body><div><button onclick=\"Msg :: Inc\">",
+            "</button></div></body></html>",
+        );
+        let server = ir(
+            "<html><body><div><button onclick=\"\">",
+            "</button></div></body></html>",
+        );
+        let other = ir("<html><body><div><p>", "</p></div></body></html>");
+
+        assert_eq!(template_hash(&client), template_hash(&server));
+        assert_ne!(template_hash(&client), template_hash(&other));
+    }
+
+    #[test]
+    fn test_domfmt_errors() {
+        use std::convert::TryFrom;
+
+        use crate::DOMFmt;
+
+        let ir = vec![HIR::Lit("<div><p></span></p></div>".into())];
+        let errors = DOMFmt::try_from(ir).err().unwrap();
+        assert!(!errors.is_empty());
+        assert!(DOMFmt::try_from(vec![HIR::Lit("<div><p></p></div>".into())]).is_ok());
+    }
 }
@@ -24,6 +24,7 @@ pub enum TreeElement {
         children: Vec<TreeElement>,
     },
     Text(String),
+    Comment(String),
     DocType,
 }
 
@@ -59,6 +60,7 @@ impl From<Sink> for Tree {
                 }
             }
             Some(Text(s)) => vec![TreeElement::Text(s)],
+            Some(Comment(s)) => vec![TreeElement::Comment(s)],
             None => vec![],
         };
 
@@ -88,6 +90,7 @@ fn get_children<I: Iterator<Item = ParseNodeId>>(children: I, sink: &mut Sink) -
                 attrs,
                 children: get_children(children.into_iter(), sink),
             }),
+            Comment(s) => tree.push(TreeElement::Comment(s)),
             _ => panic!("Expect document in root"),
         }
     }
@@ -122,6 +125,7 @@ fn _serialize<W: Write>(
                 serializer.end_elem(name)?
             }
             Text(ref s) => serializer.write_text(s)?,
+            Comment(ref s) => serializer.write_comment(s)?,
             DocType => serializer.write_doctype("html")?,
         }
     }
@@ -58,6 +58,7 @@ pub enum ParseElement {
         parent: Option<ParseNodeId>,
     },
     Text(String),
+    Comment(String),
     Document(Vec<ParseNodeId>),
 }
 
@@ -77,6 +78,7 @@ impl Debug for ParseElement {
                 .field("parent", parent)
                 .finish(),
             ParseElement::Text(s) => f.debug_tuple("Text").field(s).finish(),
+            ParseElement::Comment(s) => f.debug_tuple("Comment").field(s).finish(),
             ParseElement::Document(s) => f.debug_tuple("Document").field(s).finish(),
         }
     }
@@ -116,6 +118,7 @@ impl Sink {
                             }
                             Some(())
                         }
+                        ParseElement::Comment(_) => Some(()),
                         _ => None,
                     })
                     .expect("Get parent");
@@ -132,7 +135,13 @@ impl Sink {
 }
 
 #[derive(Debug)]
-pub struct ParseError(Cow<'static, str>);
+pub struct ParseError(pub(crate) Cow<'static, str>);
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
 
 pub type ParseResult<T> = Result<T, Vec<ParseError>>;
 
@@ -195,6 +204,13 @@ impl TreeSink for Sink {
         new_node
     }
 
+    fn create_comment(&mut self, text: StrTendril) -> Self::Handle {
+        let node = self.new_parse_node();
+        self.nodes
+            .insert(node.id, ParseElement::Comment(text.to_string()));
+        node
+    }
+
     fn append(&mut self, p: &Self::Handle, child: HtmlNodeOrText<Self::Handle>) {
         let id = self.append_child(p.id, child);
 
@@ -0,0 +1,541 @@
+//! Compile time html validation
+//!
+//! Template source is rebuilt with a placeholder for every expression, block helpers
+//! are checked as separated fragments in the context of its parent element and partials
+//! are inlined. Parse errors are reported with the span of the template source that
+//! produce them.
+use std::{borrow::Cow, mem, path::PathBuf};
+
+use markup5ever::{
+    local_name, namespace_url, ns,
+    tendril::{StrTendril, TendrilSink},
+};
+
+use yarte_helpers::config::Config;
+use yarte_hir::{Context, Struct};
+use yarte_html::{
+    driver,
+    interface::{
+        Attribute as HtmlAttribute, ElementFlags, ExpandedName, NodeOrText as HtmlNodeOrText,
+        QualName, TreeSink, YName,
+    },
+    tree_builder::{get_marquee, is_marquee},
+    utils::MARK,
+    y_name,
+};
+use yarte_parser::{source_map::Span, ErrorMessage, Helper, Node, Partial, PartialBlock, SNode};
+
+use crate::dom_fmt::HASH;
+
+/// Validate the html of the template and its partials
+///
+/// Returns an empty vector when markup is well formed
+pub fn validate(config: &Config, s: &Struct, ctx: Context) -> Vec<ErrorMessage<String>> {
+    let nodes = match ctx.get(&s.path) {
+        Some(nodes) => nodes,
+        None => return vec![],
+    };
+
+    let mut v = Validator {
+        config,
+        ctx,
+        path: s.path.clone(),
+        partials: vec![],
+        blocks: vec![],
+        recursion_limit: s.recursion_limit,
+        errors: vec![],
+    };
+    let mut markup = Markup::default();
+    v.visit(nodes, &mut markup);
+    v.check(markup);
+
+    v.errors
+}
+
+fn point(at: u32) -> Span {
+    Span { lo: at, hi: at }
+}
+
+/// Starts with `<!doctype` or `<html`
+fn is_document(html: &str) -> bool {
+    let html = html.trim_start();
+    ["<!doctype", "<html"]
+        .iter()
+        .any(|start| matches!(html.get(..start.len()), Some(x) if x.eq_ignore_ascii_case(start)))
+}
+
+struct Validator<'a> {
+    config: &'a Config<'a>,
+    ctx: Context<'a>,
+    /// Current file
+    path: PathBuf,
+    /// Partials stack
+    partials: Vec<PathBuf>,
+    /// Partial blocks stack with its file
+    blocks: Vec<(PathBuf, &'a [SNode<'a>])>,
+    recursion_limit: usize,
+    errors: Vec<ErrorMessage<String>>,
+}
+
+impl<'a> Validator<'a> {
+    fn visit(&mut self, nodes: &'a [SNode<'a>], m: &mut Markup) {
+        for n in nodes {
+            match n.t() {
+                Node::Lit(l, lit, r) | Node::Raw(_, l, lit, r) => {
+                    m.push_fixed(l, point(lit.span().lo));
+                    m.push_lit(lit.t(), lit.span());
+                    m.push_fixed(r, point(lit.span().hi));
+                }
+                Node::Comment(_) => (),
+                Node::Helper(h) => match &**h {
+                    Helper::With(_, _, block) => self.visit(block, m),
+                    Helper::Each(_, _, _, block)
+                    | Helper::Cache(_, _, _, block)
+                    | Helper::Unless(_, _, block)
+                    | Helper::Defined(_, _, _, block) => {
+                        let context = context(m);
+                        m.push_expr(n.span());
+                        self.fragment(block, &context);
+                    }
+                    Helper::If((_, _, block), ifs, els) => {
+                        let context = context(m);
+                        m.push_expr(n.span());
+                        self.fragment(block, &context);
+                        for (_, _, block) in ifs {
+                            self.fragment(block, &context);
+                        }
+                        if let Some((_, block)) = els {
+                            self.fragment(block, &context);
+                        }
+                    }
+                },
+                // Nested application is rendered as an expression
+                Node::Partial(Partial(_, ident, _)) if ident.t().starts_with('@') => {
+                    m.push_expr(n.span())
+                }
+                Node::Partial(Partial(_, ident, _)) => self.partial(ident.t(), None, n.span(), m),
+                Node::PartialBlock(PartialBlock(_, ident, _, block)) => {
+                    self.partial(ident.t(), Some(block), n.span(), m)
+                }
+                Node::Block(_) => {
+                    // Partial block is visited in the context of its caller
+                    if let Some((path, block)) = self.blocks.pop() {
+                        let parent = mem::replace(&mut self.path, path);
+                        self.visit(block, m);
+                        let path = mem::replace(&mut self.path, parent);
+                        self.blocks.push((path, block));
+                    }
+                }
+                _ => m.push_expr(n.span()),
+            }
+        }
+    }
+
+    fn partial(&mut self, ident: &str, block: Option<&'a [SNode<'a>]>, span: Span, m: &mut Markup) {
+        let path = match self.config.resolve_partial(&self.path, ident) {
+            Ok(path) => path,
+            Err(message) => {
+                self.errors.push(ErrorMessage { message, span });
+                return m.push_expr(span);
+            }
+        };
+        let nodes = match self.ctx.get(&path) {
+            Some(nodes)
+                if !self.partials.contains(&path) && self.partials.len() < self.recursion_limit =>
+            {
+                nodes
+            }
+            // Recursive partials are rendered at run time
+            _ => return m.push_expr(span),
+        };
+
+        if let Some(block) = block {
+            self.blocks.push((self.path.clone(), block));
+        }
+        let parent = mem::replace(&mut self.path, path.clone());
+        self.partials.push(path);
+        self.visit(nodes, m);
+        self.partials.pop();
+        self.path = parent;
+        if block.is_some() {
+            self.blocks.pop();
+        }
+    }
+
+    fn fragment(&mut self, nodes: &'a [SNode<'a>], context: &QualName) {
+        let mut markup = Markup {
+            context: Some(context.clone()),
+            ..Default::default()
+        };
+        self.visit(nodes, &mut markup);
+        self.check(markup);
+    }
+
+    fn check(&mut self, m: Markup) {
+        if m.html.is_empty() {
+            return;
+        }
+
+        let sink = parse(&m);
+        for (message, offset) in sink.errors {
+            let (lo, hi) = m.token(offset);
+            let message = if message.starts_with("Unexpected token") {
+                format!("unexpected `{}`", &m.html[lo..hi])
+            } else if message == "Found special tag while closing generic tag" {
+                format!("unexpected `{}` without open element", &m.html[lo..hi])
+            } else {
+                message
+            };
+            self.errors.push(ErrorMessage {
+                message: format!("invalid html: {}", message),
+                span: m.span(lo, hi),
+            });
+        }
+    }
+}
+
+/// Parse markup as document or as fragment of its context element
+fn parse(m: &Markup) -> Sink {
+    match &m.context {
+        None if is_document(&m.html) => {
+            driver::parse_document(Sink::default()).one(m.html.as_str())
+        }
+        context => driver::parse_fragment(
+            Sink::default(),
+            context.clone().unwrap_or_else(get_marquee),
+            vec![],
+        )
+        .one(m.html.as_str()),
+    }
+}
+
+/// Innermost open element at the end of markup
+fn context(m: &Markup) -> QualName {
+    parse(m)
+        .unclosed
+        .into_iter()
+        .find(|name| !(name.ns == ns!(html) && name.local == y_name!("html")))
+        .or_else(|| m.context.clone())
+        .unwrap_or_else(get_marquee)
+}
+
+/// Markup of a template scope with the source spans of its chunks
+#[derive(Default)]
+struct Markup {
+    html: String,
+    chunks: Vec<Chunk>,
+    /// Parent element of block helper fragments
+    context: Option<QualName>,
+}
+
+struct Chunk {
+    /// Start offset in markup
+    start: usize,
+    span: Span,
+    /// Markup is a copy of the source span
+    linear: bool,
+}
+
+impl Markup {
+    fn push(&mut self, s: &str, span: Span, linear: bool) {
+        if !s.is_empty() {
+            self.chunks.push(Chunk {
+                start: self.html.len(),
+                span,
+                linear,
+            });
+            self.html.push_str(s);
+        }
+    }
+
+    fn push_lit(&mut self, s: &str, span: Span) {
+        self.push(s, span, true)
+    }
+
+    fn push_fixed(&mut self, s: &str, span: Span) {
+        self.push(s, span, false)
+    }
+
+    fn push_expr(&mut self, span: Span) {
+        self.push_fixed(&[MARK, HASH].concat(), span)
+    }
+
+    /// Chunk containing byte at offset
+    fn chunk(&self, offset: usize) -> &Chunk {
+        let i = match self.chunks.binary_search_by(|c| c.start.cmp(&offset)) {
+            Ok(i) => i,
+            Err(i) => i.saturating_sub(1),
+        };
+        &self.chunks[i]
+    }
+
+    /// Markup range of the token before offset
+    fn token(&self, offset: usize) -> (usize, usize) {
+        let hi = offset.min(self.html.len());
+        let before = &self.html[..hi];
+        let lo = match (before.rfind('<'), before.rfind('>')) {
+            (Some(lt), Some(gt)) if lt > gt || gt + 1 == hi => lt,
+            (Some(lt), None) => lt,
+            _ => before.char_indices().next_back().map_or(0, |(i, _)| i),
+        };
+
+        (lo, hi.max(lo + 1).min(self.html.len()))
+    }
+
+    /// Source span of markup range
+    fn span(&self, lo: usize, hi: usize) -> Span {
+        let first = self.chunk(lo);
+        let last = self.chunk(hi - 1);
+        let start = if first.linear {
+            first.span.lo + (lo - first.start) as u32
+        } else {
+            first.span.lo
+        };
+        let end = if last.linear {
+            last.span.lo + (hi - last.start) as u32
+        } else {
+            last.span.hi
+        };
+
+        if start <= end && first.span.file_path() == last.span.file_path() {
+            Span { lo: start, hi: end }
+        } else {
+            first.span
+        }
+    }
+}
+
+type Handle = usize;
+
+/// Arena sink collecting errors with its offset
+#[derive(Default)]
+struct Sink {
+    offset: usize,
+    /// Element names with the offset after its start tag
+    names: Vec<Option<(QualName, usize)>>,
+    errors: Vec<(String, usize)>,
+    /// Open elements at the end of input from the innermost
+    unclosed: Vec<QualName>,
+}
+
+impl Sink {
+    fn new_node(&mut self, name: Option<QualName>) -> Handle {
+        self.names.push(name.map(|name| (name, self.offset)));
+        self.names.len() - 1
+    }
+}
+
+impl TreeSink for Sink {
+    type Handle = Handle;
+    type Output = Self;
+
+    fn finish(self) -> Self::Output {
+        self
+    }
+
+    fn parse_error(&mut self, msg: Cow<'static, str>) {
+        self.errors.push((msg.into_owned(), self.offset))
+    }
+
+    fn get_document(&mut self) -> Self::Handle {
+        self.new_node(None)
+    }
+
+    fn elem_name<'a>(&'a self, target: &'a Self::Handle) -> ExpandedName<'a> {
+        self.names[*target]
+            .as_ref()
+            .expect("Expected qual name of node!")
+            .0
+            .expanded()
+    }
+
+    fn create_element(
+        &mut self,
+        name: QualName,
+        _attrs: Vec<HtmlAttribute>,
+        _flags: ElementFlags,
+    ) -> Self::Handle {
+        self.new_node(Some(name))
+    }
+
+    fn create_comment(&mut self, _text: StrTendril) -> Self::Handle {
+        self.new_node(None)
+    }
+
+    fn append(&mut self, _parent: &Self::Handle, _child: HtmlNodeOrText<Self::Handle>) {}
+
+    fn append_doctype_to_document(&mut self, _: StrTendril, _: StrTendril, _: StrTendril) {}
+
+    fn get_template_contents(&mut self, target: &Self::Handle) -> Self::Handle {
+        *target
+    }
+
+    fn same_node(&self, x: &Self::Handle, y: &Self::Handle) -> bool {
+        x == y
+    }
+
+    fn pop_unclosed(&mut self, node: &Self::Handle) {
+        if let Some((name, offset)) = &self.names[*node] {
+            if !(is_marquee(name) || has_optional_end(name)) {
+                self.errors
+                    .push((format!("unclosed element `<{}>`", &*name.local), *offset));
+            }
+            self.unclosed.push(name.clone());
+        }
+    }
+
+    fn set_current_offset(&mut self, offset: usize) {
+        self.offset = offset;
+    }
+}
+
+/// Elements whose end tag can be omitted
+fn has_optional_end(name: &QualName) -> bool {
+    name.ns != ns!(html)
+        || matches!(
+            name.local,
+            y_name!("html")
+                | y_name!("head")
+                | y_name!("body")
+                | y_name!("p")
+                | y_name!("li")
+                | y_name!("dt")
+                | y_name!("dd")
+                | y_name!("option")
+                | y_name!("optgroup")
+                | y_name!("rt")
+                | y_name!("rp")
+                | y_name!("tbody")
+                | y_name!("thead")
+                | y_name!("tfoot")
+                | y_name!("tr")
+                | y_name!("td")
+                | y_name!("th")
+                | y_name!("colgroup")
+                | y_name!("caption")
+        )
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use quote::quote;
+    use syn::parse2;
+
+    use yarte_hir::visit_derive;
+    use yarte_parser::{
+        parse,
+        source_map::{clean, get_cursor},
+    };
+
+    use super::*;
+
+    /// Errors with its template source
+    fn test(src: &str) -> Vec<(String, String)> {
+        let i = quote! {
+            #[template(src = #src)]
+            struct __Foo__;
+        };
+        let config = &Config::new("");
+        let der = parse2(i).unwrap();
+        let s = visit_derive(&der, config).unwrap();
+        let cursor = get_cursor(&s.path, &s.src);
+        let base = cursor.off;
+        let sources = parse(cursor).unwrap();
+        let mut ctx = BTreeMap::new();
+        ctx.insert(&s.path, sources);
+
+        let errors = validate(config, &s, &ctx);
+        clean();
+
+        errors
+            .into_iter()
+            .map(|e| {
+                let lo = (e.span.lo - base) as usize;
+                let hi = (e.span.hi - base) as usize;
+                (e.message, s.src[lo..hi].to_owned())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_valid() {
+        assert!(test("<div class=\"{{ a }}\"><p>{{ b }}</div>").is_empty());
+        assert!(test("<ul>{{#each a}}<li>{{ this }}{{/each}}</ul>").is_empty());
+        assert!(test("<table><tr><td>{{ a }}</td></tr></table>").is_empty());
+        assert!(test(
+            "<!doctype html><html><head><title>{{ a }}</title></head><body></body></html>"
+        )
+        .is_empty());
+        assert!(test("<html>\n<body>{{ a }}</body>\n</html>").is_empty());
+        assert!(test("{{#with a}}<div>{{ b }}</div>{{/with}}").is_empty());
+    }
+
+    #[test]
+    fn test_context() {
+        assert!(
+            test("<table>{{#each rows}}<tr><td>{{ this }}</td></tr>{{/each}}</table>").is_empty()
+        );
+        assert!(test("<table><tr>{{#each a}}<td>{{ this }}</td>{{/each}}</tr></table>").is_empty());
+        assert!(test(
+            "<select>{{#if a}}<option>{{ b }}</option>{{else}}<option>c</option>{{/if}}</select>"
+        )
+        .is_empty());
+        assert!(
+            test("<ul>{{#each a}}{{#if this}}<li>{{ this }}</li>{{/if}}{{/each}}</ul>").is_empty()
+        );
+    }
+
+    #[test]
+    fn test_unclosed() {
+        assert_eq!(
+            test("<div>\n  <span>{{ a }}\n</div>"),
+            vec![(
+                "invalid html: Unexpected open element while closing div".to_owned(),
+                "</div>".to_owned()
+            )]
+        );
+        assert_eq!(
+            test("{{#if a}}<section>{{ b }}{{/if}}"),
+            vec![(
+                "invalid html: unclosed element `<section>`".to_owned(),
+                "<section>".to_owned()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_stray_end_tag() {
+        assert_eq!(
+            test("<div>{{ a }}</span></div>"),
+            vec![(
+                "invalid html: unexpected `</span>` without open element".to_owned(),
+                "</span>".to_owned()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_attribute() {
+        let errors = test("<div class=\"a\" id=\"{{ b }}\" class=\"c\"></div>");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "invalid html: Duplicate attribute");
+        assert!(errors[0].1.starts_with("<div class=\"a\""));
+    }
+
+    #[test]
+    fn test_misnested() {
+        let errors = test("<p><b><i>{{ a }}</b></i></p>");
+        assert!(!errors.is_empty());
+        assert_eq!(errors[0].1, "</b>");
+    }
+
+    #[test]
+    fn test_missing_partial() {
+        let errors = test("<div>{{> missing }}</div>");
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].0.contains("missing"), "{}", errors[0].0);
+        assert_eq!(errors[0].1, "{{> missing }}");
+    }
+}
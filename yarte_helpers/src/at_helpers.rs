@@ -1,6 +1,9 @@
 #[cfg(feature = "json")]
 pub mod json {
-    use std::fmt::{self, Display};
+    use std::{
+        fmt::{self, Display},
+        io,
+    };
 
     use serde::Serialize;
     use serde_json::{to_writer, to_writer_pretty};
@@ -57,6 +60,115 @@ pub mod json {
         }
     }
 
+    /// Json safe to embed inside `<script>` tags
+    pub struct JsonScript<'a, T>(pub &'a T);
+
+    impl<'a, T> Clone for JsonScript<'a, T> {
+        fn clone(&self) -> Self {
+            JsonScript(self.0)
+        }
+    }
+
+    impl<'a, T> Copy for JsonScript<'a, T> {}
+
+    pub trait AsJsonScript {
+        fn __as_json_script(&self) -> JsonScript<'_, Self>
+        where
+            Self: Sized;
+    }
+
+    impl<S> AsJsonScript for S {
+        fn __as_json_script(&self) -> JsonScript<'_, Self>
+        where
+            Self: Sized,
+        {
+            JsonScript(self)
+        }
+    }
+
+    /// Pretty json safe to embed inside `<script>` tags
+    pub struct JsonPrettyScript<'a, T>(pub &'a T);
+
+    impl<'a, T> Clone for JsonPrettyScript<'a, T> {
+        fn clone(&self) -> Self {
+            JsonPrettyScript(self.0)
+        }
+    }
+
+    impl<'a, T> Copy for JsonPrettyScript<'a, T> {}
+
+    pub trait AsJsonPrettyScript {
+        fn __as_json_pretty_script(&self) -> JsonPrettyScript<'_, Self>
+        where
+            Self: Sized;
+    }
+
+    impl<S> AsJsonPrettyScript for S {
+        fn __as_json_pretty_script(&self) -> JsonPrettyScript<'_, Self>
+        where
+            Self: Sized,
+        {
+            JsonPrettyScript(self)
+        }
+    }
+
+    /// Escape `<`, `>`, `&`, U+2028 and U+2029 in serialized json
+    ///
+    /// All of them only can be inside json strings, where are replaced by its `\uXXXX` escape.
+    /// `src` should be split at char boundaries
+    pub(crate) fn escape_script<E>(
+        src: &[u8],
+        mut write: impl FnMut(&[u8]) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let mut start = 0;
+        let mut i = 0;
+        while i < src.len() {
+            let (escaped, len): (&[u8], usize) = match src[i] {
+                b'<' => (b"\\u003c", 1),
+                b'>' => (b"\\u003e", 1),
+                b'&' => (b"\\u0026", 1),
+                0xE2 if src.get(i + 1) == Some(&0x80) => match src.get(i + 2) {
+                    Some(0xA8) => (b"\\u2028", 3),
+                    Some(0xA9) => (b"\\u2029", 3),
+                    _ => {
+                        i += 1;
+                        continue;
+                    }
+                },
+                _ => {
+                    i += 1;
+                    continue;
+                }
+            };
+            if start < i {
+                write(&src[start..i])?;
+            }
+            write(escaped)?;
+            i += len;
+            start = i;
+        }
+
+        if start < src.len() {
+            write(&src[start..])?;
+        }
+
+        Ok(())
+    }
+
+    /// Io write adapter escaping json for `<script>` tags
+    pub(crate) struct ScriptEscape<W>(pub W);
+
+    impl<W: io::Write> io::Write for ScriptEscape<W> {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let w = &mut self.0;
+            escape_script(buf, |b| w.write_all(b)).map(|_| buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.0.flush()
+        }
+    }
+
     impl<'a, S: Serialize> Display for Json<'a, S> {
         #[inline(always)]
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -70,6 +182,38 @@ pub mod json {
             to_writer_pretty(IoFmt::new(f), self.0).map_err(|_| fmt::Error)
         }
     }
+
+    impl<'a, S: Serialize> Display for JsonScript<'a, S> {
+        #[inline(always)]
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            to_writer(ScriptEscape(IoFmt::new(f)), self.0).map_err(|_| fmt::Error)
+        }
+    }
+
+    impl<'a, S: Serialize> Display for JsonPrettyScript<'a, S> {
+        #[inline(always)]
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            to_writer_pretty(ScriptEscape(IoFmt::new(f)), self.0).map_err(|_| fmt::Error)
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+
+        #[test]
+        fn test_script() {
+            let s = "</script><!--&\u{2028}\u{2029}é";
+            assert_eq!(
+                JsonScript(&s).to_string(),
+                "\"\\u003c/script\\u003e\\u003c!--\\u0026\\u2028\\u2029é\""
+            );
+            assert_eq!(
+                JsonPrettyScript(&vec![s]).to_string(),
+                format!("[\n  {}\n]", JsonScript(&s))
+            );
+        }
+    }
 }
 #[cfg(feature = "json")]
 pub use self::json::*;
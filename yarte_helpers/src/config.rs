@@ -5,6 +5,8 @@
 //!   - **`dir`**: name of template directory. If no value is given, a default directory
 //! **`templates`** will be used. If the defined directory is not found, an error
 //! will prompt.
+//!   - **`dirs`**: array of template directories searched, in order, after `dir`. Paths of
+//! templates, partials and `@include_*` files resolve to the first directory containing them.
 //!   - **`debug`**: type of output of debug mode. The code and/or  ast generated by  Yarte
 //! can be visualize, to do so, at most one of three possible values has to be given:
 //! `code`, `ast`, or `all`.
@@ -14,6 +16,44 @@
 //! must exist, or error will be prompt. If the tag `partials` doesn't exist no aliasing
 //! will be possible.
 //!
+//! - **`packages`** (templates of other crates - optional): each entry must be of the type
+//! `name = "links"`, where `links` is the `links` key of a dependency whose `build.rs` calls
+//! `yarte_helpers::recompile::export_templates`. `{{> name/card }}` is the template `card` of that
+//! crate. The `build.rs` of this crate must call `yarte_helpers::recompile::when_changed`.
+//!
+//! - **`extensions`** (escaping modes - optional): each entry must be of the type
+//! `ext = "mode"`, where `ext` is the inner extension of a template file name
+//! (`txt` in `email.txt.hbs`) and `mode` is one of `html`, `xml` or `text`.
+//! Entries override the default mapping: `html` and `htm` are `html`, `xml`, `svg`,
+//! `rss` and `atom` are `xml`, and `txt`, `text`, `md`, `json` and `csv` are `text`.
+//! Templates without inner extension are `html`.
+//!
+//! - **`sanitize`** (allowlist of `@sanitize` helper - optional): with attributes
+//!   - **`tags`**: array of allowed element names.
+//!   - **`attributes`**: array of allowed attribute names.
+//! A missing list uses the default allowlist of basic formatting, links, lists and tables.
+//!
+//! - **`assets`** (urls of `@asset` helper - optional): with attributes
+//!   - **`manifest`**: JSON object, relative to `dir`, mapping asset paths to built paths
+//! (`"app.js": "app.3f2a1b.js"`). Assets out of the manifest get a `?v=<content hash>` query.
+//!   - **`prefix`**: prepended to every asset url, as `/static/`.
+//!
+//! - **`minify`** (output of `html-min` templates - optional): booleans
+//!   - **`remove_comments`** (default: `true`): comments with expressions are always kept.
+//!   - **`keep_conditional_comments`** (default: `true`): keep `<!--[if IE]>` comments.
+//!   - **`omit_closing_tags`** (default: `false`): omit optional closing tags as `</li>` or `</td>`.
+//!   - **`remove_attr_quotes`** (default: `false`): unquote attribute values when it is possible.
+//!   - **`collapse_boolean_attrs`** (default: `false`): `disabled="disabled"` to `disabled`.
+//!   - **`remove_default_attrs`** (default: `false`): remove attributes as `<script type="text/javascript">`.
+//!   - **`minify_css`** (default: `false`): remove whitespace and comments in `<style>`.
+//!   - **`minify_js`** (default: `false`): remove whitespace and comments in `<script>`.
+//!
+//! - **`vars`** (compile time variables - optional): each entry must be of the type
+//!   `name = value`, where `value` is a string, number, boolean or array. Variables are
+//!   replaced by its value in template expressions, so `{{#if env == "prod" }}` is evaluated
+//!   at compile time. `#[template(vars(env = "dev"))]` overrides them. Inside `each` and `with`
+//!   blocks a name is a field of the context, use `super::env` to reach the variable.
+//!
 //! - **`debug`** (debugging configuration - optional): in order to visualize clearly generated code
 //! in a debugging environment Yarte gives it a tabulated format, and the possibility
 //! to see the number line use a color theme. Options are the following:
@@ -42,11 +82,33 @@
 //! ```toml
 //! [main]
 //! dir = "templates"
+//! dirs = ["shared"]
 //! debug = "all"
 //!
 //! [partials]
 //! alias = "./deep/more/deep"
 //!
+//! [packages]
+//! ds = "design_system"
+//!
+//! [extensions]
+//! eml = "text"
+//!
+//! [sanitize]
+//! tags = ["p", "b", "i", "a"]
+//! attributes = ["href"]
+//!
+//! [assets]
+//! manifest = "dist/manifest.json"
+//! prefix = "/static/"
+//!
+//! [vars]
+//! env = "prod"
+//!
+//! [minify]
+//! omit_closing_tags = true
+//! minify_css = true
+//!
 //! [debug]
 //! theme = "zenburn"
 //! number_line = true
@@ -60,32 +122,62 @@
 //! `{{> alias context}}` or `{{> alias}}` if the current context is well defined.
 //!
 use std::{
-    collections::BTreeMap,
-    env, fs,
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+    env, fs, iter, mem,
     path::{Path, PathBuf},
+    rc::Rc,
+    time::SystemTime,
 };
 
 use serde::Deserialize;
 
+/// Template directories in search order, the first one is the main directory
 #[derive(Debug)]
-pub struct Dir(PathBuf);
+pub struct Dir {
+    dirs: Vec<PathBuf>,
+    /// Candidates tried before the found path, a new file there shadows it
+    missed: RefCell<BTreeSet<PathBuf>>,
+}
 
 impl Dir {
-    pub fn get_template(&self, path: &Path) -> PathBuf {
-        let template = self.0.join(path);
-
-        if template.exists() {
-            template
-        } else {
-            panic!("template not found in directory {:?}", template)
+    fn new(dir: Option<&str>, dirs: &[&str]) -> Self {
+        let root = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+        let main = match (dir, dirs.is_empty()) {
+            (Some(dir), _) => Some(dir),
+            (None, true) => Some(DEFAULT_DIR),
+            (None, false) => None,
+        };
+        Dir {
+            dirs: main
+                .into_iter()
+                .chain(dirs.iter().copied())
+                .map(|x| root.join(x))
+                .collect(),
+            missed: RefCell::default(),
         }
     }
-}
 
-impl From<Option<&str>> for Dir {
-    fn from(p: Option<&str>) -> Self {
-        let root = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
-        Dir(p.map_or_else(|| root.join(DEFAULT_DIR), |v| root.join(v)))
+    /// First existing `path` in the directories
+    pub fn get_template(&self, path: &Path) -> Result<PathBuf, String> {
+        self.find(self.dirs.iter().map(|dir| dir.join(path)))
+    }
+
+    /// First existing candidate, or the error with all of them
+    fn find(&self, candidates: impl IntoIterator<Item = PathBuf>) -> Result<PathBuf, String> {
+        let mut tried = vec![];
+        for path in candidates {
+            if path.is_file() {
+                self.missed.borrow_mut().extend(tried);
+                return Ok(path);
+            }
+            if !tried.contains(&path) {
+                tried.push(path);
+            }
+        }
+
+        Err(format!("template not found, tried {:?}", tried))
     }
 }
 
@@ -108,40 +200,240 @@ impl From<Option<&str>> for PrintConfig {
     }
 }
 
+/// Output mode of a template, selected by the inner extension of its file name
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    /// Escape html characters and allow minification
+    Html,
+    /// Escape html characters but never minify
+    Xml,
+    /// Write expressions as they are
+    Text,
+}
+
+impl Mode {
+    /// Expressions are written without escape
+    pub fn is_text(self) -> bool {
+        self == Mode::Text
+    }
+
+    /// Markup can be minified by the html minifier
+    pub fn is_minifiable(self) -> bool {
+        self == Mode::Html
+    }
+
+    fn from_extension(ext: &str) -> Option<Mode> {
+        match ext {
+            "html" | "htm" => Some(Mode::Html),
+            "xml" | "svg" | "rss" | "atom" => Some(Mode::Xml),
+            "txt" | "text" | "md" | "json" | "csv" => Some(Mode::Text),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Mode {
+    fn default() -> Self {
+        Mode::Html
+    }
+}
+
+impl TryFrom<&str> for Mode {
+    type Error = String;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "html" => Ok(Mode::Html),
+            "xml" => Ok(Mode::Xml),
+            "text" => Ok(Mode::Text),
+            v => Err(format!(
+                "invalid mode in {}: {}, expected `html`, `xml` or `text`",
+                CONFIG_FILE_NAME, v
+            )),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Config<'a> {
     dir: Dir,
     alias: BTreeMap<&'a str, &'a str>,
+    packages: BTreeMap<&'a str, &'a str>,
+    /// Templates directory of each package `links`, from the build script environment
+    package_dirs: BTreeMap<&'a str, PathBuf>,
+    extensions: BTreeMap<&'a str, Mode>,
     pub print_override: PrintConfig,
     pub debug: PrintOption<'a>,
+    pub sanitize: SanitizeOption<'a>,
+    pub minify: MinifyOption,
+    pub assets: AssetsOption<'a>,
+    /// Rust expression of each compile time variable
+    pub vars: BTreeMap<&'a str, String>,
+    /// Configuration of the compiled crate, if its build script forwards it
+    pub cfg: Option<Cfg>,
+    /// Files read by `get_file`
+    files: RefCell<BTreeSet<PathBuf>>,
 }
 
 impl<'a> Config<'a> {
     pub fn new(s: &str) -> Config {
         let raw: RawConfig =
             toml::from_str(&s).unwrap_or_else(|_| panic!("invalid TOML in {}", CONFIG_FILE_NAME));
-        let (dir, print) = raw.main.map(|x| (x.dir, x.debug)).unwrap_or((None, None));
+        let (dir, dirs, print) = raw
+            .main
+            .map(|x| (x.dir, x.dirs.unwrap_or_default(), x.debug))
+            .unwrap_or_default();
+        let packages = raw.packages.unwrap_or_default();
+        let package_dirs = packages
+            .values()
+            .filter_map(|links| {
+                env::var_os(package_var(links)).map(|dir| (*links, PathBuf::from(dir)))
+            })
+            .collect();
 
         Config {
-            dir: Dir::from(dir),
+            dir: Dir::new(dir, &dirs),
             print_override: PrintConfig::from(print),
             debug: raw.debug.unwrap_or_default(),
+            sanitize: raw.sanitize.unwrap_or_default(),
+            minify: raw.minify.unwrap_or_default(),
+            assets: raw.assets.unwrap_or_default(),
+            vars: raw
+                .vars
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(k, v)| {
+                    (
+                        k,
+                        to_expr(&v).unwrap_or_else(|e| panic!("{} in var '{}'", e, k)),
+                    )
+                })
+                .collect(),
+            cfg: Cfg::from_env(),
+            files: RefCell::default(),
             alias: raw.partials.unwrap_or_default(),
+            packages,
+            package_dirs,
+            extensions: raw
+                .extensions
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(k, v)| (k, Mode::try_from(v).unwrap_or_else(|e| panic!("{}", e))))
+                .collect(),
         }
     }
 
+    /// Mode selected by the inner extension of template path (`txt` in `email.txt.hbs`)
+    pub fn get_mode(&self, path: &Path) -> Mode {
+        path.file_stem()
+            .map(Path::new)
+            .and_then(Path::extension)
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| {
+                self.extensions
+                    .get(ext)
+                    .copied()
+                    .or_else(|| Mode::from_extension(ext))
+            })
+            .unwrap_or_default()
+    }
+
+    /// Main templates directory
     pub fn get_dir(&self) -> &PathBuf {
-        &self.dir.0
+        &self.dir.dirs[0]
     }
 
-    pub fn get_template(&self, path: &Path) -> (PathBuf, String) {
-        let path = self.dir.get_template(path);
+    /// Templates directories in search order
+    pub fn get_dirs(&self) -> &[PathBuf] {
+        &self.dir.dirs
+    }
+
+    /// Templates directory exported by the `links` package, forwarded by `recompile::when_changed`
+    pub fn get_package(&self, name: &str) -> Result<PathBuf, String> {
+        let links = self
+            .packages
+            .get(name)
+            .ok_or_else(|| format!("package '{}' not found in {}", name, CONFIG_FILE_NAME))?;
+        self.package_dirs.get(links).cloned().ok_or_else(|| {
+            format!(
+                "templates of package '{}' not found, {} is not set in build script",
+                name,
+                package_var(links)
+            )
+        })
+    }
+
+    pub fn get_template(&self, path: &Path) -> Result<(PathBuf, String), String> {
+        let path = self.dir.get_template(path)?;
         let src = get_source(path.as_path());
-        (path, src)
+        Ok((path, src))
+    }
+
+    /// Path of a file at templates directories, as `@include_str`, `@include_raw` and `@asset`
+    pub fn get_file(&self, path: &str) -> Result<PathBuf, String> {
+        let file = self.dir.get_template(Path::new(path))?;
+        self.files.borrow_mut().insert(file.clone());
+        Ok(file)
+    }
+
+    /// Files found by `get_file` since last call, the compiler must track them
+    pub fn take_files(&self) -> Vec<PathBuf> {
+        mem::take(&mut *self.files.borrow_mut())
+            .into_iter()
+            .collect()
     }
 
-    pub fn resolve_partial(&self, parent: &Path, ident: &str) -> PathBuf {
-        let (mut buf, is_alias) = self
+    /// Paths tried before the files found since last call, the compiler must track them
+    /// so a new file shadowing a template recompiles
+    pub fn take_missed(&self) -> Vec<PathBuf> {
+        mem::take(&mut *self.dir.missed.borrow_mut())
+            .into_iter()
+            .collect()
+    }
+
+    /// Url of `@asset`, mapped by the manifest or with its content hash as query
+    pub fn get_asset(&self, path: &str) -> Result<String, String> {
+        let file = self.get_file(path)?;
+        let prefix = self.assets.prefix.unwrap_or_default();
+        if let Some(manifest) = self.assets.manifest {
+            let manifest = self.get_file(manifest)?;
+            let src = fs::read_to_string(&manifest)
+                .map_err(|e| format!("unable to read {:?}: {}", manifest, e))?;
+            let map: BTreeMap<String, String> = serde_json::from_str(&src)
+                .map_err(|e| format!("invalid JSON object in {:?}: {}", manifest, e))?;
+            if let Some(built) = map.get(path) {
+                return Ok(format!("{}{}", prefix, built));
+            }
+        }
+
+        let src = fs::read(&file).map_err(|e| format!("unable to read {:?}: {}", file, e))?;
+        Ok(format!(
+            "{}{}?v={:016x}",
+            prefix,
+            path,
+            crate::calculate_hash(&src)
+        ))
+    }
+
+    /// Path of partial `ident` called at `parent`
+    ///
+    /// Resolves `package/path` in the package, an alias in the templates directories and any
+    /// other path relative to `parent`, then in the templates directories.
+    pub fn resolve_partial(&self, parent: &Path, ident: &str) -> Result<PathBuf, String> {
+        let with_ext = |buf: PathBuf| match (buf.extension(), parent.extension()) {
+            (None, Some(ext)) => buf.with_extension(ext),
+            _ => buf,
+        };
+
+        if let Some(at) = ident.find('/') {
+            if self.packages.contains_key(&ident[..at]) {
+                let dir = self.get_package(&ident[..at])?;
+                let path = with_ext(dir.join(&ident[at + 1..]));
+                return self.dir.find(Some(path)).map(normalize);
+            }
+        }
+
+        let (buf, is_alias) = self
             .alias
             .iter()
             .find_map(|(k, v)| {
@@ -154,20 +446,17 @@ impl<'a> Config<'a> {
                 }
             })
             .map_or((PathBuf::from(ident), false), |s| (s, true));
-
-        if buf.extension().is_none() {
-            if let Some(ext) = parent.extension() {
-                buf = buf.with_extension(ext);
-            }
-        };
+        let buf = with_ext(buf);
 
         if is_alias {
-            normalize(self.dir.get_template(&buf))
+            self.dir.get_template(&buf).map(normalize)
         } else {
-            let mut parent = parent.to_owned();
-            parent.pop();
-            parent.push(buf);
-            normalize(parent)
+            let mut relative = parent.to_owned();
+            relative.pop();
+            relative.push(&buf);
+            self.dir
+                .find(iter::once(relative).chain(self.dir.dirs.iter().map(|dir| dir.join(&buf))))
+                .map(normalize)
         }
     }
 }
@@ -190,6 +479,116 @@ struct RawConfig<'a> {
     debug: Option<PrintOption<'a>>,
     #[serde(borrow)]
     partials: Option<BTreeMap<&'a str, &'a str>>,
+    #[serde(borrow)]
+    packages: Option<BTreeMap<&'a str, &'a str>>,
+    #[serde(borrow)]
+    extensions: Option<BTreeMap<&'a str, &'a str>>,
+    #[serde(borrow)]
+    sanitize: Option<SanitizeOption<'a>>,
+    minify: Option<MinifyOption>,
+    #[serde(borrow)]
+    assets: Option<AssetsOption<'a>>,
+    #[serde(borrow)]
+    vars: Option<BTreeMap<&'a str, toml::Value>>,
+}
+
+/// Rust expression of a TOML value
+fn to_expr(v: &toml::Value) -> Result<String, &'static str> {
+    use toml::Value::*;
+    Ok(match v {
+        String(s) => format!("{:?}", s),
+        Integer(i) => i.to_string(),
+        Float(f) => format!("{:?}", f),
+        Boolean(b) => b.to_string(),
+        Array(a) => format!(
+            "[{}]",
+            a.iter()
+                .map(to_expr)
+                .collect::<Result<Vec<_>, _>>()?
+                .join(", ")
+        ),
+        Datetime(_) | Table(_) => return Err("unsupported TOML value"),
+    })
+}
+
+/// Environment variable with the `cfg` of the compiled crate, set by `recompile::when_changed`
+pub const CFG_ENV: &str = "YARTE_CFG";
+
+/// Configuration options of the compiled crate, `unix`, `target_os = "linux"` or `feature = "json"`
+#[derive(Debug, Default, PartialEq)]
+pub struct Cfg(BTreeSet<(String, Option<String>)>);
+
+impl Cfg {
+    /// Options of build script environment, `CARGO_FEATURE_*` and `CARGO_CFG_*`
+    pub fn from_build_env() -> Self {
+        let mut cfg = BTreeSet::new();
+        for (k, v) in env::vars() {
+            if let Some(name) = k.strip_prefix("CARGO_FEATURE_") {
+                cfg.insert(("feature".into(), Some(feature(name))));
+            } else if let Some(name) = k.strip_prefix("CARGO_CFG_") {
+                let name = name.to_ascii_lowercase();
+                if name == "feature" {
+                    continue;
+                }
+                if v.is_empty() {
+                    cfg.insert((name, None));
+                } else {
+                    for v in v.split(',') {
+                        cfg.insert((name.clone(), Some(v.into())));
+                    }
+                }
+            }
+        }
+        Cfg(cfg)
+    }
+
+    /// Options forwarded by the build script in `YARTE_CFG`
+    pub fn from_env() -> Option<Self> {
+        env::var(CFG_ENV).ok().map(|s| s.parse().unwrap())
+    }
+
+    /// Option is set, feature names ignore case and `-`
+    pub fn is_set(&self, name: &str, value: Option<&str>) -> bool {
+        let value = match (name, value) {
+            ("feature", Some(v)) => Some(feature(v)),
+            (_, v) => v.map(Into::into),
+        };
+        self.0.contains(&(name.into(), value))
+    }
+}
+
+fn feature(name: &str) -> String {
+    name.to_ascii_lowercase().replace('-', "_")
+}
+
+impl std::fmt::Display for Cfg {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (i, (name, value)) in self.0.iter().enumerate() {
+            if i != 0 {
+                f.write_str(";")?;
+            }
+            f.write_str(name)?;
+            if let Some(value) = value {
+                write!(f, "={}", value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Cfg {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Cfg(s
+            .split(';')
+            .filter(|x| !x.is_empty())
+            .map(|x| match x.find('=') {
+                Some(at) => (x[..at].into(), Some(x[at + 1..].into())),
+                None => (x.into(), None),
+            })
+            .collect()))
+    }
 }
 
 #[derive(Deserialize)]
@@ -197,6 +596,8 @@ struct Main<'a> {
     #[serde(borrow)]
     dir: Option<&'a str>,
     #[serde(borrow)]
+    dirs: Option<Vec<&'a str>>,
+    #[serde(borrow)]
     debug: Option<&'a str>,
 }
 
@@ -226,23 +627,149 @@ impl<'a> Default for PrintOption<'a> {
     }
 }
 
+#[derive(Debug, Default, Deserialize)]
+pub struct SanitizeOption<'a> {
+    #[serde(borrow)]
+    pub tags: Option<Vec<&'a str>>,
+    #[serde(borrow)]
+    pub attributes: Option<Vec<&'a str>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AssetsOption<'a> {
+    #[serde(borrow)]
+    pub manifest: Option<&'a str>,
+    #[serde(borrow)]
+    pub prefix: Option<&'a str>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct MinifyOption {
+    pub remove_comments: bool,
+    pub keep_conditional_comments: bool,
+    pub omit_closing_tags: bool,
+    pub remove_attr_quotes: bool,
+    pub collapse_boolean_attrs: bool,
+    pub remove_default_attrs: bool,
+    pub minify_css: bool,
+    pub minify_js: bool,
+}
+
+impl Default for MinifyOption {
+    fn default() -> Self {
+        Self {
+            remove_comments: true,
+            keep_conditional_comments: true,
+            omit_closing_tags: false,
+            remove_attr_quotes: false,
+            collapse_boolean_attrs: false,
+            remove_default_attrs: false,
+            minify_css: false,
+            minify_js: false,
+        }
+    }
+}
+
 pub fn read_config_file() -> String {
     let filename = config_file_path();
     if filename.exists() {
-        fs::read_to_string(&filename)
+        read_file(&filename)
             .unwrap_or_else(|_| panic!("unable to read {}", filename.to_str().unwrap()))
     } else {
         String::new()
     }
 }
 
+/// Parsed `yarte.toml` with the source it borrows
+pub struct ConfigFile {
+    // Dropped before its source
+    config: Config<'static>,
+    _src: Box<str>,
+}
+
+impl ConfigFile {
+    fn new(src: String) -> ConfigFile {
+        let src = src.into_boxed_str();
+        // Safety: the boxed source doesn't move and outlives `config`,
+        // which is only borrowed for the lifetime of `self`
+        let config = Config::new(unsafe { &*(&*src as *const str) });
+        ConfigFile { config, _src: src }
+    }
+
+    pub fn get(&self) -> &Config<'_> {
+        &self.config
+    }
+}
+
+/// Parsed configurations kept for the next derives of the crates in compilation
+const CONFIGS_LEN: usize = 8;
+
+/// Configuration of the crate in compilation
+///
+/// Parsed once by content of `yarte.toml`, manifest directory and `cfg`,
+/// keeps the last used configurations
+pub fn get_config() -> Rc<ConfigFile> {
+    thread_local! {
+        static CONFIGS: RefCell<Vec<(u64, Rc<ConfigFile>)>> = RefCell::default();
+    }
+
+    let src = read_config_file();
+    let key = crate::calculate_hash(&(
+        &src,
+        env::var_os("CARGO_MANIFEST_DIR"),
+        env::var_os(CFG_ENV),
+    ));
+    CONFIGS.with(|configs| {
+        let mut configs = configs.borrow_mut();
+        let config = match configs.iter().position(|(k, _)| *k == key) {
+            Some(i) => configs.remove(i).1,
+            None => Rc::new(ConfigFile::new(src)),
+        };
+        if CONFIGS_LEN <= configs.len() {
+            configs.remove(0);
+        }
+        configs.push((key, config.clone()));
+        config
+    })
+}
+
+/// Content of `path`, read again only when its modification time or length change
+fn read_file(path: &Path) -> std::io::Result<String> {
+    thread_local! {
+        static FILES: RefCell<BTreeMap<PathBuf, (SystemTime, u64, String)>> = RefCell::default();
+    }
+
+    let meta = fs::metadata(path)?;
+    let stamp = (meta.modified()?, meta.len());
+    FILES.with(|files| {
+        let mut files = files.borrow_mut();
+        match files.get(path) {
+            Some((modified, len, src)) if (*modified, *len) == stamp => Ok(src.clone()),
+            _ => {
+                let src = fs::read_to_string(path)?;
+                files.insert(path.to_owned(), (stamp.0, stamp.1, src.clone()));
+                Ok(src)
+            }
+        }
+    })
+}
+
+/// Build script metadata with the templates directory of `links` package
+fn package_var(links: &str) -> String {
+    format!(
+        "DEP_{}_TEMPLATES",
+        links.to_ascii_uppercase().replace('-', "_")
+    )
+}
+
 #[inline]
 pub fn config_file_path() -> PathBuf {
     PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap()).join(CONFIG_FILE_NAME)
 }
 
 pub fn get_source(path: &Path) -> String {
-    match fs::read_to_string(path) {
+    match read_file(path) {
         Ok(mut source) => match source
             .as_bytes()
             .iter()
@@ -260,3 +787,104 @@ pub fn get_source(path: &Path) -> String {
 
 static CONFIG_FILE_NAME: &str = "yarte.toml";
 static DEFAULT_DIR: &str = "templates";
+
+#[cfg(test)]
+mod test {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn vars() {
+        let config = Config::new(
+            "[vars]\nenv = \"prod\"\nlevel = 2\nratio = 1.0\ndebug = false\nlangs = [\"en\", \"es\"]",
+        );
+        assert_eq!(config.vars["env"], "\"prod\"");
+        assert_eq!(config.vars["level"], "2");
+        assert_eq!(config.vars["ratio"], "1.0");
+        assert_eq!(config.vars["debug"], "false");
+        assert_eq!(config.vars["langs"], "[\"en\", \"es\"]");
+    }
+
+    #[test]
+    fn cfg() {
+        let cfg: Cfg = "debug_assertions;feature=html_min;target_os=linux"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            cfg.to_string(),
+            "debug_assertions;feature=html_min;target_os=linux"
+        );
+        assert!(cfg.is_set("debug_assertions", None));
+        assert!(cfg.is_set("feature", Some("html-min")));
+        assert!(cfg.is_set("target_os", Some("linux")));
+        assert!(!cfg.is_set("target_os", Some("windows")));
+        assert!(!cfg.is_set("unix", None));
+        assert_eq!("".parse::<Cfg>().unwrap(), Cfg::default());
+    }
+
+    fn dirs() -> (TempDir, PathBuf, PathBuf) {
+        let root = TempDir::new().unwrap();
+        let (a, b) = (root.path().join("a"), root.path().join("b"));
+        fs::create_dir_all(a.join("partials")).unwrap();
+        fs::create_dir_all(&b).unwrap();
+        fs::write(a.join("index.hbs"), "a").unwrap();
+        fs::write(a.join("partials/card.hbs"), "a").unwrap();
+        fs::write(b.join("index.hbs"), "b").unwrap();
+        fs::write(b.join("button.hbs"), "b").unwrap();
+        (root, a, b)
+    }
+
+    #[test]
+    fn search_dirs() {
+        let (_root, a, b) = dirs();
+        let src = format!("[main]\ndirs = [{:?}, {:?}]", a, b);
+        let config = Config::new(&src);
+        assert_eq!(config.get_dir(), &a);
+        assert_eq!(config.get_dirs(), &[a.clone(), b.clone()][..]);
+
+        let (path, src) = config.get_template(Path::new("index.hbs")).unwrap();
+        assert_eq!((path, src.as_str()), (a.join("index.hbs"), "a"));
+        assert!(config.take_missed().is_empty());
+        let (path, _) = config.get_template(Path::new("button.hbs")).unwrap();
+        assert_eq!(path, b.join("button.hbs"));
+        assert_eq!(config.take_missed(), vec![a.join("button.hbs")]);
+        assert!(config.take_files().is_empty());
+        let file = config.get_file("button.hbs").unwrap();
+        assert_eq!(config.take_files(), vec![file]);
+        assert!(config.take_files().is_empty());
+
+        let parent = a.join("partials/card.hbs");
+        let button = config.resolve_partial(&parent, "button").unwrap();
+        assert_eq!(button, normalize(b.join("button.hbs")));
+
+        let err = config.resolve_partial(&parent, "missing").unwrap_err();
+        for path in &[
+            a.join("partials/missing.hbs"),
+            a.join("missing.hbs"),
+            b.join("missing.hbs"),
+        ] {
+            assert!(err.contains(&format!("{:?}", path)), "{}", err);
+        }
+    }
+
+    #[test]
+    fn packages() {
+        let (_root, a, b) = dirs();
+        let src = format!(
+            "[main]\ndir = {:?}\n[packages]\nds = \"yarte-test-ds\"\nnone = \"yarte-test-none\"",
+            a
+        );
+        let mut config = Config::new(&src);
+        config.package_dirs.insert("yarte-test-ds", b.clone());
+
+        let parent = a.join("index.hbs");
+        let index = config.resolve_partial(&parent, "ds/index").unwrap();
+        assert_eq!(index, normalize(b.join("index.hbs")));
+        assert!(config.resolve_partial(&parent, "ds/card").is_err());
+        assert!(config
+            .resolve_partial(&parent, "none/index")
+            .unwrap_err()
+            .contains("DEP_YARTE_TEST_NONE_TEMPLATES"));
+    }
+}
@@ -1,5 +1,4 @@
-use std::ops::{BitAnd, BitOr, BitOrAssign};
-// TODO: to 1024
+use core::ops::{BitAnd, BitOr, BitOrAssign};
 
 // TODO: trait const zero
 pub trait YNumber: Copy + PartialEq + BitOr + BitOrAssign + BitAnd {
@@ -28,7 +27,7 @@ macro_rules! impl_ynumber {
 impl_ynumber!(u8 u16 u32);
 
 #[derive(Clone, Copy, PartialEq)]
-pub struct U64([u32; 2]);
+pub struct U64(pub [u32; 2]);
 
 impl YNumber for U64 {
     #[inline(always)]
@@ -65,7 +64,7 @@ impl BitOrAssign for U64 {
 }
 
 #[derive(Clone, Copy, PartialEq)]
-pub struct U128([u32; 4]);
+pub struct U128(pub [u32; 4]);
 
 impl YNumber for U128 {
     #[inline(always)]
@@ -112,7 +111,7 @@ impl BitOrAssign for U128 {
 }
 
 #[derive(Clone, Copy, PartialEq)]
-pub struct U256([u32; 8]);
+pub struct U256(pub [u32; 8]);
 
 impl YNumber for U256 {
     #[inline(always)]
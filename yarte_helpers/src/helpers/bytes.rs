@@ -264,7 +264,7 @@ impl<'a, B: Buffer> io::Write for UnsafeWriter<'a, B> {
 #[cfg(feature = "json")]
 mod json {
     use super::*;
-    use crate::at_helpers::Json;
+    use crate::at_helpers::{escape_script, Json, JsonScript};
     use crate::helpers::json::{self, to_bytes_mut};
 
     impl<'a, S: json::Serialize> RenderBytes for Json<'a, S> {
@@ -280,6 +280,47 @@ mod json {
             to_bytes_mut(self.0, buf)
         }
     }
+
+    #[inline]
+    fn render_script<S: json::Serialize, B: Buffer>(s: &S, buf: &mut B) {
+        // Serialize to a temporary buffer, escape can't be done in place
+        let mut tmp: Vec<u8> = Buffer::with_capacity(0);
+        to_bytes_mut(s, &mut tmp);
+        // Safety: escape only replace whole chars of utf-8 json
+        let _ = escape_script::<()>(&tmp, |b| {
+            unsafe { buf.extend_from_slice(b) };
+            Ok(())
+        });
+    }
+
+    impl<'a, S: json::Serialize> RenderBytes for JsonScript<'a, S> {
+        #[inline(always)]
+        fn render<B: Buffer>(self, buf: &mut B) {
+            render_script(self.0, buf)
+        }
+    }
+
+    impl<'a, S: json::Serialize> RenderBytesSafe for JsonScript<'a, S> {
+        #[inline(always)]
+        fn render<B: Buffer>(self, buf: &mut B) {
+            render_script(self.0, buf)
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+
+        #[test]
+        fn test_script() {
+            let mut buf = String::new();
+            RenderBytes::render(JsonScript(&"</script>"), &mut buf);
+            assert_eq!(buf, "\"\\u003c/script\\u003e\"");
+            buf.clear();
+            RenderBytesSafe::render(JsonScript(&"a\u{2028}b"), &mut buf);
+            assert_eq!(buf, "\"a\\u2028b\"");
+        }
+    }
 }
 
 #[inline(always)]
@@ -1,5 +1,5 @@
 /// Adapted and improve from [`fomat`](https://github.com/krdln/fomat-macros)
-use std::{
+use core::{
     cell::Cell,
     fmt::{self, Display, Formatter},
 };
@@ -7,7 +7,7 @@ use std::{
 /// Closure wrapper
 ///
 /// Wrap closure in mutable reference for dispatch it
-pub struct DisplayFn<F: FnOnce(&mut Formatter) -> fmt::Result>(std::cell::Cell<Option<F>>);
+pub struct DisplayFn<F: FnOnce(&mut Formatter) -> fmt::Result>(Cell<Option<F>>);
 
 impl<F: FnOnce(&mut Formatter) -> fmt::Result> DisplayFn<F> {
     pub fn new(f: F) -> Self {
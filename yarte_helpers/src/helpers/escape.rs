@@ -0,0 +1,46 @@
+// Scalar html escape for `no_std` builds, same table as `v_htmlescape`
+use core::mem::MaybeUninit;
+use core::ptr::copy_nonoverlapping;
+
+#[inline]
+fn escaped(b: u8) -> Option<&'static [u8]> {
+    match b {
+        b'<' => Some(b"&lt;"),
+        b'>' => Some(b"&gt;"),
+        b'&' => Some(b"&amp;"),
+        b'"' => Some(b"&quot;"),
+        b'\'' => Some(b"&#x27;"),
+        b'/' => Some(b"&#x2f;"),
+        _ => None,
+    }
+}
+
+#[inline]
+unsafe fn write(src: &[u8], buf: &mut [MaybeUninit<u8>], cur: usize) -> Option<usize> {
+    if buf.len() < cur + src.len() {
+        None
+    } else {
+        copy_nonoverlapping(src.as_ptr(), (buf as *mut _ as *mut u8).add(cur), src.len());
+        Some(cur + src.len())
+    }
+}
+
+/// Html escape `bytes` in buffer, returns written length or `None` if it doesn't fit
+pub(crate) unsafe fn f_escape(bytes: &[u8], buf: &mut [MaybeUninit<u8>]) -> Option<usize> {
+    let mut start = 0;
+    let mut cur = 0;
+    for (i, b) in bytes.iter().enumerate() {
+        if let Some(esc) = escaped(*b) {
+            cur = write(&bytes[start..i], buf, cur)?;
+            cur = write(esc, buf, cur)?;
+            start = i + 1;
+        }
+    }
+    write(&bytes[start..], buf, cur)
+}
+
+/// Html escape `c` in buffer, returns written length or `None` if it doesn't fit
+pub(crate) unsafe fn f_escape_char(c: char, buf: &mut [MaybeUninit<u8>]) -> Option<usize> {
+    let mut tmp = [0; 4];
+    f_escape(c.encode_utf8(&mut tmp).as_bytes(), buf)
+}
@@ -3,8 +3,15 @@
 use core::mem::MaybeUninit;
 use core::ptr::copy_nonoverlapping;
 use core::slice::from_raw_parts_mut;
+#[cfg(feature = "json")]
 use std::io;
 
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::string::String;
+
+#[cfg(not(feature = "std"))]
+use super::escape::{f_escape, f_escape_char};
+#[cfg(feature = "std")]
 use v_htmlescape::{f_escape, f_escape_char};
 
 use super::ryu::Sealed;
@@ -78,10 +85,9 @@ macro_rules! str_display {
     };
 }
 
-#[rustfmt::skip]
-str_display!(
-    &str &String
-);
+str_display!(&str);
+#[cfg(feature = "alloc")]
+str_display!(&String);
 
 macro_rules! itoa_display {
     ($($ty:ty)*) => {
@@ -113,7 +119,8 @@ macro_rules! itoa128_display {
             impl RenderFixed for $ty {
                 #[inline(always)]
                 unsafe fn render(self, buf: &mut [MaybeUninit<u8>]) -> Option<usize> {
-                    itoa::write(from_raw_parts_mut(buf_ptr!(buf), buf.len()), self).ok()
+                    let mut b = itoa::Buffer::new();
+                    RenderSafe::render(b.format(self), buf)
                 }
             }
         )*
@@ -169,10 +176,9 @@ macro_rules! str_display {
     };
 }
 
-#[rustfmt::skip]
-str_display!(
-    &str &String
-);
+str_display!(&str);
+#[cfg(feature = "alloc")]
+str_display!(&String);
 
 macro_rules! itoa_display {
     ($($ty:ty)*) => {
@@ -204,7 +210,8 @@ macro_rules! itoa128_display {
             impl RenderSafe for $ty {
                 #[inline(always)]
                 unsafe fn render(self, buf: &mut [MaybeUninit<u8>]) -> Option<usize> {
-                    itoa::write(from_raw_parts_mut(buf_ptr!(buf), buf.len()), self).ok()
+                    let mut b = itoa::Buffer::new();
+                    RenderSafe::render(b.format(self), buf)
                 }
             }
         )*
@@ -268,11 +275,13 @@ impl RenderSafe for bool {
     }
 }
 
+#[cfg(feature = "json")]
 struct Writer<'a> {
     buf: &'a mut [MaybeUninit<u8>],
     len: usize,
 }
 
+#[cfg(feature = "json")]
 impl<'a> Writer<'a> {
     #[inline]
     fn new(buf: &mut [MaybeUninit<u8>]) -> Writer {
@@ -285,6 +294,7 @@ impl<'a> Writer<'a> {
     }
 }
 
+#[cfg(feature = "json")]
 impl<'a> io::Write for Writer<'a> {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         if self.buf.len() < buf.len() + self.len {
@@ -308,7 +318,7 @@ impl<'a> io::Write for Writer<'a> {
 #[cfg(feature = "json")]
 mod json {
     use super::*;
-    use crate::at_helpers::{Json, JsonPretty};
+    use crate::at_helpers::{Json, JsonPretty, JsonPrettyScript, JsonScript, ScriptEscape};
     use serde::Serialize;
     use serde_json::{to_writer, to_writer_pretty};
 
@@ -347,6 +357,32 @@ mod json {
             Some(buf.consume())
         }
     }
+
+    macro_rules! script_display {
+        ($($t:ident)+) => {
+        $(
+            impl<'a, S: Serialize> $t for JsonScript<'a, S> {
+                #[inline(always)]
+                unsafe fn render(self, buf: &mut [MaybeUninit<u8>]) -> Option<usize> {
+                    let mut buf = ScriptEscape(Writer::new(buf));
+                    to_writer(&mut buf, self.0).ok()?;
+                    Some(buf.0.consume())
+                }
+            }
+
+            impl<'a, D: Serialize> $t for JsonPrettyScript<'a, D> {
+                #[inline(always)]
+                unsafe fn render(self, buf: &mut [MaybeUninit<u8>]) -> Option<usize> {
+                    let mut buf = ScriptEscape(Writer::new(buf));
+                    to_writer_pretty(&mut buf, self.0).ok()?;
+                    Some(buf.0.consume())
+                }
+            }
+        )+
+        };
+    }
+
+    script_display!(RenderFixed RenderSafe);
 }
 
 #[inline(always)]
@@ -380,7 +416,7 @@ unsafe fn render_bool(b: bool, buf: &mut [MaybeUninit<u8>]) -> Option<usize> {
 #[cfg(test)]
 mod test {
     use super::*;
-    use std::slice::from_raw_parts;
+    use core::slice::from_raw_parts;
 
     macro_rules! slice {
         ($b:ident, $len:expr) => {
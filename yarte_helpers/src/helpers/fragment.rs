@@ -0,0 +1,261 @@
+//! Storage of rendered `{{#cache}}` blocks
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap},
+    hash::{Hash, Hasher},
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+/// Identity of a rendered fragment
+///
+/// Keys are compared by the bytes which the template type and the block `key`
+/// write to a `Hasher`, the hash of these bytes is only used for bucketing
+#[derive(Clone, Debug)]
+pub struct FragmentKey {
+    /// Template path
+    pub path: &'static str,
+    /// Line and column of the block
+    pub start: (usize, usize),
+    /// Hashed bytes of the template type and the block `key`
+    pub key: Box<[u8]>,
+    hash: u64,
+}
+
+/// Hasher which keeps the written bytes
+#[derive(Default)]
+struct KeyBytes(Vec<u8>);
+
+impl Hasher for KeyBytes {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes)
+    }
+
+    fn finish(&self) -> u64 {
+        let mut s = DefaultHasher::new();
+        s.write(&self.0);
+        s.finish()
+    }
+}
+
+impl FragmentKey {
+    pub fn new<K: Hash + ?Sized>(
+        path: &'static str,
+        start: (usize, usize),
+        ty: &'static str,
+        key: &K,
+    ) -> Self {
+        let mut s = KeyBytes::default();
+        ty.hash(&mut s);
+        key.hash(&mut s);
+        let mut hash = DefaultHasher::new();
+        path.hash(&mut hash);
+        start.hash(&mut hash);
+        hash.write_u64(s.finish());
+        FragmentKey {
+            path,
+            start,
+            key: s.0.into(),
+            hash: hash.finish(),
+        }
+    }
+}
+
+impl PartialEq for FragmentKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.hash == other.hash
+            && self.path == other.path
+            && self.start == other.start
+            && self.key == other.key
+    }
+}
+
+impl Eq for FragmentKey {}
+
+impl Hash for FragmentKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash)
+    }
+}
+
+/// Cache backend of `{{#cache}}` blocks
+pub trait FragmentCache: Send + Sync {
+    /// Rendered fragment, if any and not expired
+    fn get(&self, key: &FragmentKey) -> Option<Arc<str>>;
+
+    /// Stores a rendered fragment for `ttl` or until evicted
+    fn insert(&self, key: FragmentKey, fragment: Arc<str>, ttl: Option<Duration>);
+}
+
+struct Entry {
+    fragment: Arc<str>,
+    expires: Option<Instant>,
+    tick: u64,
+}
+
+#[derive(Default)]
+struct Lru {
+    tick: u64,
+    entries: HashMap<FragmentKey, Entry>,
+    order: BTreeMap<u64, FragmentKey>,
+}
+
+/// In-memory cache, evicts the least recently used fragment when it's full
+pub struct LruCache {
+    capacity: usize,
+    lru: Mutex<Lru>,
+}
+
+impl LruCache {
+    pub fn new(capacity: usize) -> Self {
+        LruCache {
+            capacity,
+            lru: Mutex::default(),
+        }
+    }
+
+    /// Number of stored fragments, expired ones included
+    pub fn len(&self) -> usize {
+        self.lru.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        *self.lru.lock().unwrap() = Lru::default();
+    }
+}
+
+impl Default for LruCache {
+    fn default() -> Self {
+        LruCache::new(1024)
+    }
+}
+
+impl FragmentCache for LruCache {
+    fn get(&self, key: &FragmentKey) -> Option<Arc<str>> {
+        let mut lru = self.lru.lock().unwrap();
+        let Lru {
+            tick,
+            entries,
+            order,
+        } = &mut *lru;
+        let entry = entries.get_mut(key)?;
+        order.remove(&entry.tick);
+        if entry.expires.is_some_and(|x| x <= Instant::now()) {
+            entries.remove(key);
+            return None;
+        }
+        *tick += 1;
+        entry.tick = *tick;
+        order.insert(*tick, key.clone());
+        Some(entry.fragment.clone())
+    }
+
+    fn insert(&self, key: FragmentKey, fragment: Arc<str>, ttl: Option<Duration>) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut lru = self.lru.lock().unwrap();
+        let Lru {
+            tick,
+            entries,
+            order,
+        } = &mut *lru;
+        if let Some(old) = entries.remove(&key) {
+            order.remove(&old.tick);
+        } else if self.capacity <= entries.len() {
+            if let Some((_, oldest)) = order.pop_first() {
+                entries.remove(&oldest);
+            }
+        }
+        *tick += 1;
+        order.insert(*tick, key.clone());
+        entries.insert(
+            key,
+            Entry {
+                fragment,
+                expires: ttl.map(|ttl| Instant::now() + ttl),
+                tick: *tick,
+            },
+        );
+    }
+}
+
+static CACHE: OnceLock<Box<dyn FragmentCache>> = OnceLock::new();
+
+/// Sets the backend of `{{#cache}}` blocks, returns it back if the cache is already in use
+pub fn set_fragment_cache<C: FragmentCache + 'static>(
+    cache: C,
+) -> Result<(), Box<dyn FragmentCache>> {
+    CACHE.set(Box::new(cache))
+}
+
+/// Backend of `{{#cache}}` blocks, a `LruCache` of 1024 fragments by default
+pub fn fragment_cache() -> &'static dyn FragmentCache {
+    &**CACHE.get_or_init(|| Box::new(LruCache::default()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn key(i: usize) -> FragmentKey {
+        FragmentKey::new("nav.hbs", (1, 10), "Nav", &i)
+    }
+
+    #[test]
+    fn lru() {
+        let cache = LruCache::new(2);
+        cache.insert(key(0), "zero".into(), None);
+        cache.insert(key(1), "one".into(), None);
+        assert_eq!(cache.get(&key(0)).as_deref(), Some("zero"));
+
+        cache.insert(key(2), "two".into(), None);
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&key(1)).is_none());
+        assert_eq!(cache.get(&key(0)).as_deref(), Some("zero"));
+        assert_eq!(cache.get(&key(2)).as_deref(), Some("two"));
+
+        cache.insert(key(2), "dos".into(), None);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&key(2)).as_deref(), Some("dos"));
+    }
+
+    #[test]
+    fn ttl() {
+        let cache = LruCache::default();
+        cache.insert(key(0), "zero".into(), Some(Duration::from_secs(0)));
+        cache.insert(key(1), "one".into(), Some(Duration::from_secs(300)));
+        assert!(cache.get(&key(0)).is_none());
+        assert_eq!(cache.get(&key(1)).as_deref(), Some("one"));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn key_scope() {
+        assert_ne!(key(0), FragmentKey::new("nav.hbs", (2, 10), "Nav", &0usize));
+        assert_ne!(
+            key(0),
+            FragmentKey::new("nav.hbs", (1, 10), "Menu", &0usize)
+        );
+        assert_eq!(key(0), FragmentKey::new("nav.hbs", (1, 10), "Nav", &0usize));
+    }
+
+    #[test]
+    fn key_collision() {
+        let a = FragmentKey::new("nav.hbs", (1, 10), "Nav", "a");
+        // Same hash with other key bytes
+        let b = FragmentKey {
+            key: (*b"Nav\xffb\xff").into(),
+            ..a.clone()
+        };
+        assert_ne!(a, b);
+
+        let cache = LruCache::default();
+        cache.insert(a.clone(), "a".into(), None);
+        assert!(cache.get(&b).is_none());
+        assert_eq!(cache.get(&a).as_deref(), Some("a"));
+    }
+}
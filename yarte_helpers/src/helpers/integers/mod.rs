@@ -1,6 +1,6 @@
 // based on https://github.com/miloyip/itoa-benchmark
 
-#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+#[cfg(all(feature = "std", any(target_arch = "x86_64", target_arch = "x86")))]
 mod v_integer;
 
 static DIGITS_LUT: &[u8] = b"\
@@ -287,7 +287,8 @@ mod fallback {
     }
 }
 
-#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+// Runtime feature detection needs `std`, `no_std` builds use the fallback
+#[cfg(all(feature = "std", any(target_arch = "x86_64", target_arch = "x86")))]
 macro_rules! detect_fn {
     ($name:ident, $t:ty) => {
         // https://github.com/BurntSushi/rust-memchr/blob/master/src/x86/mod.rs#L9-L29
@@ -315,11 +316,11 @@ macro_rules! detect_fn {
     };
 }
 
-#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+#[cfg(all(feature = "std", any(target_arch = "x86_64", target_arch = "x86")))]
 detect_fn!(write_u32, u32);
-#[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+#[cfg(all(feature = "std", any(target_arch = "x86_64", target_arch = "x86")))]
 detect_fn!(write_u64, u64);
-#[cfg(not(any(target_arch = "x86_64", target_arch = "x86")))]
+#[cfg(not(all(feature = "std", any(target_arch = "x86_64", target_arch = "x86"))))]
 use fallback::*;
 
 pub trait Integer {
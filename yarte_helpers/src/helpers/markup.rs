@@ -109,7 +109,7 @@ raw_display! {
 #[cfg(feature = "json")]
 mod json {
     use super::*;
-    use crate::at_helpers::{Json, JsonPretty};
+    use crate::at_helpers::{Json, JsonPretty, JsonPrettyScript, JsonScript, ScriptEscape};
     use serde::Serialize;
     use serde_json::{to_writer, to_writer_pretty};
 
@@ -126,4 +126,18 @@ mod json {
             to_writer_pretty(IoFmt::new(f), self.0).map_err(|_| fmt::Error)
         }
     }
+
+    impl<'a, S: Serialize> Render for JsonScript<'a, S> {
+        #[inline(always)]
+        fn render(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            to_writer(ScriptEscape(IoFmt::new(f)), self.0).map_err(|_| fmt::Error)
+        }
+    }
+
+    impl<'a, D: Serialize> Render for JsonPrettyScript<'a, D> {
+        #[inline(always)]
+        fn render(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            to_writer_pretty(ScriptEscape(IoFmt::new(f)), self.0).map_err(|_| fmt::Error)
+        }
+    }
 }
@@ -1,5 +1,7 @@
+#[cfg(feature = "std")]
 pub mod cow;
 pub mod integers;
+#[cfg(feature = "std")]
 pub mod io_fmt;
 
 #[repr(align(32))]
@@ -16,8 +18,12 @@ pub mod display_fn;
 
 #[cfg(any(feature = "bytes-buf", feature = "json"))]
 mod bytes;
+#[cfg(all(feature = "fixed", not(feature = "std")))]
+mod escape;
 #[cfg(feature = "fixed")]
 mod fixed;
+#[cfg(feature = "bytes-buf")]
+pub mod fragment;
 #[cfg(feature = "markup")]
 mod markup;
 #[cfg(feature = "json")]
@@ -40,6 +46,20 @@ pub mod json {
     };
 }
 
+/// Next capacity of `adaptive` templates from the current one and the rendered length
+///
+/// Grows to a bigger render at once, so similar renders don't reallocate,
+/// and smaller renders decay it by an eighth of the difference, an exponential
+/// moving average, so an outlier stops reserving its length after a few dozen renders
+#[inline]
+pub fn adapt_size(capacity: usize, len: usize) -> usize {
+    if capacity <= len {
+        len
+    } else {
+        capacity - (capacity - len) / 8
+    }
+}
+
 pub trait IntoCopyIterator: IntoIterator {
     fn __into_citer(self) -> <Self as IntoIterator>::IntoIter;
 }
@@ -1,4 +1,10 @@
-pub use std::fmt::Error;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+extern crate alloc;
+
+pub use core::fmt::Error;
+#[cfg(feature = "std")]
 use std::{
     collections::hash_map::DefaultHasher,
     env,
@@ -7,7 +13,7 @@ use std::{
     process::Command,
 };
 
-pub type Result<I> = ::std::result::Result<I, Error>;
+pub type Result<I> = ::core::result::Result<I, Error>;
 
 #[cfg(feature = "config")]
 pub mod config;
@@ -20,12 +26,14 @@ pub mod helpers;
 #[cfg(all(feature = "logger", not(target_arch = "wasm32")))]
 pub mod logger;
 
+#[cfg(feature = "std")]
 pub fn calculate_hash<T: Hash>(t: &T) -> u64 {
     let mut s = DefaultHasher::new();
     t.hash(&mut s);
     s.finish()
 }
 
+#[cfg(feature = "std")]
 pub fn definitely_not_nightly() -> bool {
     let mut cmd = Command::new(cargo_binary());
     cmd.arg("--version");
@@ -43,6 +51,7 @@ pub fn definitely_not_nightly() -> bool {
     version.starts_with("cargo 1") && !version.contains("nightly")
 }
 
+#[cfg(feature = "std")]
 fn cargo_binary() -> OsString {
     env::var_os("CARGO").unwrap_or_else(|| "cargo".to_owned().into())
 }
@@ -1,30 +1,40 @@
-use std::fs;
+use std::env;
 
-use crate::config::{config_file_path, read_config_file, Config};
+use crate::config::{config_file_path, read_config_file, Cfg, Config, CFG_ENV};
 
-/// Recompile when changed. Put me on your `build.rs`
+/// Forwards features and `cfg` of the crate to `cfg!` and `[packages]` to the derive.
+/// Put me on your `build.rs`
+///
+/// Templates, partials and included files are tracked by the derive, so only `cfg!(..)`
+/// in templates and `[packages]` templates need it. A new file shadowing a template of a
+/// later search directory is tracked with `RUSTFLAGS="--cfg yarte_tracked_path"` in nightly
 pub fn when_changed() {
+    println!("cargo:rustc-env={}={}", CFG_ENV, Cfg::from_build_env());
+
     // rerun when config file change
     println!(
         "cargo:rerun-if-changed={}",
         config_file_path().to_str().unwrap()
     );
 
+    // forward templates directories of `[packages]` to the derive
+    for (key, value) in env::vars() {
+        if key.starts_with("DEP_") && key.ends_with("_TEMPLATES") {
+            println!("cargo:rerun-if-env-changed={}", key);
+            println!("cargo:rustc-env={}={}", key, value);
+        }
+    }
+}
+
+/// Export templates directory to dependent crates. Put me on your `build.rs`
+///
+/// Needs the `links` key in `Cargo.toml`, dependents use it at `[packages]`
+pub fn export_templates() {
     let file = read_config_file();
     let config = Config::new(&file);
 
-    let mut stack = vec![config.get_dir().clone()];
-    while let Some(dir) = stack.pop() {
-        // rerun when dir change
-        println!("cargo:rerun-if-changed={}", dir.to_str().unwrap());
-        for entry in fs::read_dir(dir).expect("valid directory") {
-            let path = entry.expect("valid directory entry").path();
-            if path.is_dir() {
-                stack.push(path);
-            } else {
-                // rerun when file change
-                println!("cargo:rerun-if-changed={}", path.to_str().unwrap());
-            }
-        }
-    }
+    // `links` is required to export metadata
+    env::var("CARGO_MANIFEST_LINKS").expect("`links` key in Cargo.toml");
+    println!("cargo:templates={}", config.get_dir().to_str().unwrap());
+    when_changed();
 }
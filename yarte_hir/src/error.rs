@@ -75,4 +75,18 @@ pub enum GError {
     Internal,
     #[display(fmt = "use super without any parent")]
     SuperWithoutParent,
+    #[display(fmt = "expected a string literal path")]
+    AtHelperPath,
+    #[display(fmt = "{}", _0)]
+    AtHelperFile(String),
+    #[display(
+        fmt = "component partials are only available in `App` derives with `wasm-component` feature"
+    )]
+    Component,
+    #[display(fmt = "expected an application type after `@`")]
+    ComponentPath,
+    #[display(fmt = "component partial arguments are `field = expr` or `forward = path`")]
+    ComponentArguments,
+    #[display(fmt = "`cache` helper is only available in `TemplateBytes` derives")]
+    Cache,
 }
@@ -8,6 +8,8 @@ pub enum HIR {
     Each(Box<Each>),
     IfElse(Box<IfElse>),
     Local(Box<syn::Local>),
+    Cache(Box<Cache>),
+    Component(Box<Component>),
 }
 
 // TODO: to switch
@@ -24,4 +26,30 @@ pub struct Each {
     pub args: syn::Expr,
     pub body: Vec<HIR>,
     pub expr: syn::Expr,
+    /// Identity of each item, `{{#each args key = expr }}`
+    pub key: Option<syn::Expr>,
+}
+
+/// Fragment cached by template path, block position and `key`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cache {
+    /// Template path relative to templates directory
+    pub path: String,
+    /// Line and column of the block arguments
+    pub start: (usize, usize),
+    pub key: syn::Expr,
+    /// Time to live in seconds, `{{#cache key = expr ttl = expr }}`
+    pub ttl: Option<syn::Expr>,
+    pub body: Vec<HIR>,
+}
+
+/// Nested application, `{{> @Path field = expr, forward = path }}`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Component {
+    /// Application type
+    pub path: syn::Path,
+    /// Initial state fields of the application
+    pub props: Vec<(syn::Ident, syn::Expr)>,
+    /// `fn(&Child::Message) -> Option<Parent::Message>`
+    pub forward: Option<syn::Expr>,
 }
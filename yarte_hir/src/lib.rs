@@ -3,7 +3,7 @@
     clippy::type_complexity,
     clippy::match_on_vec_items
 )]
-use std::{collections::BTreeMap, mem, path::PathBuf, str};
+use std::{collections::BTreeMap, fs, mem, path::PathBuf, str};
 
 use quote::{format_ident, quote};
 use syn::{
@@ -43,14 +43,23 @@ use self::{
 pub use self::{
     hir::*,
     serialize::{serialize, serialize_resolved},
-    visit_derive::{visit_derive, Print, Struct},
+    visit_derive::{visit_derive, Bootstrap, Print, Struct},
 };
 
+/// Event values available in resolve expressions, `{{? Msg::Input(value) }}`
+///
+/// They shadow the fields of the state with the same name, use `self.value` for those
+pub const EVENT_ARGS: &[&str] = &["__event", "value", "checked", "key", "key_code"];
+
 #[derive(Copy, Clone, Debug)]
 pub struct HIROptions {
     pub is_text: bool,
     pub resolve_to_self: bool,
     pub parent: &'static str,
+    /// Lower `{{#cache}}` blocks to `HIR::Cache`, otherwise they are an error
+    pub cache: bool,
+    /// Lower `{{> @Path }}` partials to `HIR::Component`, otherwise they are an error
+    pub components: bool,
 }
 
 impl Default for HIROptions {
@@ -59,6 +68,8 @@ impl Default for HIROptions {
             resolve_to_self: true,
             is_text: false,
             parent: "yarte",
+            cache: false,
+            components: false,
         }
     }
 }
@@ -67,8 +78,9 @@ pub fn generate(
     c: &Config,
     s: &Struct,
     ctx: Context,
-    opt: HIROptions,
+    mut opt: HIROptions,
 ) -> Result<Vec<HIR>, Vec<ErrorMessage<GError>>> {
+    opt.is_text |= s.mode.is_text();
     LoweringContext::new(c, s, ctx, opt).build()
 }
 
@@ -134,6 +146,12 @@ struct LoweringContext<'a> {
     skip_ws: bool,
     // Copiable
     recursion: usize,
+    /// Last literal left open a `<script>` tag
+    // Copiable
+    in_script: bool,
+    /// Last literal left open a `<style>` tag
+    // Copiable
+    in_style: bool,
 }
 
 // TODO: remove in favor of mut reference
@@ -154,6 +172,8 @@ impl<'a> Clone for LoweringContext<'a> {
             ctx: self.ctx,
             on_path: self.on_path.clone(),
             recursion: self.recursion,
+            in_script: self.in_script,
+            in_style: self.in_style,
             next_ws: self.next_ws,
             skip_ws: self.skip_ws,
         }
@@ -182,6 +202,8 @@ impl<'a> LoweringContext<'a> {
             errors: vec![],
             block: vec![],
             recursion: 0,
+            in_script: false,
+            in_style: false,
             buf_err: vec![],
             spans: vec![],
         }
@@ -247,7 +269,9 @@ impl<'a> LoweringContext<'a> {
 
                     if self.const_eval(&expr, false).is_none() {
                         validator::expression(sexpr, &mut self.errors);
-                        self.buf_w.push(Writable::Expr(Box::new(expr), false));
+                        // Text mode never escape
+                        let safe = self.opt.is_text;
+                        self.buf_w.push(Writable::Expr(Box::new(expr), safe));
                     }
                 }
                 #[cfg(feature = "wasm-app")]
@@ -255,7 +279,14 @@ impl<'a> LoweringContext<'a> {
                     let mut expr = (***sexpr.t()).clone();
 
                     self.handle_ws(*ws);
+                    self.scp.push_scope(
+                        EVENT_ARGS
+                            .iter()
+                            .map(|x| syn::parse_str(x).expect("ident"))
+                            .collect(),
+                    );
                     self.visit_expr_mut(&mut expr);
+                    self.scp.pop();
                     self.write_errors(sexpr.span());
 
                     self.buf_w.push(Writable::LitP(quote!(#expr).to_string()));
@@ -266,6 +297,14 @@ impl<'a> LoweringContext<'a> {
                     self.visit_helper(buf, &h);
                     self.spans.pop();
                 }
+                Node::Partial(Partial(ws, path, expr)) if path.t().starts_with('@') => {
+                    if let Err(message) = self.visit_component(buf, *ws, &path.t()[1..], expr) {
+                        self.errors.push(ErrorMessage {
+                            message,
+                            span: n.span(),
+                        });
+                    }
+                }
                 Node::Partial(Partial(ws, path, expr)) => {
                     if let Err(message) = self.visit_partial(buf, *ws, path.t(), expr, None) {
                         self.errors.push(ErrorMessage {
@@ -286,6 +325,8 @@ impl<'a> LoweringContext<'a> {
                     if let Some((i_ws, block, mut old)) = self.block.pop() {
                         old.next_ws = self.next_ws.take();
                         old.skip_ws = self.skip_ws;
+                        old.in_script = self.in_script;
+                        old.in_style = self.in_style;
                         old.scp.count = self.scp.count;
                         old.buf_w.extend(self.buf_w.drain(..));
 
@@ -299,6 +340,8 @@ impl<'a> LoweringContext<'a> {
                         self.scp.count = old.scp.count;
                         self.next_ws = old.next_ws.take();
                         self.skip_ws = old.skip_ws;
+                        self.in_script = old.in_script;
+                        self.in_style = old.in_style;
 
                         self.handle_ws((i_ws.1, ws.1));
 
@@ -312,6 +355,12 @@ impl<'a> LoweringContext<'a> {
                         self.prepare_ws(*ws);
                     }
                 }
+                Node::PartialBlock(PartialBlock(_, path, ..)) if path.t().starts_with('@') => {
+                    self.errors.push(ErrorMessage {
+                        message: GError::ComponentArguments,
+                        span: n.span(),
+                    })
+                }
                 Node::PartialBlock(PartialBlock(ws, path, expr, block)) => {
                     if let Err(message) =
                         self.visit_partial(buf, ws.0, path.t(), expr, Some((ws.1, block)))
@@ -335,18 +384,44 @@ impl<'a> LoweringContext<'a> {
                     self.handle_ws(*ws);
                     use AtHelperKind::*;
                     match e {
-                        Json => {
+                        Json | JsonScript if self.in_script || *e == JsonScript => {
+                            let mut arg = (*args.t()[0]).clone();
+                            self.visit_expr_mut(&mut arg);
+                            let expr = parse2(quote!((&(#arg).__as_json_script()))).unwrap();
+                            self.buf_w.push(Writable::Expr(Box::new(expr), false))
+                        }
+                        Json | JsonScript => {
                             let mut arg = (*args.t()[0]).clone();
                             self.visit_expr_mut(&mut arg);
                             let expr = parse2(quote!((&(#arg).__as_json()))).unwrap();
                             self.buf_w.push(Writable::Expr(Box::new(expr), false))
                         }
+                        JsonPretty if self.in_script => {
+                            let mut arg = (*args.t()[0]).clone();
+                            self.visit_expr_mut(&mut arg);
+                            let expr = parse2(quote!(&(#arg).__as_json_pretty_script())).unwrap();
+                            self.buf_w.push(Writable::Expr(Box::new(expr), false))
+                        }
                         JsonPretty => {
                             let mut arg = (*args.t()[0]).clone();
                             self.visit_expr_mut(&mut arg);
                             let expr = parse2(quote!(&(#arg).__as_json_pretty())).unwrap();
                             self.buf_w.push(Writable::Expr(Box::new(expr), false))
                         }
+                        Sanitize => {
+                            let mut arg = (*args.t()[0]).clone();
+                            self.visit_expr_mut(&mut arg);
+                            let allow = self.allowlist();
+                            let expr = parse2(quote!((&(#arg).__as_sanitize(#allow)))).unwrap();
+                            self.buf_w.push(Writable::Expr(Box::new(expr), false))
+                        }
+                        IncludeStr | IncludeRaw | Asset => match self.at_file(e, &args.t()[0]) {
+                            Ok(src) => self.buf_w.push(Writable::LitP(src)),
+                            Err(message) => self.errors.push(ErrorMessage {
+                                message,
+                                span: args.span(),
+                            }),
+                        },
                     }
                 }
                 #[allow(unreachable_patterns)]
@@ -355,6 +430,25 @@ impl<'a> LoweringContext<'a> {
         }
     }
 
+    /// Allowlist of `@sanitize` from configuration file, missing lists use the default
+    fn allowlist(&self) -> proc_macro2::TokenStream {
+        let sanitize = &self.c.sanitize;
+        match (&sanitize.tags, &sanitize.attributes) {
+            (None, None) => quote!(yarte::Allowlist::DEFAULT),
+            (tags, attrs) => {
+                let tags = tags.as_ref().map_or_else(
+                    || quote!(yarte::Allowlist::DEFAULT.tags),
+                    |x| quote!(&[#(#x),*]),
+                );
+                let attrs = attrs.as_ref().map_or_else(
+                    || quote!(yarte::Allowlist::DEFAULT.attrs),
+                    |x| quote!(&[#(#x),*]),
+                );
+                quote!(yarte::Allowlist { tags: #tags, attrs: #attrs })
+            }
+        }
+    }
+
     // TODO:
     fn format_error(&mut self, err: &SVExpr) -> Option<String> {
         if let Some(first) = err.t().first().map(|x| &**x) {
@@ -387,6 +481,7 @@ impl<'a> LoweringContext<'a> {
         }
 
         if !lit.is_empty() {
+            self.visit_script(lit);
             self.buf_w.push(Writable::Lit(lit));
         }
 
@@ -395,12 +490,59 @@ impl<'a> LoweringContext<'a> {
         }
     }
 
+    /// Follow open and close `<script>` and `<style>` tags in html literals
+    fn visit_script(&mut self, lit: &str) {
+        if self.opt.is_text {
+            return;
+        }
+
+        let lit = lit.to_ascii_lowercase();
+        let opened = |tag: &str, state: &mut bool| match (
+            lit.rfind(&format!("<{}", tag)),
+            lit.rfind(&format!("</{}", tag)),
+        ) {
+            (Some(open), Some(close)) => *state = close < open,
+            (Some(_), None) => *state = true,
+            (None, Some(_)) => *state = false,
+            (None, None) => (),
+        };
+        opened("script", &mut self.in_script);
+        opened("style", &mut self.in_style);
+    }
+
+    /// Literal of `@include_str`, `@include_raw` and `@asset` from a file at templates directory
+    fn at_file(&self, kind: &AtHelperKind, arg: &syn::Expr) -> Result<String, GError> {
+        let path = match arg {
+            syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(path),
+                ..
+            }) => path.value(),
+            _ => return Err(GError::AtHelperPath),
+        };
+        match kind {
+            AtHelperKind::Asset => self.c.get_asset(&path),
+            _ => self
+                .c
+                .get_file(&path)
+                .and_then(|file| fs::read_to_string(&file).map_err(|e| e.to_string())),
+        }
+        .map_err(GError::AtHelperFile)
+        .map(|src| match kind {
+            // Text at raw text elements or text templates is already safe
+            AtHelperKind::IncludeStr if !(self.opt.is_text || self.in_script || self.in_style) => {
+                escape(&src).to_string()
+            }
+            _ => src,
+        })
+    }
+
     fn visit_helper(&mut self, buf: &mut Vec<HIR>, h: &'a Helper<'a>) {
         use yarte_parser::Helper::*;
         match h {
-            Each(ws, e, b) => self.visit_each(buf, *ws, e, b),
+            Each(ws, e, k, b) => self.visit_each(buf, *ws, e, k, b),
             If(ifs, elsif, els) => self.visit_if(buf, ifs, elsif, els),
             With(ws, e, b) => self.visit_with(buf, *ws, e, b),
+            Cache(ws, k, t, b) => self.visit_cache(buf, *ws, k, t, b),
             Unless(ws, e, b) => self.visit_unless(buf, *ws, e, b),
             Defined(..) => unimplemented!(),
         }
@@ -473,11 +615,57 @@ impl<'a> LoweringContext<'a> {
         self.handle_ws(ws.1);
     }
 
+    fn visit_cache(
+        &mut self,
+        buf: &mut Vec<HIR>,
+        ws: (Ws, Ws),
+        skey: &'a SExpr,
+        sttl: &'a Option<SExpr>,
+        nodes: &'a [SNode<'a>],
+    ) {
+        if !self.opt.cache {
+            self.errors.push(ErrorMessage {
+                message: GError::Cache,
+                span: skey.span(),
+            });
+            return;
+        }
+        let mut key = (***skey.t()).clone();
+        self.visit_expr_mut(&mut key);
+        self.write_errors(skey.span());
+        let ttl = sttl.as_ref().map(|sttl| {
+            let mut ttl = (***sttl.t()).clone();
+            self.visit_expr_mut(&mut ttl);
+            self.write_errors(sttl.span());
+            ttl
+        });
+
+        self.handle_ws(ws.0);
+        self.write_buf_writable(buf);
+
+        let mut body = Vec::new();
+        self.handle(nodes, &mut body);
+        self.handle_ws(ws.1);
+        self.write_buf_writable(&mut body);
+
+        let path = skey.span().file_path();
+        let path = path.strip_prefix(self.c.get_dir()).unwrap_or(&path);
+        let start = skey.span().start();
+        buf.push(HIR::Cache(Box::new(Cache {
+            path: path.to_string_lossy().into_owned(),
+            start: (start.line, start.column),
+            key,
+            ttl,
+            body,
+        })))
+    }
+
     fn visit_each(
         &mut self,
         buf: &mut Vec<HIR>,
         ws: (Ws, Ws),
         sargs: &'a SExpr,
+        skey: &'a Option<SExpr>,
         nodes: &'a [SNode<'a>],
     ) {
         self.spans.push(sargs.span());
@@ -525,6 +713,13 @@ impl<'a> LoweringContext<'a> {
         };
         self.on.push(On::Each(id));
 
+        let key = skey.as_ref().map(|skey| {
+            let mut key = (***skey.t()).clone();
+            self.visit_expr_mut(&mut key);
+            self.write_errors(skey.span());
+            key
+        });
+
         let mut body = Vec::new();
         self.handle(nodes, &mut body);
         self.handle_ws(ws.1);
@@ -534,7 +729,12 @@ impl<'a> LoweringContext<'a> {
         self.scp.pop();
         self.spans.pop();
 
-        buf.push(HIR::Each(Box::new(Each { args, body, expr })))
+        buf.push(HIR::Each(Box::new(Each {
+            args,
+            body,
+            expr,
+            key,
+        })))
     }
 
     fn visit_if(
@@ -658,6 +858,49 @@ impl<'a> LoweringContext<'a> {
         }
     }
 
+    /// Nested application, named arguments are the initial state of the application
+    fn visit_component(
+        &mut self,
+        buf: &mut Vec<HIR>,
+        ws: Ws,
+        path: &str,
+        exprs: &'a SVExpr,
+    ) -> GResult<()> {
+        if !self.opt.components {
+            return Err(GError::Component);
+        }
+        let path: syn::Path = syn::parse_str(path).map_err(|_| GError::ComponentPath)?;
+
+        let mut props = vec![];
+        let mut forward = None;
+        if !exprs.t().is_empty() {
+            let (args, scope) = visit_partial(exprs, &mut self.errors);
+            if scope.is_some() {
+                return Err(GError::ComponentArguments);
+            }
+            for (k, expr) in args {
+                // Function path of the parent module, isn't a template expression
+                if k == "forward" {
+                    forward = Some(expr.clone());
+                    continue;
+                }
+                let mut expr = expr.clone();
+                self.visit_expr_mut(&mut expr);
+                self.write_errors(exprs.span());
+                props.push((format_ident!("{}", k), expr));
+            }
+        }
+
+        self.handle_ws(ws);
+        self.write_buf_writable(buf);
+        buf.push(HIR::Component(Box::new(Component {
+            path,
+            props,
+            forward,
+        })));
+        Ok(())
+    }
+
     fn visit_partial(
         &mut self,
         buf: &mut Vec<HIR>,
@@ -672,7 +915,10 @@ impl<'a> LoweringContext<'a> {
         }
 
         // TODO: identifiers
-        let p = self.c.resolve_partial(&self.on_path, path);
+        let p = self
+            .c
+            .resolve_partial(&self.on_path, path)
+            .expect("resolved partial");
         let nodes = self.ctx.get(&p).unwrap();
 
         // TODO: to on path stack without duplicates
@@ -969,7 +1215,13 @@ impl<'a> LoweringContext<'a> {
                 }
 
                 match self.on.last() {
-                    None => self_var!(ident),
+                    None => {
+                        // compile time variable, `each` and `with` contexts shadow it
+                        if let Some(var) = self.s.vars.get(ident) {
+                            return Ok(var.clone());
+                        }
+                        self_var!(ident)
+                    }
                     Some(On::Each(j)) => each_var!(ident, *j),
                     Some(On::With(j)) => with_var!(ident, *j),
                 };
@@ -979,6 +1231,9 @@ impl<'a> LoweringContext<'a> {
                 Err(GError::SuperWithoutParent)
             } else if self.on.len() == j {
                 partial_var!(ident, j);
+                if let Some(var) = self.s.vars.get(ident.as_str()) {
+                    return Ok(var.clone());
+                }
                 self_var!(ident);
             } else if j < self.on.len() {
                 partial_var!(ident, j);
@@ -8,7 +8,7 @@ use syn::{
 
 use yarte_parser::StmtLocal;
 
-use crate::{Each, IfElse, HIR};
+use crate::{Cache, Component, Each, IfElse, HIR};
 
 #[inline]
 pub fn serialize<'a, W, I>(ir: I, writer: &mut W) -> fmt::Result
@@ -83,8 +83,55 @@ where
                 }
                 writer.write_str("{{/if}}")?;
             }
+            HIR::Cache(a) => {
+                let Cache { key, ttl, body, .. } = &**a;
+                let mut key = key.clone();
+                visitor.visit_expr_mut(&mut key);
+                writer.write_str("{{#cache key = ")?;
+                writer.write_str(&quote!(#key).to_string())?;
+                if let Some(ttl) = ttl {
+                    let mut ttl = ttl.clone();
+                    visitor.visit_expr_mut(&mut ttl);
+                    writer.write_str(" ttl = ")?;
+                    writer.write_str(&quote!(#ttl).to_string())?;
+                }
+                writer.write_str(" }}")?;
+                serialize(body.iter(), writer)?;
+                writer.write_str("{{/cache}}")?;
+            }
+            HIR::Component(a) => {
+                let Component {
+                    path,
+                    props,
+                    forward,
+                } = &**a;
+                writer.write_str("{{> @")?;
+                writer.write_str(&quote!(#path).to_string())?;
+                let mut args = props
+                    .iter()
+                    .map(|(prop, expr)| {
+                        let mut expr = expr.clone();
+                        visitor.visit_expr_mut(&mut expr);
+                        format!("{} = {}", prop, quote!(#expr))
+                    })
+                    .chain(forward.iter().map(|x| format!("forward = {}", quote!(#x))));
+                if let Some(arg) = args.next() {
+                    writer.write_str(" ")?;
+                    writer.write_str(&arg)?;
+                }
+                for arg in args {
+                    writer.write_str(", ")?;
+                    writer.write_str(&arg)?;
+                }
+                writer.write_str(" }}")?
+            }
             HIR::Each(a) => {
-                let Each { args, body, expr } = &**a;
+                let Each {
+                    args,
+                    body,
+                    expr,
+                    key,
+                } = &**a;
                 use syn::Expr::*;
                 let args = if let Paren(ExprParen { expr, .. }) = args {
                     &**expr
@@ -140,6 +187,12 @@ where
                 };
                 writer.write_str("{{#each ")?;
                 writer.write_str(&quote!(#any).to_string())?;
+                if let Some(key) = key {
+                    let mut key = key.clone();
+                    visitor.visit_expr_mut(&mut key);
+                    writer.write_str(" key = ")?;
+                    writer.write_str(&quote!(#key).to_string())?;
+                }
                 writer.write_str(" }}")?;
                 serialize(body.iter(), writer)?;
                 writer.write_str("{{/each}}")?;
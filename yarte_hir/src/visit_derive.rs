@@ -1,13 +1,14 @@
 use std::{
+    collections::BTreeMap,
     convert::{TryFrom, TryInto},
     path::PathBuf,
 };
 
 use proc_macro2::TokenStream;
 use quote::quote;
-use syn::{parse_str, visit::Visit, Data, Error, ItemEnum};
+use syn::{parse_quote, parse_str, visit::Visit, Data, Error, ItemEnum};
 
-use yarte_helpers::config::Config;
+use yarte_helpers::config::{Config, MinifyOption, Mode};
 
 // TODO:
 const RECURSION_LIMIT: usize = 128;
@@ -19,19 +20,54 @@ pub fn visit_derive<'a, 'b>(
     StructBuilder::new(config).build(i)
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Struct<'a> {
     pub src: String,
     pub path: PathBuf,
+    pub mode: Mode,
+    pub minify: MinifyOption,
     pub print: Print,
     pub recursion_limit: usize,
+    /// Validate html at compile time
+    pub strict: bool,
+    /// Learn the buffer capacity from previous renders
+    pub adaptive: bool,
+    /// Compile time variables, from `[vars]` and `vars(..)` attribute
+    pub vars: BTreeMap<String, syn::Expr>,
     pub msgs: Option<ItemEnum>,
     pub script: Option<String>,
+    /// Bootstrap of the wasm application
+    pub bootstrap: Bootstrap,
     pub fields: Vec<syn::Field>,
     pub ident: &'a syn::Ident,
     generics: &'a syn::Generics,
 }
 
+/// `TemplateWasmServer` output and `App` mount point
+#[derive(Debug, Default, Clone)]
+pub struct Bootstrap {
+    /// Field with the CSP nonce of the scripts
+    pub nonce: Option<syn::Expr>,
+    /// Subresource integrity of `script`
+    pub integrity: Option<String>,
+    /// Path of the wasm module
+    pub wasm: Option<String>,
+    /// Subresource integrity of `wasm`
+    pub wasm_integrity: Option<String>,
+    /// Element id of fragment templates
+    pub mount: Option<String>,
+}
+
+impl Bootstrap {
+    /// Element id of the `application/json` state script
+    pub fn state_id(&self) -> String {
+        match &self.mount {
+            Some(id) => format!("{}-state", id),
+            None => "yarte-state".into(),
+        }
+    }
+}
+
 impl<'a> Struct<'a> {
     pub fn implement_head(&self, t: TokenStream, body: &TokenStream) -> TokenStream {
         let Struct {
@@ -41,6 +77,46 @@ impl<'a> Struct<'a> {
 
         quote!(impl#impl_generics #t for #ident #orig_ty_generics #where_clause { #body })
     }
+
+    /// Application with server side rendering of its initial state,
+    /// documents with `script` attribute and fragments mounted in an element
+    pub fn is_server_rendered(&self) -> bool {
+        self.script.is_some() || self.bootstrap.mount.is_some()
+    }
+
+    /// Same template options implemented for other type
+    pub fn with_ident<'b>(&self, ident: &'b syn::Ident) -> Struct<'b>
+    where
+        'a: 'b,
+    {
+        let s: Struct<'b> = self.clone();
+        Struct { ident, ..s }
+    }
+
+    /// `From` implementation of `ty` for this type, the value is `template`
+    pub fn implement_from(&self, ty: TokenStream, body: &TokenStream) -> TokenStream {
+        let Struct {
+            ident, generics, ..
+        } = *self;
+        let (impl_generics, orig_ty_generics, where_clause) = generics.split_for_impl();
+
+        quote! {
+            impl#impl_generics ::core::convert::From<#ident #orig_ty_generics> for #ty #where_clause {
+                fn from(template: #ident #orig_ty_generics) -> Self {
+                    #body
+                }
+            }
+        }
+    }
+
+    pub fn implement_inherent(&self, body: &TokenStream) -> TokenStream {
+        let Struct {
+            ident, generics, ..
+        } = *self;
+        let (impl_generics, orig_ty_generics, where_clause) = generics.split_for_impl();
+
+        quote!(impl#impl_generics #ident #orig_ty_generics #where_clause { #body })
+    }
 }
 
 struct StructBuilder<'a> {
@@ -48,7 +124,11 @@ struct StructBuilder<'a> {
     path: Option<PathBuf>,
     print: Option<Print>,
     script: Option<String>,
+    bootstrap: Bootstrap,
     recursion_limit: Option<usize>,
+    strict: bool,
+    adaptive: bool,
+    vars: BTreeMap<String, syn::Expr>,
     src: Option<String>,
     err: Vec<Error>,
     ident: String,
@@ -64,7 +144,15 @@ impl<'a> StructBuilder<'a> {
             path: None,
             print: None,
             script: None,
+            bootstrap: Bootstrap::default(),
             recursion_limit: None,
+            strict: false,
+            adaptive: false,
+            vars: config
+                .vars
+                .iter()
+                .map(|(k, v)| ((*k).to_string(), parse_str(v).expect("valid var")))
+                .collect(),
             src: None,
             err: vec![],
         }
@@ -106,6 +194,20 @@ impl<'a> StructBuilder<'a> {
             }
         }
 
+        // Compile time variables don't shadow fields
+        for field in &self.fields {
+            if let Some(ident) = field
+                .ident
+                .as_ref()
+                .filter(|x| self.vars.contains_key(&x.to_string()))
+            {
+                self.err.push(Error::new_spanned(
+                    ident,
+                    format!("field `{}` collides with a compile time variable", ident),
+                ));
+            }
+        }
+
         let (path, src) = match (self.path, self.src) {
             (Some(path), Some(src)) => (path, src),
             _ => {
@@ -119,6 +221,8 @@ impl<'a> StructBuilder<'a> {
 
         if self.err.is_empty() {
             Ok(Struct {
+                mode: self.config.get_mode(&path),
+                minify: self.config.minify,
                 recursion_limit: self.recursion_limit.unwrap_or(RECURSION_LIMIT),
                 fields: self.fields,
                 generics,
@@ -127,7 +231,11 @@ impl<'a> StructBuilder<'a> {
                 path,
                 print: self.print.unwrap_or(Print::None),
                 script: self.script,
+                bootstrap: self.bootstrap,
                 src,
+                strict: self.strict,
+                adaptive: self.adaptive,
+                vars: self.vars,
             })
         } else {
             Err(self.err.iter().flat_map(Error::to_compile_error).collect())
@@ -140,6 +248,28 @@ impl<'a, 'b> Visit<'a> for StructBuilder<'b> {
         self.fields.push(e.clone());
     }
 
+    fn visit_meta_list(&mut self, i: &'a syn::MetaList) {
+        if !i.path.is_ident("vars") {
+            return syn::visit::visit_meta_list(self, i);
+        }
+        for nested in &i.nested {
+            match nested {
+                syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                    path,
+                    lit,
+                    ..
+                })) if path.get_ident().is_some() => {
+                    self.vars
+                        .insert(path.get_ident().unwrap().to_string(), parse_quote!(#lit));
+                }
+                _ => self.err.push(Error::new_spanned(
+                    nested,
+                    "attribute 'vars' must be a list of `name = literal`",
+                )),
+            }
+        }
+    }
+
     fn visit_meta_name_value(&mut self, i: &'a syn::MetaNameValue) {
         let syn::MetaNameValue { path, lit, .. } = i;
         if path.is_ident("path") {
@@ -151,19 +281,23 @@ impl<'a, 'b> Visit<'a> for StructBuilder<'b> {
                     ))
                 }
                 let mut path = PathBuf::from(s.value());
-                if let Some(ext) = path.extension() {
-                    if ext != DEFAULT_EXTENSION {
-                        self.err.push(Error::new_spanned(
-                            i,
-                            "Default extension for yarte templates is `.hbs`",
-                        ))
+                if path
+                    .extension()
+                    .map_or(true, |ext| ext != DEFAULT_EXTENSION)
+                {
+                    // Inner extension select the mode, `email.txt` is `email.txt.hbs`
+                    let mut file = path.into_os_string();
+                    file.push(".");
+                    file.push(DEFAULT_EXTENSION);
+                    path = file.into();
+                }
+                match self.config.get_template(&path) {
+                    Ok((path, src)) => {
+                        self.path = Some(path);
+                        self.src = Some(src);
                     }
-                } else {
-                    path = path.with_extension(DEFAULT_EXTENSION);
-                };
-                let (path, src) = self.config.get_template(&path);
-                self.path = Some(path);
-                self.src = Some(src);
+                    Err(e) => self.err.push(Error::new_spanned(i, e)),
+                }
             } else {
                 self.err.push(Error::new_spanned(
                     i,
@@ -214,6 +348,38 @@ impl<'a, 'b> Visit<'a> for StructBuilder<'b> {
                     "attribute 'script' must be string literal",
                 ));
             }
+        } else if path.is_ident("nonce") {
+            match lit {
+                syn::Lit::Str(s) => match parse_str::<syn::Ident>(&s.value()) {
+                    Ok(field) => self.bootstrap.nonce = Some(parse_quote!(self.#field)),
+                    Err(_) => self.err.push(Error::new_spanned(
+                        i,
+                        "attribute 'nonce' must be a field name",
+                    )),
+                },
+                _ => self.err.push(Error::new_spanned(
+                    i,
+                    "attribute 'nonce' must be string literal",
+                )),
+            }
+        } else if let Some(attr) = ["integrity", "wasm", "wasm_integrity", "mount"]
+            .iter()
+            .find(|x| path.is_ident(x))
+        {
+            if let syn::Lit::Str(ref s) = lit {
+                let value = Some(s.value());
+                match *attr {
+                    "integrity" => self.bootstrap.integrity = value,
+                    "wasm" => self.bootstrap.wasm = value,
+                    "wasm_integrity" => self.bootstrap.wasm_integrity = value,
+                    _ => self.bootstrap.mount = value,
+                }
+            } else {
+                self.err.push(Error::new_spanned(
+                    i,
+                    format!("attribute '{}' must be string literal", attr),
+                ));
+            }
         } else if path.is_ident("recursion") {
             if let syn::Lit::Int(s) = lit {
                 self.recursion_limit = Some(s.base10_parse().unwrap());
@@ -223,6 +389,24 @@ impl<'a, 'b> Visit<'a> for StructBuilder<'b> {
                     "attribute 'recursion-limit' must be number literal",
                 ));
             }
+        } else if path.is_ident("strict") {
+            if let syn::Lit::Bool(s) = lit {
+                self.strict = s.value;
+            } else {
+                self.err.push(Error::new_spanned(
+                    i,
+                    "attribute 'strict' must be bool literal",
+                ));
+            }
+        } else if path.is_ident("adaptive") {
+            if let syn::Lit::Bool(s) = lit {
+                self.adaptive = s.value;
+            } else {
+                self.err.push(Error::new_spanned(
+                    i,
+                    "attribute 'adaptive' must be bool literal",
+                ));
+            }
         } else {
             self.err.push(Error::new_spanned(
                 i,
@@ -232,7 +416,7 @@ impl<'a, 'b> Visit<'a> for StructBuilder<'b> {
     }
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
 pub enum Print {
     All,
     Ast,
@@ -286,5 +470,44 @@ mod test {
         assert_eq!(s.src, "");
         assert_eq!(s.path, config.get_dir().join(PathBuf::from("Test.hbs")));
         assert_eq!(s.print, Print::Code);
+        assert_eq!(s.mode, Mode::Html);
+    }
+
+    #[test]
+    fn test_vars() {
+        let src = r#"
+            #[derive(Template)]
+            #[template(src = "", vars(env = "dev", level = 1))]
+            struct Test;
+        "#;
+        let i = parse_str::<syn::DeriveInput>(src).unwrap();
+        let config = Config::new("[vars]\nenv = \"prod\"\nlang = \"en\"");
+        let s = visit_derive(&i, &config).unwrap();
+
+        let env: syn::Expr = parse_quote!("dev");
+        let level: syn::Expr = parse_quote!(1);
+        let lang: syn::Expr = parse_quote!("en");
+        assert_eq!(s.vars.len(), 3);
+        assert_eq!(s.vars["env"], env);
+        assert_eq!(s.vars["level"], level);
+        assert_eq!(s.vars["lang"], lang);
+    }
+
+    #[test]
+    fn test_vars_collision() {
+        let src = r#"
+            #[derive(Template)]
+            #[template(src = "", vars(level = 1))]
+            struct Test {
+                env: String,
+                level: u8,
+            }
+        "#;
+        let i = parse_str::<syn::DeriveInput>(src).unwrap();
+        let config = Config::new("[vars]\nenv = \"prod\"");
+        let err = visit_derive(&i, &config).unwrap_err().to_string();
+
+        assert!(err.contains("field `env` collides with a compile time variable"));
+        assert!(err.contains("field `level` collides with a compile time variable"));
     }
 }
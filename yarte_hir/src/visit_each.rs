@@ -66,7 +66,10 @@ impl<'a> FindEach<'a> {
                     break;
                 }
 
-                let p = self.c.resolve_partial(&self.on_path, $path.t());
+                let p = self
+                    .c
+                    .resolve_partial(&self.on_path, $path.t())
+                    .expect("resolved partial");
                 let nodes = self.ctx.get(&p).unwrap();
                 let expr = $expr.t();
                 if !expr.is_empty() {
@@ -122,13 +125,24 @@ impl<'a> FindEach<'a> {
                             self.find(b)?;
                             self.on_ -= 1;
                         }
+                        Helper::Cache(_, key, ttl, block) => {
+                            self.visit_expr(key.t());
+                            breaks!(self);
+
+                            if let Some(ttl) = ttl {
+                                self.visit_expr(ttl.t());
+                                breaks!(self);
+                            }
+
+                            self.find(block)?;
+                        }
                         Helper::Unless(_, expr, block) => {
                             self.visit_expr(expr.t());
                             breaks!(self);
 
                             self.find(block)?;
                         }
-                        Helper::Each(_, expr, block) => {
+                        Helper::Each(_, expr, _, block) => {
                             self.visit_expr(expr.t());
                             breaks!(self);
 
@@ -142,6 +156,12 @@ impl<'a> FindEach<'a> {
                         }
                     }
                 }
+                Node::Partial(Partial(_, path, expr)) if path.t().starts_with('@') => {
+                    for e in expr.t() {
+                        self.visit_expr(e);
+                        breaks!(self);
+                    }
+                }
                 Node::Partial(Partial(_, path, expr)) => {
                     let (parent, nodes) = partial!(path, expr);
 
@@ -150,6 +170,7 @@ impl<'a> FindEach<'a> {
                     self.on_path = parent;
                     self.recursion -= 1;
                 }
+                Node::PartialBlock(PartialBlock(_, path, ..)) if path.t().starts_with('@') => (),
                 Node::PartialBlock(PartialBlock(_, path, expr, block)) => {
                     let (parent, nodes) = partial!(path, expr);
 
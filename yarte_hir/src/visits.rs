@@ -5,9 +5,39 @@ use syn::{
     visit_mut::{self, VisitMut},
 };
 
+use yarte_helpers::config::Cfg;
+
 use super::LoweringContext;
 use crate::error::GError;
 
+/// Evaluate `cfg!` predicate
+fn is_cfg(cfg: &Cfg, meta: &syn::Meta) -> Option<bool> {
+    use syn::{Lit, Meta, MetaNameValue, NestedMeta};
+    let list = |nested: &Punctuated<NestedMeta, syn::Token![,]>| {
+        nested
+            .iter()
+            .map(|x| match x {
+                NestedMeta::Meta(meta) => is_cfg(cfg, meta),
+                NestedMeta::Lit(_) => None,
+            })
+            .collect::<Option<Vec<_>>>()
+    };
+    match meta {
+        Meta::Path(path) => Some(cfg.is_set(&path.get_ident()?.to_string(), None)),
+        Meta::NameValue(MetaNameValue {
+            path,
+            lit: Lit::Str(value),
+            ..
+        }) => Some(cfg.is_set(&path.get_ident()?.to_string(), Some(&value.value()))),
+        Meta::List(i) if i.path.is_ident("all") => Some(list(&i.nested)?.into_iter().all(|x| x)),
+        Meta::List(i) if i.path.is_ident("any") => Some(list(&i.nested)?.into_iter().any(|x| x)),
+        Meta::List(i) if i.path.is_ident("not") && i.nested.len() == 1 => {
+            Some(!list(&i.nested)?[0])
+        }
+        _ => None,
+    }
+}
+
 impl<'a> VisitMut for LoweringContext<'a> {
     fn visit_arm_mut(
         &mut self,
@@ -44,6 +74,15 @@ impl<'a> VisitMut for LoweringContext<'a> {
                     }
                 }
             }
+            Macro(i) if i.mac.path.is_ident("cfg") => {
+                // Without forwarded configuration is evaluated by rustc
+                if let Some(cfg) = &self.c.cfg {
+                    match i.mac.parse_body().map(|meta| is_cfg(cfg, &meta)) {
+                        Ok(Some(val)) => *expr = syn::parse_quote!(#val),
+                        _ => self.buf_err.push((GError::NotAvailable, i.span())),
+                    }
+                }
+            }
             a => visit_mut::visit_expr_mut(self, a),
         };
     }
@@ -253,6 +253,9 @@ pub trait TreeSink {
         flags: ElementFlags,
     ) -> Self::Handle;
 
+    /// Create a comment node.
+    fn create_comment(&mut self, text: StrTendril) -> Self::Handle;
+
     /// Append a node as the last child of the given node.  If this would
     /// produce adjacent sibling text nodes, it should concatenate the text
     /// instead.
@@ -274,6 +277,11 @@ pub trait TreeSink {
     /// Indicate that a node was popped off the stack of open elements.
     fn pop(&mut self, _node: &Self::Handle) {}
 
+    /// Indicate that a node was left open at the end of the input.
+    fn pop_unclosed(&mut self, node: &Self::Handle) {
+        self.pop(node)
+    }
+
     /// Get a handle to a template's template contents. The tree builder
     /// promises this will never be called with something else than
     /// a template element.
@@ -291,6 +299,10 @@ pub trait TreeSink {
     /// Called whenever the line number changes.
     fn set_current_line(&mut self, _line_number: u64) {}
 
+    /// Called before each token with the bytes of input consumed,
+    /// a following parse error is at this offset.
+    fn set_current_offset(&mut self, _offset: usize) {}
+
     /// Indicate that a `script` element is complete.
     fn complete_script(&mut self, _node: &Self::Handle) -> NextParserState {
         NextParserState::Continue
@@ -14,6 +14,8 @@ mod macros;
 pub mod driver;
 #[macro_use]
 pub mod interface;
+mod minify;
+pub mod sanitize;
 pub mod serializer;
 pub mod tokenizer;
 pub mod tree_builder;
@@ -0,0 +1,308 @@
+//! Conservative minifiers for inline `<style>` and `<script>` literals
+//!
+//! Only comments and whitespace are removed, strings, template literals and
+//! regular expressions are copied as they are. Comments with expressions are kept.
+use crate::utils::MARK;
+
+/// Characters where the surrounding whitespace is not needed in css
+const CSS_PUNCT: &[char] = &['{', '}', ';', ',', '>'];
+
+pub(crate) fn minify_css(src: &str) -> String {
+    let mut out = String::with_capacity(src.len());
+    let mut chars = src.char_indices().peekable();
+    let mut ws = false;
+    let mut semi = false;
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '/' if src[i + 1..].starts_with('*') => {
+                let end = src[i + 2..].find("*/").map_or(src.len(), |j| i + 4 + j);
+                let comment = &src[i..end];
+                if comment.contains(MARK) {
+                    flush_css(&mut out, &mut ws, &mut semi, '/');
+                    out.push_str(comment);
+                }
+                while matches!(chars.peek(), Some((j, _)) if *j < end) {
+                    chars.next();
+                }
+            }
+            c if c.is_whitespace() => ws = true,
+            '"' | '\'' => {
+                flush_css(&mut out, &mut ws, &mut semi, c);
+                out.push(c);
+                while let Some((_, n)) = chars.next() {
+                    out.push(n);
+                    if n == '\\' {
+                        if let Some((_, e)) = chars.next() {
+                            out.push(e);
+                        }
+                    } else if n == c {
+                        break;
+                    }
+                }
+            }
+            ';' => {
+                flush_css(&mut out, &mut ws, &mut semi, c);
+                semi = true;
+            }
+            c => {
+                flush_css(&mut out, &mut ws, &mut semi, c);
+                out.push(c);
+            }
+        }
+    }
+
+    out
+}
+
+fn flush_css(out: &mut String, ws: &mut bool, semi: &mut bool, next: char) {
+    // Last declaration doesn't need semicolon
+    if *semi && next != '}' {
+        out.push(';');
+    }
+    *semi = false;
+    if *ws
+        && !out.is_empty()
+        && !CSS_PUNCT.contains(&next)
+        && !out.ends_with(CSS_PUNCT)
+        && !out.ends_with(':')
+    {
+        out.push(' ');
+    }
+    *ws = false;
+}
+
+/// Characters where the surrounding whitespace is not needed in javascript
+///
+/// `+`, `-`, `/`, `.`, `<`, `>` and `!` aren't since they can be joined to another token
+const JS_PUNCT: &[char] = &[
+    '{', '}', '(', ')', '[', ']', ';', ',', '=', ':', '?', '*', '%', '^', '~', '&', '|',
+];
+
+/// Keywords preceding an expression, a following slash is a regular expression
+const JS_KEYWORDS: &[&str] = &[
+    "await",
+    "case",
+    "delete",
+    "do",
+    "else",
+    "in",
+    "instanceof",
+    "new",
+    "return",
+    "throw",
+    "typeof",
+    "void",
+    "yield",
+];
+
+pub(crate) fn minify_js(src: &str) -> String {
+    let mut out = String::with_capacity(src.len());
+    let mut chars = src.char_indices().peekable();
+    // Whitespace run: None, space or new line
+    let mut ws: Option<bool> = None;
+    // Brace depth of each open template literal substitution
+    let mut templates: Vec<usize> = vec![];
+    let mut depth = 0usize;
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '/' if src[i + 1..].starts_with('/') || src[i + 1..].starts_with('*') => {
+                let end = if src[i + 1..].starts_with('/') {
+                    src[i..].find('\n').map_or(src.len(), |j| i + j)
+                } else {
+                    src[i + 2..].find("*/").map_or(src.len(), |j| i + 4 + j)
+                };
+                let comment = &src[i..end];
+                if comment.contains(MARK) {
+                    flush_js(&mut out, &mut ws, '/');
+                    out.push_str(comment);
+                    if comment.starts_with("//") {
+                        ws = Some(true);
+                    }
+                } else if comment.contains('\n') {
+                    // Keep automatic semicolon insertion
+                    ws = Some(true);
+                } else if ws.is_none() {
+                    ws = Some(false);
+                }
+                while matches!(chars.peek(), Some((j, _)) if *j < end) {
+                    chars.next();
+                }
+            }
+            c if c.is_whitespace() => {
+                ws = Some(ws.unwrap_or(false) || c == '\n' || c == '\r');
+            }
+            '"' | '\'' => {
+                flush_js(&mut out, &mut ws, c);
+                out.push(c);
+                copy_quoted(&mut chars, &mut out, c);
+            }
+            '`' => {
+                flush_js(&mut out, &mut ws, c);
+                out.push(c);
+                if copy_template(&mut chars, &mut out) {
+                    templates.push(depth);
+                }
+            }
+            '/' if is_regex_start(&out) => {
+                flush_js(&mut out, &mut ws, c);
+                out.push(c);
+                copy_regex(&mut chars, &mut out);
+            }
+            '{' => {
+                flush_js(&mut out, &mut ws, c);
+                out.push(c);
+                depth += 1;
+            }
+            '}' if templates.last() == Some(&depth) => {
+                // End of substitution, continue with template literal
+                flush_js(&mut out, &mut ws, c);
+                out.push(c);
+                if !copy_template(&mut chars, &mut out) {
+                    templates.pop();
+                }
+            }
+            '}' => {
+                flush_js(&mut out, &mut ws, c);
+                out.push(c);
+                depth = depth.saturating_sub(1);
+            }
+            c => {
+                flush_js(&mut out, &mut ws, c);
+                out.push(c);
+            }
+        }
+    }
+
+    out
+}
+
+fn flush_js(out: &mut String, ws: &mut Option<bool>, next: char) {
+    if let Some(new_line) = ws.take() {
+        if out.is_empty() {
+            return;
+        }
+        if new_line {
+            if !out.ends_with(&['{', '(', '[', ';', ','][..]) && !['}', ')', ']'].contains(&next) {
+                out.push('\n');
+            }
+        } else if !JS_PUNCT.contains(&next) && !out.ends_with(JS_PUNCT) {
+            out.push(' ');
+        }
+    }
+}
+
+type Chars<'a> = std::iter::Peekable<std::str::CharIndices<'a>>;
+
+fn copy_quoted(chars: &mut Chars, out: &mut String, quote: char) {
+    while let Some((_, c)) = chars.next() {
+        out.push(c);
+        if c == '\\' {
+            if let Some((_, e)) = chars.next() {
+                out.push(e);
+            }
+        } else if c == quote {
+            break;
+        }
+    }
+}
+
+/// Copy template literal until its end or a substitution, returns `true` in substitution
+fn copy_template(chars: &mut Chars, out: &mut String) -> bool {
+    while let Some((_, c)) = chars.next() {
+        out.push(c);
+        match c {
+            '\\' => {
+                if let Some((_, e)) = chars.next() {
+                    out.push(e);
+                }
+            }
+            '`' => return false,
+            '$' if matches!(chars.peek(), Some((_, '{'))) => {
+                chars.next();
+                out.push('{');
+                return true;
+            }
+            _ => (),
+        }
+    }
+
+    false
+}
+
+fn copy_regex(chars: &mut Chars, out: &mut String) {
+    let mut class = false;
+    while let Some((_, c)) = chars.next() {
+        out.push(c);
+        match c {
+            '\\' => {
+                if let Some((_, e)) = chars.next() {
+                    out.push(e);
+                }
+            }
+            '[' => class = true,
+            ']' => class = false,
+            '/' if !class => break,
+            '\n' => break,
+            _ => (),
+        }
+    }
+}
+
+fn is_regex_start(out: &str) -> bool {
+    match out.chars().last() {
+        None => true,
+        Some(c) if "(,=:[!&|?{};+-*%<>~^".contains(c) => true,
+        Some(c) if c.is_alphanumeric() || c == '_' || c == '$' => {
+            let word = out
+                .rsplit(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+                .next()
+                .unwrap_or("");
+            JS_KEYWORDS.contains(&word)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_css() {
+        assert_eq!(
+            minify_css("  a > b ,  c { color: red ; /* c */ margin: 0 auto; }\n"),
+            "a>b,c{color:red;margin:0 auto}"
+        );
+        assert_eq!(
+            minify_css("a:hover, a :focus { content: \"a  ;  b\" }"),
+            "a:hover,a :focus{content:\"a  ;  b\"}"
+        );
+        assert_eq!(
+            minify_css("@media (max-width: 10px) { p { width: calc(1px + 2px); } }"),
+            "@media (max-width:10px){p{width:calc(1px + 2px)}}"
+        );
+    }
+
+    #[test]
+    fn test_js() {
+        assert_eq!(
+            minify_js("  var a = 1 ;\n  // comment\n  var b = a + +1;\n"),
+            "var a=1;var b=a + +1;"
+        );
+        assert_eq!(
+            minify_js("let a = b\nlet c = 'x  //  y' /* z */\nreturn"),
+            "let a=b\nlet c='x  //  y'\nreturn"
+        );
+        assert_eq!(
+            minify_js("if (a) {\n  x = /  \\/\\/ [/]/g.test(s) / 2;\n}"),
+            "if(a){x=/  \\/\\/ [/]/g.test(s)/ 2;}"
+        );
+        assert_eq!(
+            minify_js("f(`a  ${ {a: `b  c`}.a }  d`)"),
+            "f(`a  ${{a:`b  c`}.a}  d`)"
+        );
+        assert_eq!(minify_js("return a < !--b"), "return a < !--b");
+    }
+}
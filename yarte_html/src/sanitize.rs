@@ -0,0 +1,424 @@
+//! Allowlist based sanitizer for untrusted markup
+//!
+//! Input is parsed as a fragment with the tree builder, every element or attribute
+//! not in the allowlist is dropped and the result is written with `HtmlSerializer`.
+use std::{
+    borrow::Cow,
+    io::{self, Write},
+};
+
+use markup5ever::{
+    namespace_url, ns,
+    tendril::{StrTendril, TendrilSink},
+};
+
+use crate::{
+    driver,
+    interface::{Attribute, ElementFlags, ExpandedName, NodeOrText, QualName, TreeSink, YName},
+    serializer::{HtmlSerializer, SerializerOpt},
+    tree_builder::get_marquee,
+};
+
+/// Tags and attributes allowed in sanitized output
+#[derive(Debug, Clone, Copy)]
+pub struct Allowlist<'a> {
+    pub tags: &'a [&'a str],
+    pub attrs: &'a [&'a str],
+}
+
+impl Allowlist<'static> {
+    /// Basic formatting, links, lists and tables
+    pub const DEFAULT: Allowlist<'static> = Allowlist {
+        tags: &[
+            "a",
+            "abbr",
+            "b",
+            "blockquote",
+            "br",
+            "code",
+            "del",
+            "em",
+            "h1",
+            "h2",
+            "h3",
+            "h4",
+            "h5",
+            "h6",
+            "hr",
+            "i",
+            "ins",
+            "li",
+            "ol",
+            "p",
+            "pre",
+            "s",
+            "small",
+            "span",
+            "strong",
+            "sub",
+            "sup",
+            "table",
+            "tbody",
+            "td",
+            "th",
+            "thead",
+            "tr",
+            "u",
+            "ul",
+        ],
+        attrs: &["href", "title", "alt", "colspan", "rowspan"],
+    };
+}
+
+impl Default for Allowlist<'static> {
+    fn default() -> Self {
+        Allowlist::DEFAULT
+    }
+}
+
+/// Parse `src` as a html fragment and write it to `writer` keeping only allowed nodes
+///
+/// Not allowed elements are unwrapped, except the ones with raw content
+/// (`script`, `style`, ...) that are removed with their children.
+pub fn sanitize<W: Write>(writer: W, src: &str, allow: &Allowlist) -> io::Result<()> {
+    let parser = driver::parse_fragment(Sink::default(), get_marquee(), vec![]);
+    let sink = parser.one(src);
+    let nodes = sink.filter(allow);
+
+    let mut ser = HtmlSerializer::new(writer, SerializerOpt::default());
+    serialize(&mut ser, nodes, None)
+}
+
+/// Sanitize `src` into a new `String`
+pub fn sanitize_to_string(src: &str, allow: &Allowlist) -> String {
+    let mut buf = Vec::with_capacity(src.len());
+    sanitize(&mut buf, src, allow).expect("write in Vec");
+    // Serializer only write whole utf-8 strings
+    String::from_utf8(buf).expect("valid utf-8")
+}
+
+type Handle = usize;
+
+enum Node {
+    Document(Vec<Handle>),
+    Element {
+        name: QualName,
+        attrs: Vec<Attribute>,
+        children: Vec<Handle>,
+    },
+    Text(StrTendril),
+    Comment,
+}
+
+#[derive(Default)]
+struct Sink {
+    nodes: Vec<Node>,
+    document: Option<Handle>,
+}
+
+enum Tree {
+    Element {
+        name: QualName,
+        attrs: Vec<Attribute>,
+        children: Vec<Tree>,
+    },
+    Text(String),
+}
+
+impl Sink {
+    fn push(&mut self, node: Node) -> Handle {
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+
+    fn take(&mut self, id: Handle) -> Node {
+        std::mem::replace(&mut self.nodes[id], Node::Document(vec![]))
+    }
+
+    fn filter(mut self, allow: &Allowlist) -> Vec<Tree> {
+        // Fragment content is the children of the root `html` element
+        let root = match self.document.map(|x| self.take(x)) {
+            Some(Node::Document(children)) => children.first().copied(),
+            _ => None,
+        };
+        let children = match root.map(|x| self.take(x)) {
+            Some(Node::Element { children, .. }) => children,
+            _ => vec![],
+        };
+
+        let mut tree = vec![];
+        self.filter_children(children, allow, &mut tree);
+        tree
+    }
+
+    fn filter_children(&mut self, children: Vec<Handle>, allow: &Allowlist, tree: &mut Vec<Tree>) {
+        for child in children {
+            match self.take(child) {
+                Node::Text(text) => push_text(tree, &text),
+                Node::Element {
+                    name,
+                    attrs,
+                    children,
+                } => {
+                    if is_allowed(&name, allow) {
+                        let attrs = attrs
+                            .into_iter()
+                            .filter(|x| is_allowed_attr(x, allow))
+                            .collect();
+                        let mut sub = vec![];
+                        self.filter_children(children, allow, &mut sub);
+                        tree.push(Tree::Element {
+                            name,
+                            attrs,
+                            children: sub,
+                        });
+                    } else if !is_raw(&name) {
+                        self.filter_children(children, allow, tree);
+                    }
+                }
+                Node::Document(_) | Node::Comment => (),
+            }
+        }
+    }
+}
+
+/// Adjacent text nodes are merged since serializer expects only one
+fn push_text(tree: &mut Vec<Tree>, text: &str) {
+    if let Some(Tree::Text(last)) = tree.last_mut() {
+        last.push_str(text);
+    } else {
+        tree.push(Tree::Text(text.into()))
+    }
+}
+
+fn is_allowed(name: &QualName, allow: &Allowlist) -> bool {
+    name.ns == ns!(html)
+        && matches!(name.local, YName::Local(_))
+        && allow
+            .tags
+            .iter()
+            .any(|x| x.eq_ignore_ascii_case(&name.local))
+}
+
+/// Elements removed with their content
+fn is_raw(name: &QualName) -> bool {
+    name.ns != ns!(html)
+        || matches!(
+            &*name.local,
+            "script"
+                | "style"
+                | "template"
+                | "iframe"
+                | "object"
+                | "embed"
+                | "noscript"
+                | "noembed"
+                | "noframes"
+                | "xmp"
+                | "plaintext"
+                | "textarea"
+                | "title"
+        )
+}
+
+fn is_allowed_attr(attr: &Attribute, allow: &Allowlist) -> bool {
+    let name = &*attr.name.local;
+    attr.name.ns == ns!()
+        && matches!(attr.name.local, YName::Local(_))
+        && !name.starts_with("on")
+        && allow.attrs.iter().any(|x| x.eq_ignore_ascii_case(name))
+        && (!is_url_attr(name) || is_safe_url(&attr.value))
+}
+
+fn is_url_attr(name: &str) -> bool {
+    matches!(
+        name,
+        "href" | "src" | "action" | "cite" | "formaction" | "poster" | "background"
+    )
+}
+
+/// Relative urls or absolute with `http`, `https` or `mailto` scheme
+fn is_safe_url(url: &str) -> bool {
+    // Browsers ignore whitespace and control characters inside the scheme
+    let url: String = url
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && !c.is_ascii_control())
+        .collect();
+    match url.find(&[':', '/', '?', '#'][..]) {
+        Some(i) if url[i..].starts_with(':') => {
+            let scheme = &url[..i];
+            ["http", "https", "mailto"]
+                .iter()
+                .any(|x| x.eq_ignore_ascii_case(scheme))
+        }
+        _ => true,
+    }
+}
+
+fn serialize<W: Write>(
+    ser: &mut HtmlSerializer<W>,
+    nodes: Vec<Tree>,
+    parent: Option<&QualName>,
+) -> io::Result<()> {
+    for node in nodes {
+        match node {
+            Tree::Element {
+                name,
+                attrs,
+                children,
+            } => {
+                ser.start_elem(
+                    name.clone(),
+                    attrs.iter().map(|x| (&x.name, x.value.as_ref())),
+                )?;
+                serialize(ser, children, Some(&name))?;
+                ser.end_elem(name)?;
+            }
+            Tree::Text(text) => ser.write_text(&text)?,
+        }
+    }
+    ser.end(parent)
+}
+
+impl TreeSink for Sink {
+    type Handle = Handle;
+    type Output = Self;
+
+    fn finish(self) -> Self::Output {
+        self
+    }
+
+    // Untrusted input is expected to be malformed
+    fn parse_error(&mut self, _: Cow<'static, str>) {}
+
+    fn get_document(&mut self) -> Self::Handle {
+        if let Some(document) = self.document {
+            document
+        } else {
+            let document = self.push(Node::Document(vec![]));
+            self.document = Some(document);
+            document
+        }
+    }
+
+    fn elem_name<'a>(&'a self, target: &'a Self::Handle) -> ExpandedName<'a> {
+        match &self.nodes[*target] {
+            Node::Element { name, .. } => name.expanded(),
+            _ => panic!("Expected element"),
+        }
+    }
+
+    fn create_element(
+        &mut self,
+        name: QualName,
+        attrs: Vec<Attribute>,
+        _: ElementFlags,
+    ) -> Self::Handle {
+        self.push(Node::Element {
+            name,
+            attrs,
+            children: vec![],
+        })
+    }
+
+    fn create_comment(&mut self, _: StrTendril) -> Self::Handle {
+        self.push(Node::Comment)
+    }
+
+    fn append(&mut self, parent: &Self::Handle, child: NodeOrText<Self::Handle>) {
+        let child = match child {
+            NodeOrText::AppendNode(node) => node,
+            NodeOrText::AppendText(text) => {
+                if let Some(Node::Text(last)) = self.last_child(*parent).map(|x| &mut self.nodes[x])
+                {
+                    last.push_tendril(&text);
+                    return;
+                }
+                self.push(Node::Text(text))
+            }
+        };
+        match &mut self.nodes[*parent] {
+            Node::Document(children) | Node::Element { children, .. } => children.push(child),
+            Node::Text(_) | Node::Comment => (),
+        }
+    }
+
+    fn append_doctype_to_document(&mut self, _: StrTendril, _: StrTendril, _: StrTendril) {}
+
+    fn get_template_contents(&mut self, target: &Self::Handle) -> Self::Handle {
+        *target
+    }
+
+    fn same_node(&self, x: &Self::Handle, y: &Self::Handle) -> bool {
+        x == y
+    }
+}
+
+impl Sink {
+    fn last_child(&self, parent: Handle) -> Option<Handle> {
+        match &self.nodes[parent] {
+            Node::Document(children) | Node::Element { children, .. } => children.last().copied(),
+            Node::Text(_) | Node::Comment => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sanitize(src: &str) -> String {
+        sanitize_to_string(src, &Allowlist::DEFAULT)
+    }
+
+    #[test]
+    fn test_allowed() {
+        assert_eq!(sanitize("<p>Hi <b>all</b>!</p>"), "<p>Hi <b>all</b>!</p>");
+        assert_eq!(
+            sanitize("<a href=\"https://a.b\" title=\"t\">a</a>"),
+            "<a href=\"https://a.b\" title=\"t\">a</a>"
+        );
+    }
+
+    #[test]
+    fn test_unwrap() {
+        assert_eq!(sanitize("<div><b>a</b> <font>b</font></div>"), "<b>a</b> b");
+        assert_eq!(sanitize("<p>a<script>alert(1)</script>b</p>"), "<p>ab</p>");
+        assert_eq!(sanitize("<style>p{}</style><svg><g></g></svg>a"), "a");
+    }
+
+    #[test]
+    fn test_attributes() {
+        assert_eq!(
+            sanitize("<b onclick=\"alert(1)\" class=\"a\">a</b>"),
+            "<b>a</b>"
+        );
+        assert_eq!(
+            sanitize("<a href=\"java\tscript:alert(1)\">a</a>"),
+            "<a>a</a>"
+        );
+        assert_eq!(
+            sanitize("<a href=\"/foo?a=b:c\">a</a>"),
+            "<a href=\"/foo?a=b:c\">a</a>"
+        );
+    }
+
+    #[test]
+    fn test_text() {
+        assert_eq!(sanitize("a < b &amp; c"), "a &lt; b &amp; c");
+        assert_eq!(sanitize("<!-- a --><b>b"), "<b>b</b>");
+        assert_eq!(sanitize(""), "");
+    }
+
+    #[test]
+    fn test_custom() {
+        let allow = Allowlist {
+            tags: &["div"],
+            attrs: &["class"],
+        };
+        assert_eq!(
+            sanitize_to_string("<div class=\"a\"><b>b</b></div>", &allow),
+            "<div class=\"a\">b</div>"
+        );
+    }
+}
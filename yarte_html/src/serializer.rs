@@ -1,6 +1,9 @@
 #![allow(clippy::unnested_or_patterns)]
 
-use std::io::{self, Write};
+use std::{
+    borrow::Cow,
+    io::{self, Write},
+};
 
 use log::warn;
 
@@ -8,7 +11,11 @@ use markup5ever::{local_name, namespace_url, ns};
 
 use yarte_parser::trim;
 
-use crate::interface::{QualName, YName};
+use crate::{
+    interface::{QualName, YName},
+    minify::{minify_css, minify_js},
+    utils::MARK,
+};
 
 pub type AttrRef<'a> = (&'a QualName, &'a str);
 
@@ -16,6 +23,23 @@ pub type AttrRef<'a> = (&'a QualName, &'a str);
 pub struct ElemInfo {
     html_name: Option<YName>,
     ignore_children: bool,
+    preserve_ws: bool,
+    raw: Option<Raw>,
+}
+
+/// Minified raw text content
+#[derive(Clone, Copy)]
+enum Raw {
+    Css,
+    Js,
+}
+
+/// What follows an omittable closing tag
+enum Next<'a> {
+    Start(&'a YName),
+    /// End of parent or of the output
+    End(Option<&'a YName>),
+    Other,
 }
 
 enum Ws {
@@ -23,9 +47,42 @@ enum Ws {
     C,
 }
 
-#[derive(Copy, Clone, Default)]
+#[derive(Copy, Clone)]
 pub struct SerializerOpt {
+    /// Skip `on*` attributes, they are handled by the wasm client
     pub wasm: bool,
+    /// Remove html comments, comments with expressions are always kept
+    pub remove_comments: bool,
+    /// Keep conditional comments `<!--[if IE]>...<![endif]-->` when comments are removed
+    pub keep_conditional_comments: bool,
+    /// Omit optional closing tags like `</li>`, `</p>` or `</td>`
+    pub omit_closing_tags: bool,
+    /// Write attribute values without quotes when it is possible
+    pub remove_attr_quotes: bool,
+    /// Write boolean attributes only with its name
+    pub collapse_boolean_attrs: bool,
+    /// Remove attributes with its default value like `<input type="text">`
+    pub remove_default_attrs: bool,
+    /// Minify `<style>` content
+    pub minify_css: bool,
+    /// Minify `<script>` content
+    pub minify_js: bool,
+}
+
+impl Default for SerializerOpt {
+    fn default() -> Self {
+        SerializerOpt {
+            wasm: false,
+            remove_comments: true,
+            keep_conditional_comments: true,
+            omit_closing_tags: false,
+            remove_attr_quotes: false,
+            collapse_boolean_attrs: false,
+            remove_default_attrs: false,
+            minify_css: false,
+            minify_js: false,
+        }
+    }
 }
 
 pub struct HtmlSerializer<Wr: Write> {
@@ -33,6 +90,7 @@ pub struct HtmlSerializer<Wr: Write> {
     stack: Vec<ElemInfo>,
     skip_ws: Option<Ws>,
     next_ws: Option<String>,
+    pending_end: Option<QualName>,
     opts: SerializerOpt,
 }
 
@@ -48,17 +106,14 @@ fn tagname(name: &QualName) -> YName {
     name.local.clone()
 }
 
-// TODO: optional tag
 impl<Wr: Write> HtmlSerializer<Wr> {
     pub fn new(writer: Wr, opts: SerializerOpt) -> Self {
         HtmlSerializer {
             writer,
-            stack: vec![ElemInfo {
-                html_name: None,
-                ignore_children: false,
-            }],
+            stack: vec![ElemInfo::default()],
             next_ws: None,
             skip_ws: None,
+            pending_end: None,
             opts,
         }
     }
@@ -97,25 +152,31 @@ impl<Wr: Write> HtmlSerializer<Wr> {
             self.stack.push(ElemInfo {
                 html_name,
                 ignore_children: true,
+                ..Default::default()
             });
             return Ok(());
         }
 
+        self.flush_end(Next::Start(&name.local))?;
         self.tag_whitespace(&name)?;
 
+        let attrs: Vec<_> = attrs.collect();
         self.writer.write_all(b"<")?;
         self.writer.write_all(tagname(&name).as_bytes())?;
-        for (name, value) in attrs {
-            if self.opts.wasm && name.local.to_string().starts_with("on") {
+        for &(attr, value) in &attrs {
+            if self.opts.wasm && attr.local.to_string().starts_with("on") {
+                continue;
+            }
+            if self.opts.remove_default_attrs && is_default_attr(&name, attr, value) {
                 continue;
             }
             self.writer.write_all(b" ")?;
 
-            match name.ns {
+            match attr.ns {
                 ns!() => (),
                 ns!(xml) => self.writer.write_all(b"xml:")?,
                 ns!(xmlns) => {
-                    if name.local != y_name!("xmlns") {
+                    if attr.local != y_name!("xmlns") {
                         self.writer.write_all(b"xmlns:")?;
                     }
                 }
@@ -127,8 +188,18 @@ impl<Wr: Write> HtmlSerializer<Wr> {
                 }
             }
 
-            self.writer.write_all(name.local.as_bytes())?;
-            if !value.is_empty() {
+            self.writer.write_all(attr.local.as_bytes())?;
+            if value.is_empty()
+                || self.opts.collapse_boolean_attrs
+                    && is_boolean_attr(&name, attr)
+                    && !value.contains(MARK)
+            {
+                continue;
+            }
+            if self.opts.remove_attr_quotes && can_unquote(value) {
+                self.writer.write_all(b"=")?;
+                self.write_escaped(value, true)?;
+            } else {
                 self.writer.write_all(b"=\"")?;
                 self.write_escaped(value, true)?;
                 self.writer.write_all(b"\"")?;
@@ -159,9 +230,23 @@ impl<Wr: Write> HtmlSerializer<Wr> {
                 _ => false,
             };
 
+        let preserve_ws = self.parent().preserve_ws
+            || matches!(
+                html_name,
+                Some(y_name!("pre")) | Some(y_name!("listing")) | Some(y_name!("textarea"))
+            );
+
+        let raw = match html_name {
+            Some(y_name!("style")) if self.opts.minify_css => Some(Raw::Css),
+            Some(y_name!("script")) if self.opts.minify_js && is_js(&attrs) => Some(Raw::Js),
+            _ => None,
+        };
+
         self.stack.push(ElemInfo {
             html_name,
             ignore_children,
+            preserve_ws,
+            raw,
         });
 
         Ok(())
@@ -176,15 +261,42 @@ impl<Wr: Write> HtmlSerializer<Wr> {
             return Ok(());
         }
 
+        self.flush_end(Next::End(Some(&name.local)))?;
         self.tag_whitespace(&name)?;
 
+        if self.opts.omit_closing_tags && name.ns == ns!(html) && is_omittable(&name.local) {
+            self.pending_end = Some(name);
+            return Ok(());
+        }
+
+        self.write_end(&name)
+    }
+
+    fn write_end(&mut self, name: &QualName) -> io::Result<()> {
         self.writer.write_all(b"</")?;
-        self.writer.write_all(tagname(&name).as_bytes())?;
+        self.writer.write_all(tagname(name).as_bytes())?;
         self.writer.write_all(b">")
     }
 
+    /// Write pending closing tag if it can't be omitted before `next`
+    fn flush_end(&mut self, next: Next) -> io::Result<()> {
+        if let Some(name) = self.pending_end.take() {
+            if !can_omit(&name.local, next) {
+                self.write_end(&name)?;
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn write_text(&mut self, text: &str) -> io::Result<()> {
-        assert!(self.next_ws.is_none(), "{:?} at \n{:?}", self.next_ws, text);
+        // Adjacent text nodes, i.e. separated by a removed comment
+        let text = match self.next_ws.take() {
+            Some(ws) if !text.starts_with(|c: char| c.is_ascii_whitespace()) => {
+                Cow::Owned(ws + text)
+            }
+            _ => Cow::Borrowed(text),
+        };
         let escape = match self.parent().html_name {
             Some(y_name!("style"))
             | Some(y_name!("script"))
@@ -197,33 +309,39 @@ impl<Wr: Write> HtmlSerializer<Wr> {
             _ => true,
         };
 
-        let v = match self.parent().html_name {
-            Some(y_name!("pre")) | Some(y_name!("listing")) => {
-                self.skip_ws = None;
-                self.next_ws = None;
-                text
-            }
-            _ => {
-                let (l, v, r) = trim(text);
+        let minified = match self.parent().raw {
+            Some(Raw::Css) => Some(minify_css(&text)),
+            Some(Raw::Js) => Some(minify_js(&text)),
+            None => None,
+        };
 
-                if !l.is_empty() && v.is_empty() && r.is_empty() {
-                    self.next_ws = Some(l.into());
-                    v
+        let v = if self.parent().preserve_ws {
+            self.flush_end(Next::Other)?;
+            self.skip_ws = None;
+            self.next_ws = None;
+            &text
+        } else {
+            let (l, v, r) = trim(&text);
+
+            if !l.is_empty() && v.is_empty() && r.is_empty() {
+                self.next_ws = Some(l.into());
+                v
+            } else {
+                self.flush_end(Next::Other)?;
+                match self.skip_ws.take() {
+                    Some(Ws::C) if !l.is_empty() => self.writer.write_all(b" ")?,
+                    None => self.writer.write_all(l.as_bytes())?,
+                    _ => (),
+                }
+                if !r.is_empty() {
+                    self.next_ws = Some(r.into());
                 } else {
-                    match self.skip_ws.take() {
-                        Some(Ws::C) if !l.is_empty() => self.writer.write_all(b" ")?,
-                        None => self.writer.write_all(l.as_bytes())?,
-                        _ => (),
-                    }
-                    if !r.is_empty() {
-                        self.next_ws = Some(r.into());
-                    } else {
-                        self.next_ws = None;
-                    }
-                    v
+                    self.next_ws = None;
                 }
+                v
             }
         };
+        let v = minified.as_deref().unwrap_or(v);
 
         if escape {
             self.write_escaped(v, false)
@@ -232,8 +350,24 @@ impl<Wr: Write> HtmlSerializer<Wr> {
         }
     }
 
+    pub fn write_comment(&mut self, text: &str) -> io::Result<()> {
+        let keep = !self.opts.remove_comments
+            || text.contains(MARK)
+            || self.opts.keep_conditional_comments && is_conditional_comment(text);
+        if !keep || self.parent().ignore_children {
+            return Ok(());
+        }
+
+        self.flush_end(Next::Other)?;
+        self.inline_whitespace()?;
+        self.writer.write_all(b"<!--")?;
+        self.writer.write_all(text.as_bytes())?;
+        self.writer.write_all(b"-->")
+    }
+
     pub fn write_doctype(&mut self, name: &str) -> io::Result<()> {
         assert!(self.next_ws.is_none(), "text before doctype");
+        self.flush_end(Next::Other)?;
         self.writer.write_all(b"<!DOCTYPE ")?;
         self.writer.write_all(name.as_bytes())?;
         self.writer.write_all(b">")
@@ -241,12 +375,16 @@ impl<Wr: Write> HtmlSerializer<Wr> {
 
     pub fn end(&mut self, parent: Option<&QualName>) -> io::Result<()> {
         if let Some(name) = parent {
+            self.flush_end(Next::End(Some(&name.local)))?;
             self.tag_whitespace(name)?;
-        } else if let Some(text) = &self.next_ws.take() {
-            match self.skip_ws {
-                Some(Ws::C) => self.writer.write_all(b" ")?,
-                None => self.writer.write_all(text.as_bytes())?,
-                Some(Ws::Skip) => (),
+        } else {
+            self.flush_end(Next::End(None))?;
+            if let Some(text) = &self.next_ws.take() {
+                match self.skip_ws {
+                    Some(Ws::C) => self.writer.write_all(b" ")?,
+                    None => self.writer.write_all(text.as_bytes())?,
+                    Some(Ws::Skip) => (),
+                }
             }
         }
         Ok(())
@@ -285,25 +423,25 @@ impl<Wr: Write> HtmlSerializer<Wr> {
             | y_name!("time")
             | y_name!("u")
             | y_name!("var")
-            | y_name!("wbr") => {
-                if let Some(text) = self.next_ws.take() {
-                    match self.skip_ws {
-                        Some(Ws::C) | None if !text.is_empty() => self.writer.write_all(b" ")?,
-                        _ => (),
-                    }
-                }
-                self.skip_ws = Some(Ws::C);
-            }
-            YName::Expr(_) => {
-                if let Some(text) = self.next_ws.take() {
-                    match self.skip_ws {
-                        None if !text.is_empty() => self.writer.write_all(text.as_bytes())?,
-                        Some(Ws::C) => self.writer.write_all(b" ")?,
-                        _ => (),
-                    }
-                }
-                self.skip_ws = None;
-            }
+            | y_name!("wbr")
+            // inline-block
+            | y_name!("audio")
+            | y_name!("button")
+            | y_name!("canvas")
+            | y_name!("embed")
+            | y_name!("iframe")
+            | y_name!("img")
+            | y_name!("label")
+            | y_name!("meter")
+            | y_name!("object")
+            | y_name!("output")
+            | y_name!("picture")
+            | y_name!("progress")
+            | y_name!("select")
+            | y_name!("svg")
+            | y_name!("textarea")
+            | y_name!("video") => self.inline_whitespace()?,
+            YName::Expr(_) => self.expr_whitespace()?,
             _ => {
                 self.next_ws = None;
                 self.skip_ws = Some(Ws::Skip);
@@ -312,4 +450,229 @@ impl<Wr: Write> HtmlSerializer<Wr> {
 
         Ok(())
     }
+
+    /// Collapse whitespace around inline elements and comments
+    fn inline_whitespace(&mut self) -> io::Result<()> {
+        if let Some(text) = self.next_ws.take() {
+            match self.skip_ws {
+                Some(Ws::C) | None if !text.is_empty() => self.writer.write_all(b" ")?,
+                _ => (),
+            }
+        }
+        self.skip_ws = Some(Ws::C);
+
+        Ok(())
+    }
+
+    /// Keep whitespace around expressions
+    fn expr_whitespace(&mut self) -> io::Result<()> {
+        if let Some(text) = self.next_ws.take() {
+            match self.skip_ws {
+                None if !text.is_empty() => self.writer.write_all(text.as_bytes())?,
+                Some(Ws::C) => self.writer.write_all(b" ")?,
+                _ => (),
+            }
+        }
+        self.skip_ws = None;
+
+        Ok(())
+    }
+}
+
+fn is_conditional_comment(text: &str) -> bool {
+    let text = text.trim_start();
+    text.starts_with("[if") || text.starts_with("<![endif]")
+}
+
+/// Elements with optional closing tag
+fn is_omittable(name: &YName) -> bool {
+    matches!(
+        name,
+        y_name!("li")
+            | y_name!("dt")
+            | y_name!("dd")
+            | y_name!("p")
+            | y_name!("rt")
+            | y_name!("rp")
+            | y_name!("optgroup")
+            | y_name!("option")
+            | y_name!("thead")
+            | y_name!("tbody")
+            | y_name!("tfoot")
+            | y_name!("tr")
+            | y_name!("td")
+            | y_name!("th")
+            | y_name!("head")
+            | y_name!("body")
+            | y_name!("html")
+    )
+}
+
+/// [Optional tags](https://html.spec.whatwg.org/multipage/syntax.html#optional-tags)
+///
+/// Only the next sibling start tag or the end of the parent are checked,
+/// so a closing tag followed by text or a comment is never omitted.
+fn can_omit(name: &YName, next: Next) -> bool {
+    match (name, next) {
+        (y_name!("li"), Next::Start(y_name!("li"))) => true,
+        (y_name!("dt"), Next::Start(y_name!("dt")))
+        | (y_name!("dt"), Next::Start(y_name!("dd")))
+        | (y_name!("dd"), Next::Start(y_name!("dt")))
+        | (y_name!("dd"), Next::Start(y_name!("dd"))) => true,
+        (y_name!("rt"), Next::Start(y_name!("rt")))
+        | (y_name!("rt"), Next::Start(y_name!("rp")))
+        | (y_name!("rp"), Next::Start(y_name!("rt")))
+        | (y_name!("rp"), Next::Start(y_name!("rp"))) => true,
+        (y_name!("optgroup"), Next::Start(y_name!("optgroup"))) => true,
+        (y_name!("option"), Next::Start(y_name!("option")))
+        | (y_name!("option"), Next::Start(y_name!("optgroup"))) => true,
+        (y_name!("thead"), Next::Start(y_name!("tbody")))
+        | (y_name!("thead"), Next::Start(y_name!("tfoot")))
+        | (y_name!("tbody"), Next::Start(y_name!("tbody")))
+        | (y_name!("tbody"), Next::Start(y_name!("tfoot"))) => true,
+        (y_name!("tr"), Next::Start(y_name!("tr"))) => true,
+        (y_name!("td"), Next::Start(y_name!("td")))
+        | (y_name!("td"), Next::Start(y_name!("th")))
+        | (y_name!("th"), Next::Start(y_name!("td")))
+        | (y_name!("th"), Next::Start(y_name!("th"))) => true,
+        (y_name!("head"), Next::Start(y_name!("body"))) => true,
+        (y_name!("p"), Next::Start(next)) => matches!(
+            next,
+            y_name!("address")
+                | y_name!("article")
+                | y_name!("aside")
+                | y_name!("blockquote")
+                | y_name!("details")
+                | y_name!("div")
+                | y_name!("dl")
+                | y_name!("fieldset")
+                | y_name!("figcaption")
+                | y_name!("figure")
+                | y_name!("footer")
+                | y_name!("form")
+                | y_name!("h1")
+                | y_name!("h2")
+                | y_name!("h3")
+                | y_name!("h4")
+                | y_name!("h5")
+                | y_name!("h6")
+                | y_name!("header")
+                | y_name!("hgroup")
+                | y_name!("hr")
+                | y_name!("main")
+                | y_name!("menu")
+                | y_name!("nav")
+                | y_name!("ol")
+                | y_name!("p")
+                | y_name!("pre")
+                | y_name!("section")
+                | y_name!("table")
+                | y_name!("ul")
+        ),
+        (y_name!("p"), Next::End(Some(parent))) => !matches!(
+            parent,
+            y_name!("a")
+                | y_name!("audio")
+                | y_name!("del")
+                | y_name!("ins")
+                | y_name!("map")
+                | y_name!("noscript")
+                | y_name!("video")
+                | YName::Expr(_)
+        ),
+        // Fragment can be inserted anywhere
+        (y_name!("html"), Next::End(_)) => true,
+        (y_name!("p"), _) | (_, Next::End(None)) => false,
+        (
+            y_name!("li")
+            | y_name!("dd")
+            | y_name!("rt")
+            | y_name!("rp")
+            | y_name!("optgroup")
+            | y_name!("option")
+            | y_name!("tbody")
+            | y_name!("tfoot")
+            | y_name!("tr")
+            | y_name!("td")
+            | y_name!("th")
+            | y_name!("body"),
+            Next::End(Some(_)),
+        ) => true,
+        _ => false,
+    }
+}
+
+fn can_unquote(value: &str) -> bool {
+    !value.is_empty()
+        && !value.contains(MARK)
+        && !value.contains(|c: char| {
+            c.is_ascii_whitespace() || matches!(c, '"' | '\'' | '=' | '<' | '>' | '`')
+        })
+}
+
+fn is_boolean_attr(elem: &QualName, attr: &QualName) -> bool {
+    elem.ns == ns!(html)
+        && attr.ns == ns!()
+        && matches!(
+            attr.local,
+            y_name!("allowfullscreen")
+                | y_name!("async")
+                | y_name!("autofocus")
+                | y_name!("autoplay")
+                | y_name!("checked")
+                | y_name!("controls")
+                | y_name!("default")
+                | y_name!("defer")
+                | y_name!("disabled")
+                | y_name!("formnovalidate")
+                | y_name!("hidden")
+                | y_name!("ismap")
+                | y_name!("loop")
+                | y_name!("multiple")
+                | y_name!("muted")
+                | y_name!("nomodule")
+                | y_name!("novalidate")
+                | y_name!("open")
+                | y_name!("readonly")
+                | y_name!("required")
+                | y_name!("reversed")
+                | y_name!("selected")
+        )
+}
+
+fn is_default_attr(elem: &QualName, attr: &QualName, value: &str) -> bool {
+    if elem.ns != ns!(html) || attr.ns != ns!() {
+        return false;
+    }
+    let value = value.trim();
+    let is = |x: &str| value.eq_ignore_ascii_case(x);
+    match (&elem.local, &attr.local) {
+        (y_name!("script"), y_name!("type")) => {
+            is("text/javascript") || is("application/javascript")
+        }
+        (y_name!("script"), y_name!("language")) => is("javascript"),
+        (y_name!("style"), y_name!("type")) | (y_name!("link"), y_name!("type")) => is("text/css"),
+        (y_name!("style"), y_name!("media")) => is("all"),
+        (y_name!("form"), y_name!("method")) => is("get"),
+        (y_name!("input"), y_name!("type")) => is("text"),
+        (y_name!("button"), y_name!("type")) => is("submit"),
+        _ => false,
+    }
+}
+
+/// Script without `type` or with a javascript one
+fn is_js(attrs: &[AttrRef]) -> bool {
+    match attrs
+        .iter()
+        .find(|(name, _)| name.ns == ns!() && name.local == y_name!("type"))
+    {
+        Some((_, value)) => {
+            let value = value.trim();
+            value.is_empty()
+                || ["text/javascript", "application/javascript", "module"]
+                    .iter()
+                    .any(|x| x.eq_ignore_ascii_case(value))
+        }
+        None => true,
+    }
 }
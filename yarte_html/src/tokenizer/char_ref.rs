@@ -236,7 +236,7 @@ impl CharRefTokenizer {
             None => (),
         }
 
-        input.push_front(unconsume);
+        tokenizer.unconsume(input, unconsume);
         tokenizer.emit_error(Borrowed("Numeric character reference without digits"));
         self.finish_none()
     }
@@ -302,8 +302,12 @@ impl CharRefTokenizer {
         tokenizer.emit_error(msg);
     }
 
-    fn unconsume_name(&mut self, input: &mut BufferQueue) {
-        input.push_front(self.name_buf_opt.take().unwrap());
+    fn unconsume_name<Sink: TokenSink>(
+        &mut self,
+        tokenizer: &mut Tokenizer<Sink>,
+        input: &mut BufferQueue,
+    ) {
+        tokenizer.unconsume(input, self.name_buf_opt.take().unwrap());
     }
 
     fn finish_named<Sink: TokenSink>(
@@ -327,7 +331,7 @@ impl CharRefTokenizer {
 
                     _ => (),
                 }
-                self.unconsume_name(input);
+                self.unconsume_name(tokenizer, input);
                 self.finish_none()
             }
 
@@ -380,10 +384,11 @@ impl CharRefTokenizer {
                 };
 
                 if unconsume_all {
-                    self.unconsume_name(input);
+                    self.unconsume_name(tokenizer, input);
                     self.finish_none()
                 } else {
-                    input.push_front(StrTendril::from_slice(&self.name_buf()[name_len..]));
+                    tokenizer
+                        .unconsume(input, StrTendril::from_slice(&self.name_buf()[name_len..]));
                     self.result = Some(CharRef {
                         chars: [from_u32(c1).unwrap(), from_u32(c2).unwrap()],
                         num_chars: if c2 == 0 { 1 } else { 2 },
@@ -406,7 +411,7 @@ impl CharRefTokenizer {
             ';' => self.emit_name_error(tokenizer),
             _ => (),
         }
-        self.unconsume_name(input);
+        self.unconsume_name(tokenizer, input);
         self.finish_none()
     }
 
@@ -429,12 +434,12 @@ impl CharRefTokenizer {
                 Named => drop(self.finish_named(tokenizer, input, None)),
 
                 BogusName => {
-                    self.unconsume_name(input);
+                    self.unconsume_name(tokenizer, input);
                     self.finish_none();
                 }
 
                 Octothorpe => {
-                    input.push_front(StrTendril::from_slice("#"));
+                    tokenizer.unconsume(input, StrTendril::from_slice("#"));
                     tokenizer.emit_error(Borrowed("EOF after '#' in character reference"));
                     self.finish_none();
                 }
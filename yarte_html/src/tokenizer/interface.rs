@@ -95,6 +95,9 @@ pub trait TokenSink {
     // Signal sink that tokenization reached the end.
     fn end(&mut self) {}
 
+    /// Called before each token with the bytes of input consumed
+    fn set_current_offset(&mut self, _offset: usize) {}
+
     /// Used in the markup declaration open state. By default, this always
     /// returns false and thus all CDATA sections are tokenized as bogus
     /// comments.
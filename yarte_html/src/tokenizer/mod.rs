@@ -123,6 +123,9 @@ pub struct Tokenizer<Sink> {
 
     /// Track current line
     current_line: u64,
+
+    /// Track consumed bytes of input
+    current_offset: usize,
 }
 
 impl<Sink: TokenSink> Tokenizer<Sink> {
@@ -147,6 +150,7 @@ impl<Sink: TokenSink> Tokenizer<Sink> {
             last_start_tag_name: None,
             temp_buf: StrTendril::new(),
             current_line: 1,
+            current_offset: 0,
         }
     }
 
@@ -159,6 +163,7 @@ impl<Sink: TokenSink> Tokenizer<Sink> {
         if let Some(c) = input.peek() {
             if c == '\u{feff}' {
                 input.next();
+                self.current_offset += c.len_utf8();
             }
         } else {
             return TokenizerResult::Done;
@@ -172,6 +177,7 @@ impl<Sink: TokenSink> Tokenizer<Sink> {
     }
 
     fn process_token(&mut self, token: Token) -> TokenSinkResult<Sink::Handle> {
+        self.sink.set_current_offset(self.current_offset);
         self.sink.process_token(token, self.current_line)
     }
 
@@ -186,10 +192,12 @@ impl<Sink: TokenSink> Tokenizer<Sink> {
     // Get the next input character, which might be the character
     // 'c' that we already consumed from the buffers.
     fn get_preprocessed_char(&mut self, mut c: char, input: &mut BufferQueue) -> Option<char> {
+        self.current_offset += c.len_utf8();
         if self.ignore_lf {
             self.ignore_lf = false;
             if c == '\n' {
                 c = unwrap_or_return!(input.next(), None);
+                self.current_offset += c.len_utf8();
             }
         }
 
@@ -221,6 +229,14 @@ impl<Sink: TokenSink> Tokenizer<Sink> {
     }
 
     fn pop_except_from(&mut self, input: &mut BufferQueue, set: SmallCharSet) -> Option<SetResult> {
+        // Bail to the slow path for various corner cases.
+        // This means that `FromSet` can contain characters not in the set!
+        // It shouldn't matter because the fallback `FromSet` case should
+        // always do the same thing as the `NotFromSet` case.
+        if self.reconsume || self.ignore_lf {
+            return self.get_char(input).map(FromSet);
+        }
+
         let d = input.pop_except_from(set);
         debug!("got characters {:?}", d);
         match d {
@@ -229,7 +245,11 @@ impl<Sink: TokenSink> Tokenizer<Sink> {
             // NB: We don't set self.current_char for a run of characters not
             // in the set.  It shouldn't matter for the codepaths that use
             // this.
-            _ => d,
+            Some(NotFromSet(ref buf)) => {
+                self.current_offset += buf.len();
+                d
+            }
+            None => d,
         }
     }
 
@@ -253,10 +273,21 @@ impl<Sink: TokenSink> Tokenizer<Sink> {
                 }
                 None
             }
-            Some(matched) => Some(matched),
+            Some(matched) => {
+                if matched {
+                    self.current_offset += pat.len();
+                }
+                Some(matched)
+            }
         }
     }
 
+    /// Push back consumed input
+    fn unconsume(&mut self, input: &mut BufferQueue, buf: StrTendril) {
+        self.current_offset -= buf.len();
+        input.push_front(buf);
+    }
+
     /// Run the state machine for as long as we can.
     fn run(&mut self, input: &mut BufferQueue) -> TokenizerResult<Sink::Handle> {
         loop {
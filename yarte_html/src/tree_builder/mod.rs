@@ -243,6 +243,10 @@ where
 {
     type Handle = Handle;
 
+    fn set_current_offset(&mut self, offset: usize) {
+        self.sink.set_current_offset(offset)
+    }
+
     fn process_token(
         &mut self,
         token: tokenizer::Token,
@@ -300,7 +304,7 @@ where
 
     fn end(&mut self) {
         for elem in self.open_elems.drain(..).rev() {
-            self.sink.pop(&elem);
+            self.sink.pop_unclosed(&elem);
         }
     }
 
@@ -681,9 +685,22 @@ where
         Done
     }
 
-    fn append_comment(&mut self, _text: StrTendril) -> ProcessResult<Handle> {
-        self.sink
-            .parse_error(Borrowed("No use html comment, use yarte comments instead"));
+    fn append_comment(&mut self, text: StrTendril) -> ProcessResult<Handle> {
+        let comment = self.sink.create_comment(text);
+        self.insert_appropriately(AppendNode(comment), None);
+        Done
+    }
+
+    fn append_comment_to_html(&mut self, text: StrTendril) -> ProcessResult<Handle> {
+        let target = self.open_elems.first().expect("no html element").clone();
+        let comment = self.sink.create_comment(text);
+        self.insert_appropriately(AppendNode(comment), Some(target));
+        Done
+    }
+
+    fn append_comment_to_doc(&mut self, text: StrTendril) -> ProcessResult<Handle> {
+        let comment = self.sink.create_comment(text);
+        self.sink.append(&self.doc_handle, AppendNode(comment));
         Done
     }
 
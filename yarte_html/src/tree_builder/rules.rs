@@ -32,6 +32,7 @@ where
             Initial => match_token!(token {
                 CharacterTokens(NotSplit, text) => SplitWhitespace(text),
                 CharacterTokens(Whitespace, _) => Done,
+                CommentToken(text) => self.append_comment_to_doc(text),
                 tag @ CharacterTokens(NotWhitespace, _) => self.unexpected(&tag),
                 token => Reprocess(BeforeHtml, token),
             }),
@@ -39,6 +40,7 @@ where
             BeforeHtml => match_token!(token {
                 CharacterTokens(NotSplit, text) => SplitWhitespace(text),
                 CharacterTokens(Whitespace, _) => Done,
+                CommentToken(text) => self.append_comment_to_doc(text),
                 tag @ <html> => {
                     self.create_root(tag.attrs);
                     self.mode = InHtml;
@@ -342,6 +344,7 @@ where
             AfterBody => match_token!(token {
                 CharacterTokens(NotSplit, text) => SplitWhitespace(text),
                 CharacterTokens(Whitespace, _) => Done,
+                CommentToken(text) => self.append_comment_to_html(text),
 
                 <html> => self.step(InHtml, token),
 
@@ -365,6 +368,7 @@ where
             AfterAfterBody => match_token!(token {
                 CharacterTokens(NotSplit, text) => SplitWhitespace(text),
                 CharacterTokens(Whitespace, _) => Done,
+                CommentToken(text) => self.append_comment_to_doc(text),
 
                 <html> => self.step(InHtml, token),
 
@@ -0,0 +1,169 @@
+//! Minimal html for `inner_html`, only well formed markup is accepted
+use std::rc::Rc;
+
+use crate::web::{Data, NodeData};
+
+const VOID: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+pub(crate) fn parse(src: &str) -> Vec<Rc<NodeData>> {
+    let root = NodeData::new(Data::Document);
+    let mut stack = vec![(String::new(), root.clone())];
+    let mut rest = src;
+
+    while !rest.is_empty() {
+        let parent = stack.last().expect("open element").1.clone();
+        if let Some(tail) = rest.strip_prefix("<!--") {
+            let end = tail.find("-->").expect("closed comment");
+            rest = &tail[end + 3..];
+        } else if let Some(tail) = rest.strip_prefix("</") {
+            let end = tail.find('>').expect("closed tag");
+            let name = tail[..end].trim().to_lowercase();
+            let (open, _) = stack.pop().expect("open element");
+            assert_eq!(open, name, "unbalanced close tag");
+            rest = &tail[end + 1..];
+        } else if let Some(tail) = rest.strip_prefix('<') {
+            let (name, attrs, self_closed, tail) = tag(tail);
+            let node = NodeData::new(Data::Element {
+                name: name.clone(),
+                attrs,
+            });
+            parent.insert(node.clone(), None);
+            if !self_closed && !VOID.contains(&name.as_str()) {
+                stack.push((name, node));
+            }
+            rest = tail;
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            parent.insert(NodeData::new(Data::Text(unescape(&rest[..end]))), None);
+            rest = &rest[end..];
+        }
+    }
+    assert_eq!(
+        stack.len(),
+        1,
+        "unclosed element {}",
+        stack.last().unwrap().0
+    );
+
+    root.children.take()
+}
+
+fn tag(src: &str) -> (String, Vec<(String, String)>, bool, &str) {
+    let end = src
+        .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .expect("closed tag");
+    let name = src[..end].to_lowercase();
+    let mut attrs = vec![];
+    let mut rest = src[end..].trim_start();
+    loop {
+        if let Some(tail) = rest.strip_prefix("/>") {
+            return (name, attrs, true, tail);
+        } else if let Some(tail) = rest.strip_prefix('>') {
+            return (name, attrs, false, tail);
+        }
+        let end = rest
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '>' || c == '/')
+            .expect("closed tag");
+        let attr = rest[..end].to_lowercase();
+        rest = rest[end..].trim_start();
+        let value = if let Some(tail) = rest.strip_prefix('=') {
+            let tail = tail.trim_start();
+            let quote = tail.chars().next().expect("attribute value");
+            let (value, tail) = if quote == '"' || quote == '\'' {
+                let end = tail[1..].find(quote).expect("closed attribute value") + 1;
+                (&tail[1..end], &tail[end + 1..])
+            } else {
+                let end = tail
+                    .find(|c: char| c.is_whitespace() || c == '>')
+                    .expect("closed tag");
+                tail.split_at(end)
+            };
+            rest = tail.trim_start();
+            unescape(value)
+        } else {
+            String::new()
+        };
+        attrs.push((attr, value));
+    }
+}
+
+fn unescape(src: &str) -> String {
+    src.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#x27;", "'")
+        .replace("&#39;", "'")
+        .replace("&#x2f;", "/")
+        .replace("&amp;", "&")
+}
+
+pub(crate) fn serialize(node: &NodeData, buf: &mut String) {
+    match &*node.data.borrow() {
+        Data::Text(s) => escape(s, false, buf),
+        Data::Element { name, attrs } => {
+            buf.push('<');
+            buf.push_str(name);
+            for (attr, value) in attrs {
+                buf.push(' ');
+                buf.push_str(attr);
+                buf.push_str("=\"");
+                escape(value, true, buf);
+                buf.push('"');
+            }
+            buf.push('>');
+            if !VOID.contains(&name.as_str()) {
+                for child in node.children.borrow().iter() {
+                    serialize(child, buf);
+                }
+                buf.push_str("</");
+                buf.push_str(name);
+                buf.push('>');
+            }
+        }
+        Data::Document => {
+            for child in node.children.borrow().iter() {
+                serialize(child, buf);
+            }
+        }
+    }
+}
+
+fn escape(src: &str, attr: bool, buf: &mut String) {
+    for c in src.chars() {
+        match c {
+            '&' => buf.push_str("&amp;"),
+            '<' if !attr => buf.push_str("&lt;"),
+            '>' if !attr => buf.push_str("&gt;"),
+            '"' if attr => buf.push_str("&quot;"),
+            c => buf.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn round_trip(src: &str) -> String {
+        let mut buf = String::new();
+        for node in parse(src) {
+            serialize(&node, &mut buf);
+        }
+        buf
+    }
+
+    #[test]
+    fn test() {
+        assert_eq!(
+            round_trip("<div id=a class='b c'><input disabled value=1><br/>a &amp; b</div>"),
+            "<div id=\"a\" class=\"b c\"><input disabled=\"\" value=\"1\"><br>a &amp; b</div>"
+        );
+        assert_eq!(
+            round_trip("<p><!-- c -->&lt;a&gt;</p> "),
+            "<p>&lt;a&gt;</p> "
+        );
+    }
+}
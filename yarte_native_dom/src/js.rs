@@ -0,0 +1,220 @@
+use std::{
+    fmt::{self, Debug, Formatter},
+    marker::PhantomData,
+    rc::Rc,
+};
+
+use crate::web::{EventData, NodeData};
+
+/// Handle to any value of the native DOM
+#[derive(Clone)]
+pub struct JsValue(pub(crate) Value);
+
+#[derive(Clone)]
+pub(crate) enum Value {
+    Undefined,
+    Node(Rc<NodeData>),
+    Event(Rc<EventData>),
+    Function(Rc<dyn Fn(JsValue)>),
+}
+
+impl JsValue {
+    pub const UNDEFINED: JsValue = JsValue(Value::Undefined);
+
+    pub fn is_undefined(&self) -> bool {
+        matches!(self.0, Value::Undefined)
+    }
+
+    pub(crate) fn node(&self) -> &Rc<NodeData> {
+        match &self.0 {
+            Value::Node(node) => node,
+            _ => panic!("JsValue is not a node"),
+        }
+    }
+
+    pub(crate) fn event(&self) -> &Rc<EventData> {
+        match &self.0 {
+            Value::Event(event) => event,
+            _ => panic!("JsValue is not an event"),
+        }
+    }
+}
+
+impl Debug for JsValue {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match &self.0 {
+            Value::Undefined => f.write_str("JsValue(undefined)"),
+            Value::Node(node) => write!(f, "JsValue({:?})", node),
+            Value::Event(event) => write!(f, "JsValue(Event {:?})", event.ty),
+            Value::Function(_) => f.write_str("JsValue(Function)"),
+        }
+    }
+}
+
+impl AsRef<JsValue> for JsValue {
+    fn as_ref(&self) -> &JsValue {
+        self
+    }
+}
+
+/// Unchecked conversions between DOM types
+pub trait JsCast: AsRef<JsValue> + Into<JsValue> {
+    fn unchecked_from_js(val: JsValue) -> Self;
+
+    fn unchecked_from_js_ref(val: &JsValue) -> &Self;
+
+    fn unchecked_into<T: JsCast>(self) -> T {
+        T::unchecked_from_js(self.into())
+    }
+
+    fn unchecked_ref<T: JsCast>(&self) -> &T {
+        T::unchecked_from_js_ref(self.as_ref())
+    }
+}
+
+impl JsCast for JsValue {
+    fn unchecked_from_js(val: JsValue) -> Self {
+        val
+    }
+
+    fn unchecked_from_js_ref(val: &JsValue) -> &Self {
+        val
+    }
+}
+
+/// Define a `JsValue` wrapper type
+macro_rules! js_type {
+    ($($(#[$meta:meta])* $name:ident $(: $target:ty)?),* $(,)?) => {
+        $(
+            $(#[$meta])*
+            #[repr(transparent)]
+            #[derive(Clone, Debug)]
+            pub struct $name {
+                obj: $crate::JsValue,
+            }
+
+            impl AsRef<$crate::JsValue> for $name {
+                fn as_ref(&self) -> &$crate::JsValue {
+                    &self.obj
+                }
+            }
+
+            impl From<$name> for $crate::JsValue {
+                fn from(x: $name) -> $crate::JsValue {
+                    x.obj
+                }
+            }
+
+            impl $crate::JsCast for $name {
+                fn unchecked_from_js(obj: $crate::JsValue) -> Self {
+                    $name { obj }
+                }
+
+                fn unchecked_from_js_ref(val: &$crate::JsValue) -> &Self {
+                    // Safety: `#[repr(transparent)]` over `JsValue`
+                    unsafe { &*(val as *const $crate::JsValue as *const $name) }
+                }
+            }
+
+            $(
+                impl std::ops::Deref for $name {
+                    type Target = $target;
+
+                    fn deref(&self) -> &$target {
+                        $crate::JsCast::unchecked_ref(self)
+                    }
+                }
+            )?
+        )*
+    };
+}
+
+js_type! {
+    /// Callable value
+    Function,
+}
+
+impl Function {
+    pub(crate) fn call(&self, arg: JsValue) {
+        match &self.obj.0 {
+            Value::Function(f) => f(arg),
+            _ => panic!("JsValue is not a function"),
+        }
+    }
+
+    pub(crate) fn same(&self, other: &Function) -> bool {
+        match (&self.obj.0, &other.obj.0) {
+            (Value::Function(a), Value::Function(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+/// Closure types which can be wrapped
+pub trait WasmClosure {
+    #[doc(hidden)]
+    fn into_js(f: Box<Self>) -> JsValue;
+}
+
+impl<A: JsCast + 'static> WasmClosure for dyn Fn(A) {
+    fn into_js(f: Box<Self>) -> JsValue {
+        JsValue(Value::Function(Rc::new(move |x| {
+            f(A::unchecked_from_js(x))
+        })))
+    }
+}
+
+/// Rust closure as a DOM callback
+pub struct Closure<T: ?Sized> {
+    js: JsValue,
+    _f: PhantomData<Box<T>>,
+}
+
+impl<T: ?Sized + WasmClosure> Closure<T> {
+    pub fn wrap(f: Box<T>) -> Closure<T> {
+        Closure {
+            js: T::into_js(f),
+            _f: PhantomData,
+        }
+    }
+
+    /// Callbacks are reference counted, nothing is leaked
+    pub fn forget(self) {}
+}
+
+impl<T: ?Sized> AsRef<JsValue> for Closure<T> {
+    fn as_ref(&self) -> &JsValue {
+        &self.js
+    }
+}
+
+/// `unwrap` with the `wasm_bindgen` name
+pub trait UnwrapThrowExt<T> {
+    fn unwrap_throw(self) -> T;
+
+    fn expect_throw(self, message: &str) -> T;
+}
+
+impl<T> UnwrapThrowExt<T> for Option<T> {
+    #[track_caller]
+    fn unwrap_throw(self) -> T {
+        self.expect("called `unwrap_throw` on a `None` value")
+    }
+
+    #[track_caller]
+    fn expect_throw(self, message: &str) -> T {
+        self.expect(message)
+    }
+}
+
+impl<T, E: Debug> UnwrapThrowExt<T> for Result<T, E> {
+    #[track_caller]
+    fn unwrap_throw(self) -> T {
+        self.expect("called `unwrap_throw` on an `Err` value")
+    }
+
+    #[track_caller]
+    fn expect_throw(self, message: &str) -> T {
+        self.expect(message)
+    }
+}
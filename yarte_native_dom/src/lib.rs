@@ -0,0 +1,156 @@
+//! In-process DOM with the interface of `yarte_wasm_app`
+//!
+//! Generated code of the `App` derive only calls the `yarte_wasm_app` paths, so renaming
+//! this crate to `yarte_wasm_app` runs applications on any target:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! yarte_wasm_app = { package = "yarte_native_dom", path = "../yarte_native_dom" }
+//! ```
+//!
+//! Every thread has its own document, write the server rendered html in the `body`
+//! with [`set_body`], mount the application with [`Addr::run`] and send messages or
+//! dispatch events on the document elements. Initial state is read from the
+//! `<script type="application/json">` element written by `TemplateWasmServer`.
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    fmt::{self, Debug, Formatter},
+};
+
+use serde::de::DeserializeOwned;
+
+#[macro_use]
+mod js;
+mod html;
+pub mod web;
+
+pub use yarte_helpers::helpers::{
+    big_num_32::{YNumber, U128, U256, U64},
+    IntoCopyIterator,
+};
+
+pub use self::js::{Closure, Function, JsCast, JsValue, UnwrapThrowExt, WasmClosure};
+
+/// Application with the interface implemented by the `App` derive
+pub trait App: Default + Sized + 'static {
+    type BlackBox;
+    type Message: 'static;
+
+    #[doc(hidden)]
+    fn __render(&mut self, _addr: &'static Addr<Self>);
+
+    #[doc(hidden)]
+    fn __hydrate(&mut self, _addr: &'static Addr<Self>);
+
+    #[doc(hidden)]
+    fn __dispatch(&mut self, _msg: Self::Message, _addr: &'static Addr<Self>);
+}
+
+type Forward<M> = Box<dyn Fn(&M)>;
+
+/// Message or state update sent by the parent application
+enum Queued<A: App> {
+    Msg(A::Message),
+    Update(Box<dyn FnOnce(&mut A)>),
+}
+
+/// Address of a mounted application
+///
+/// Messages are dispatched synchronously, messages sent during a dispatch are queued
+pub struct Addr<A: App> {
+    app: RefCell<A>,
+    queue: RefCell<VecDeque<Queued<A>>>,
+    forward: Option<Forward<A::Message>>,
+}
+
+impl<A: App> Addr<A> {
+    /// Build the application from the current document and hydrate it
+    pub fn run() -> &'static Addr<A> {
+        Self::mount(None)
+    }
+
+    /// Build a nested application, forwarded messages are sent to the parent
+    pub fn run_child<P: App>(
+        parent: &'static Addr<P>,
+        forward: fn(&A::Message) -> Option<P::Message>,
+    ) -> &'static Addr<A> {
+        Self::mount(Some(Box::new(move |msg| {
+            if let Some(msg) = forward(msg) {
+                parent.send(msg)
+            }
+        })))
+    }
+
+    fn mount(forward: Option<Forward<A::Message>>) -> &'static Addr<A> {
+        let addr: &'static Addr<A> = Box::leak(Box::new(Addr {
+            app: RefCell::new(A::default()),
+            queue: RefCell::new(VecDeque::new()),
+            forward,
+        }));
+        let mut app = addr.app.borrow_mut();
+        app.__hydrate(addr);
+        addr.flush(&mut app);
+        addr
+    }
+
+    /// Dispatch the message and render
+    pub fn send(&'static self, msg: A::Message) {
+        self.push(Queued::Msg(msg));
+    }
+
+    /// Update the state and render, parents set the fields of nested applications
+    pub fn update<F: FnOnce(&mut A) + 'static>(&'static self, f: F) {
+        self.push(Queued::Update(Box::new(f)));
+    }
+
+    fn push(&'static self, queued: Queued<A>) {
+        self.queue.borrow_mut().push_back(queued);
+        if let Ok(mut app) = self.app.try_borrow_mut() {
+            self.flush(&mut app);
+        }
+    }
+
+    fn flush(&'static self, app: &mut A) {
+        loop {
+            let queued = self.queue.borrow_mut().pop_front();
+            match queued {
+                Some(Queued::Msg(msg)) => {
+                    if let Some(forward) = &self.forward {
+                        forward(&msg);
+                    }
+                    app.__dispatch(msg, self);
+                }
+                Some(Queued::Update(f)) => f(app),
+                None => break,
+            }
+            app.__render(self);
+        }
+    }
+}
+
+impl<A: App> Debug for Addr<A> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("Addr")
+            .field("queue", &self.queue.borrow().len())
+            .finish()
+    }
+}
+
+/// Deserialize the initial state
+pub fn from_str<T: DeserializeOwned>(s: &str) -> serde_json::Result<T> {
+    serde_json::from_str(s)
+}
+
+/// Replace the `body` of the current document
+pub fn set_body(html: &str) {
+    body().set_inner_html(html)
+}
+
+/// `body` of the current document
+pub fn body() -> web::HtmlElement {
+    web::window()
+        .and_then(|x| x.document())
+        .and_then(|x| x.body())
+        .expect("document body")
+}
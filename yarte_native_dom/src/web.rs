@@ -0,0 +1,740 @@
+//! DOM types with the `web_sys` names and signatures used by generated code
+use std::{
+    cell::{Cell, RefCell},
+    fmt::{self, Debug, Formatter},
+    rc::{Rc, Weak},
+};
+
+use crate::{
+    html,
+    js::{Function, JsCast, JsValue, Value},
+};
+
+pub(crate) enum Data {
+    Document,
+    Element {
+        name: String,
+        attrs: Vec<(String, String)>,
+    },
+    Text(String),
+}
+
+struct Listener {
+    ty: String,
+    callback: Function,
+    capture: bool,
+    passive: bool,
+    once: bool,
+}
+
+pub(crate) struct NodeData {
+    pub(crate) data: RefCell<Data>,
+    parent: RefCell<Weak<NodeData>>,
+    pub(crate) children: RefCell<Vec<Rc<NodeData>>>,
+    listeners: RefCell<Vec<Listener>>,
+}
+
+impl Debug for NodeData {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match &*self.data.borrow() {
+            Data::Document => f.write_str("#document"),
+            Data::Element { name, .. } => write!(f, "<{}>", name),
+            Data::Text(s) => write!(f, "{:?}", s),
+        }
+    }
+}
+
+impl NodeData {
+    pub(crate) fn new(data: Data) -> Rc<NodeData> {
+        Rc::new(NodeData {
+            data: RefCell::new(data),
+            parent: RefCell::new(Weak::new()),
+            children: RefCell::new(vec![]),
+            listeners: RefCell::new(vec![]),
+        })
+    }
+
+    fn is_element(&self) -> bool {
+        matches!(&*self.data.borrow(), Data::Element { .. })
+    }
+
+    fn parent(&self) -> Option<Rc<NodeData>> {
+        self.parent.borrow().upgrade()
+    }
+
+    fn detach(self: &Rc<NodeData>) {
+        if let Some(parent) = self.parent() {
+            parent
+                .children
+                .borrow_mut()
+                .retain(|x| !Rc::ptr_eq(x, self));
+        }
+        *self.parent.borrow_mut() = Weak::new();
+    }
+
+    pub(crate) fn insert(self: &Rc<NodeData>, child: Rc<NodeData>, before: Option<&Rc<NodeData>>) {
+        let mut p = Some(self.clone());
+        while let Some(node) = p {
+            assert!(!Rc::ptr_eq(&node, &child), "insert a node into itself");
+            p = node.parent();
+        }
+        child.detach();
+        *child.parent.borrow_mut() = Rc::downgrade(self);
+        let mut children = self.children.borrow_mut();
+        let i = before.map_or(children.len(), |before| {
+            children
+                .iter()
+                .position(|x| Rc::ptr_eq(x, before))
+                .expect("reference node is a child")
+        });
+        children.insert(i, child);
+    }
+
+    fn clear(&self) {
+        for child in self.children.borrow_mut().drain(..) {
+            *child.parent.borrow_mut() = Weak::new();
+        }
+    }
+
+    fn deep_clone(&self, deep: bool) -> Rc<NodeData> {
+        let data = match &*self.data.borrow() {
+            Data::Document => Data::Document,
+            Data::Element { name, attrs } => Data::Element {
+                name: name.clone(),
+                attrs: attrs.clone(),
+            },
+            Data::Text(s) => Data::Text(s.clone()),
+        };
+        let node = NodeData::new(data);
+        if deep {
+            for child in self.children.borrow().iter() {
+                node.insert(child.deep_clone(true), None);
+            }
+        }
+        node
+    }
+
+    fn siblings(self: &Rc<NodeData>) -> Vec<Rc<NodeData>> {
+        self.parent()
+            .map_or_else(Vec::new, |p| p.children.borrow().clone())
+    }
+
+    fn text(&self, buf: &mut String) {
+        match &*self.data.borrow() {
+            Data::Text(s) => buf.push_str(s),
+            _ => {
+                for child in self.children.borrow().iter() {
+                    child.text(buf);
+                }
+            }
+        }
+    }
+
+    fn attr(&self, attr: &str) -> Option<String> {
+        match &*self.data.borrow() {
+            Data::Element { attrs, .. } => attrs
+                .iter()
+                .find(|(name, _)| name == attr)
+                .map(|(_, value)| value.clone()),
+            _ => None,
+        }
+    }
+
+    fn set_attr(&self, attr: &str, value: Option<&str>) {
+        if let Data::Element { attrs, .. } = &mut *self.data.borrow_mut() {
+            let i = attrs.iter().position(|(name, _)| name == attr);
+            match (i, value) {
+                (Some(i), Some(value)) => attrs[i].1 = value.to_string(),
+                (None, Some(value)) => attrs.push((attr.to_string(), value.to_string())),
+                (Some(i), None) => {
+                    attrs.remove(i);
+                }
+                (None, None) => (),
+            }
+        }
+    }
+
+    fn find(self: &Rc<NodeData>, f: &dyn Fn(&NodeData) -> bool) -> Option<Rc<NodeData>> {
+        if f(self) {
+            return Some(self.clone());
+        }
+        self.children.borrow().iter().find_map(|x| x.find(f))
+    }
+}
+
+fn node(node: Rc<NodeData>) -> JsValue {
+    JsValue(Value::Node(node))
+}
+
+fn cast<T: JsCast>(data: Rc<NodeData>) -> T {
+    T::unchecked_from_js(node(data))
+}
+
+js_type! {
+    /// Base of the DOM types
+    Object,
+    /// Node which can receive events
+    EventTarget: Object,
+    Node: EventTarget,
+    Element: Node,
+    HtmlElement: Element,
+    HtmlHeadElement: HtmlElement,
+    HtmlInputElement: HtmlElement,
+    Document: Node,
+    Window: EventTarget,
+    /// Child elements of a node, live as in browsers
+    HtmlCollection: Object,
+    Event: Object,
+    UiEvent: Event,
+    KeyboardEvent: UiEvent,
+}
+
+thread_local! {
+    static DOCUMENT: Document = {
+        let doc = NodeData::new(Data::Document);
+        let html = NodeData::new(Data::Element { name: "html".into(), attrs: vec![] });
+        for name in &["head", "body"] {
+            html.insert(NodeData::new(Data::Element { name: name.to_string(), attrs: vec![] }), None);
+        }
+        doc.insert(html, None);
+        cast(doc)
+    };
+    /// Focused element of the document
+    static FOCUS: RefCell<Weak<NodeData>> = RefCell::new(Weak::new());
+}
+
+/// Current thread window, every test thread has its own document
+pub fn window() -> Option<Window> {
+    Some(Window::unchecked_from_js(JsValue::UNDEFINED))
+}
+
+impl Window {
+    pub fn document(&self) -> Option<Document> {
+        Some(DOCUMENT.with(Clone::clone))
+    }
+}
+
+impl EventTarget {
+    pub fn add_event_listener_with_callback(
+        &self,
+        ty: &str,
+        listener: &Function,
+    ) -> Result<(), JsValue> {
+        self.add_event_listener_with_callback_and_add_event_listener_options(
+            ty,
+            listener,
+            &AddEventListenerOptions::new(),
+        )
+    }
+
+    pub fn add_event_listener_with_callback_and_add_event_listener_options(
+        &self,
+        ty: &str,
+        listener: &Function,
+        options: &AddEventListenerOptions,
+    ) -> Result<(), JsValue> {
+        let mut listeners = self.as_ref().node().listeners.borrow_mut();
+        if !listeners
+            .iter()
+            .any(|x| x.ty == ty && x.capture == options.capture && x.callback.same(listener))
+        {
+            listeners.push(Listener {
+                ty: ty.to_string(),
+                callback: listener.clone(),
+                capture: options.capture,
+                passive: options.passive,
+                once: options.once,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn remove_event_listener_with_callback(
+        &self,
+        ty: &str,
+        listener: &Function,
+    ) -> Result<(), JsValue> {
+        self.remove_event_listener_with_callback_and_bool(ty, listener, false)
+    }
+
+    pub fn remove_event_listener_with_callback_and_bool(
+        &self,
+        ty: &str,
+        listener: &Function,
+        capture: bool,
+    ) -> Result<(), JsValue> {
+        self.as_ref()
+            .node()
+            .listeners
+            .borrow_mut()
+            .retain(|x| !(x.ty == ty && x.capture == capture && x.callback.same(listener)));
+        Ok(())
+    }
+
+    /// Capture, target and bubble phases, returns `false` if default was prevented
+    pub fn dispatch_event(&self, event: &Event) -> Result<bool, JsValue> {
+        let target = self.as_ref().node().clone();
+        let data = event.as_ref().event();
+        *data.target.borrow_mut() = Some(target.clone());
+        data.stop.set(false);
+
+        let mut path = vec![];
+        let mut p = target.parent();
+        while let Some(node) = p {
+            p = node.parent();
+            path.push(node);
+        }
+
+        for node in path.iter().rev() {
+            if !invoke(node, event, Some(true)) {
+                return Ok(!data.prevented.get());
+            }
+        }
+        if !invoke(&target, event, None) {
+            return Ok(!data.prevented.get());
+        }
+        if data.bubbles {
+            for node in &path {
+                if !invoke(node, event, Some(false)) {
+                    break;
+                }
+            }
+        }
+
+        Ok(!data.prevented.get())
+    }
+}
+
+/// Call listeners of the phase, returns `false` if propagation is stopped
+fn invoke(node: &Rc<NodeData>, event: &Event, capture: Option<bool>) -> bool {
+    let data = event.as_ref().event();
+    let listeners: Vec<_> = node
+        .listeners
+        .borrow()
+        .iter()
+        .filter(|x| x.ty == data.ty && !matches!(capture, Some(c) if c != x.capture))
+        .map(|x| (x.callback.clone(), x.capture, x.passive, x.once))
+        .collect();
+    for (callback, capture, passive, once) in listeners {
+        if once {
+            node.listeners
+                .borrow_mut()
+                .retain(|x| !(x.capture == capture && x.callback.same(&callback)));
+        }
+        data.passive.set(passive);
+        callback.call(event.clone().into());
+        data.passive.set(false);
+    }
+
+    !data.stop.get()
+}
+
+impl Node {
+    pub fn is_same_node(&self, other: Option<&Node>) -> bool {
+        other.map_or(false, |x| Rc::ptr_eq(self.obj.node(), x.obj.node()))
+    }
+
+    pub fn parent_node(&self) -> Option<Node> {
+        self.obj.node().parent().map(cast)
+    }
+
+    pub fn parent_element(&self) -> Option<Element> {
+        self.obj
+            .node()
+            .parent()
+            .filter(|x| x.is_element())
+            .map(cast)
+    }
+
+    pub fn first_child(&self) -> Option<Node> {
+        self.obj.node().children.borrow().first().cloned().map(cast)
+    }
+
+    pub fn next_sibling(&self) -> Option<Node> {
+        let me = self.obj.node();
+        let siblings = me.siblings();
+        let i = siblings.iter().position(|x| Rc::ptr_eq(x, me))?;
+        siblings.get(i + 1).cloned().map(cast)
+    }
+
+    pub fn append_child(&self, child: &Node) -> Result<Node, JsValue> {
+        self.obj.node().insert(child.obj.node().clone(), None);
+        Ok(child.clone())
+    }
+
+    pub fn insert_before(&self, child: &Node, before: Option<&Node>) -> Result<Node, JsValue> {
+        self.obj
+            .node()
+            .insert(child.obj.node().clone(), before.map(|x| x.obj.node()));
+        Ok(child.clone())
+    }
+
+    pub fn remove_child(&self, child: &Node) -> Result<Node, JsValue> {
+        child.obj.node().detach();
+        Ok(child.clone())
+    }
+
+    pub fn clone_node_with_deep(&self, deep: bool) -> Result<Node, JsValue> {
+        Ok(cast(self.obj.node().deep_clone(deep)))
+    }
+
+    pub fn text_content(&self) -> Option<String> {
+        let mut buf = String::new();
+        self.obj.node().text(&mut buf);
+        Some(buf)
+    }
+
+    pub fn set_text_content(&self, text: Option<&str>) {
+        let node = self.obj.node();
+        if let Data::Text(s) = &mut *node.data.borrow_mut() {
+            *s = text.unwrap_or_default().to_string();
+            return;
+        }
+        node.clear();
+        match text {
+            Some(text) if !text.is_empty() => {
+                node.insert(NodeData::new(Data::Text(text.to_string())), None)
+            }
+            _ => (),
+        }
+    }
+}
+
+impl Element {
+    pub fn tag_name(&self) -> String {
+        match &*self.obj.node().data.borrow() {
+            Data::Element { name, .. } => name.to_uppercase(),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn first_element_child(&self) -> Option<Element> {
+        self.children().item(0)
+    }
+
+    pub fn next_element_sibling(&self) -> Option<Element> {
+        let me = self.obj.node();
+        let siblings = me.siblings();
+        let i = siblings.iter().position(|x| Rc::ptr_eq(x, me))?;
+        siblings[i + 1..]
+            .iter()
+            .find(|x| x.is_element())
+            .cloned()
+            .map(cast)
+    }
+
+    pub fn children(&self) -> HtmlCollection {
+        cast(self.obj.node().clone())
+    }
+
+    pub fn remove(&self) {
+        self.obj.node().detach()
+    }
+
+    pub fn get_attribute(&self, name: &str) -> Option<String> {
+        self.obj.node().attr(name)
+    }
+
+    pub fn set_attribute(&self, name: &str, value: &str) -> Result<(), JsValue> {
+        self.obj.node().set_attr(name, Some(value));
+        Ok(())
+    }
+
+    pub fn remove_attribute(&self, name: &str) -> Result<(), JsValue> {
+        self.obj.node().set_attr(name, None);
+        Ok(())
+    }
+
+    pub fn inner_html(&self) -> String {
+        let mut buf = String::new();
+        for child in self.obj.node().children.borrow().iter() {
+            html::serialize(child, &mut buf);
+        }
+        buf
+    }
+
+    pub fn outer_html(&self) -> String {
+        let mut buf = String::new();
+        html::serialize(self.obj.node(), &mut buf);
+        buf
+    }
+
+    /// Parse well formed html, panics at syntax errors
+    pub fn set_inner_html(&self, src: &str) {
+        let node = self.obj.node();
+        node.clear();
+        for child in html::parse(src) {
+            node.insert(child, None);
+        }
+    }
+}
+
+impl HtmlCollection {
+    pub fn length(&self) -> u32 {
+        self.obj
+            .node()
+            .children
+            .borrow()
+            .iter()
+            .filter(|x| x.is_element())
+            .count() as u32
+    }
+
+    pub fn item(&self, index: u32) -> Option<Element> {
+        self.obj
+            .node()
+            .children
+            .borrow()
+            .iter()
+            .filter(|x| x.is_element())
+            .nth(index as usize)
+            .cloned()
+            .map(cast)
+    }
+}
+
+impl HtmlElement {
+    pub fn focus(&self) -> Result<(), JsValue> {
+        FOCUS.with(|x| *x.borrow_mut() = Rc::downgrade(self.obj.node()));
+        Ok(())
+    }
+
+    pub fn blur(&self) -> Result<(), JsValue> {
+        FOCUS.with(|x| {
+            let mut focus = x.borrow_mut();
+            if focus.ptr_eq(&Rc::downgrade(self.obj.node())) {
+                *focus = Weak::new();
+            }
+        });
+        Ok(())
+    }
+}
+
+impl HtmlInputElement {
+    pub fn value(&self) -> String {
+        self.get_attribute("value").unwrap_or_default()
+    }
+
+    pub fn set_value(&self, value: &str) {
+        self.obj.node().set_attr("value", Some(value))
+    }
+
+    pub fn checked(&self) -> bool {
+        self.get_attribute("checked").is_some()
+    }
+
+    pub fn set_checked(&self, checked: bool) {
+        self.obj
+            .node()
+            .set_attr("checked", if checked { Some("") } else { None })
+    }
+}
+
+impl Document {
+    pub fn document_element(&self) -> Option<Element> {
+        self.obj.node().children.borrow().first().cloned().map(cast)
+    }
+
+    pub fn head(&self) -> Option<HtmlHeadElement> {
+        self.find_element(&|name| name == "head").map(cast)
+    }
+
+    pub fn body(&self) -> Option<HtmlElement> {
+        self.find_element(&|name| name == "body").map(cast)
+    }
+
+    pub fn get_element_by_id(&self, id: &str) -> Option<Element> {
+        self.obj
+            .node()
+            .find(&|x| x.attr("id").as_deref() == Some(id))
+            .map(cast)
+    }
+
+    /// Focused element or the body
+    pub fn active_element(&self) -> Option<Element> {
+        FOCUS
+            .with(|x| x.borrow().upgrade())
+            .or_else(|| self.find_element(&|name| name == "body"))
+            .map(cast)
+    }
+
+    pub fn create_element(&self, name: &str) -> Result<Element, JsValue> {
+        Ok(cast(NodeData::new(Data::Element {
+            name: name.to_lowercase(),
+            attrs: vec![],
+        })))
+    }
+
+    pub fn create_text_node(&self, text: &str) -> Node {
+        cast(NodeData::new(Data::Text(text.to_string())))
+    }
+
+    fn find_element(&self, f: &dyn Fn(&str) -> bool) -> Option<Rc<NodeData>> {
+        self.obj
+            .node()
+            .find(&|x| matches!(&*x.data.borrow(), Data::Element { name, .. } if f(name)))
+    }
+}
+
+pub(crate) struct EventData {
+    pub(crate) ty: String,
+    bubbles: bool,
+    cancelable: bool,
+    key: String,
+    key_code: u32,
+    target: RefCell<Option<Rc<NodeData>>>,
+    prevented: Cell<bool>,
+    stop: Cell<bool>,
+    passive: Cell<bool>,
+}
+
+/// `Event` constructor dictionary
+#[derive(Clone, Debug, Default)]
+pub struct EventInit {
+    bubbles: bool,
+    cancelable: bool,
+}
+
+impl EventInit {
+    pub fn new() -> EventInit {
+        EventInit::default()
+    }
+
+    pub fn bubbles(&mut self, val: bool) -> &mut Self {
+        self.bubbles = val;
+        self
+    }
+
+    pub fn cancelable(&mut self, val: bool) -> &mut Self {
+        self.cancelable = val;
+        self
+    }
+}
+
+/// `KeyboardEvent` constructor dictionary
+#[derive(Clone, Debug, Default)]
+pub struct KeyboardEventInit {
+    init: EventInit,
+    key: String,
+    key_code: u32,
+}
+
+impl KeyboardEventInit {
+    pub fn new() -> KeyboardEventInit {
+        KeyboardEventInit::default()
+    }
+
+    pub fn bubbles(&mut self, val: bool) -> &mut Self {
+        self.init.bubbles = val;
+        self
+    }
+
+    pub fn cancelable(&mut self, val: bool) -> &mut Self {
+        self.init.cancelable = val;
+        self
+    }
+
+    pub fn key(&mut self, val: &str) -> &mut Self {
+        self.key = val.to_string();
+        self
+    }
+
+    pub fn key_code(&mut self, val: u32) -> &mut Self {
+        self.key_code = val;
+        self
+    }
+}
+
+fn event<T: JsCast>(ty: &str, init: &EventInit, key: &str, key_code: u32) -> T {
+    T::unchecked_from_js(JsValue(Value::Event(Rc::new(EventData {
+        ty: ty.to_string(),
+        bubbles: init.bubbles,
+        cancelable: init.cancelable,
+        key: key.to_string(),
+        key_code,
+        target: RefCell::new(None),
+        prevented: Cell::new(false),
+        stop: Cell::new(false),
+        passive: Cell::new(false),
+    }))))
+}
+
+impl Event {
+    pub fn new(ty: &str) -> Result<Event, JsValue> {
+        Ok(event(ty, &EventInit::new(), "", 0))
+    }
+
+    pub fn new_with_event_init_dict(ty: &str, init: &EventInit) -> Result<Event, JsValue> {
+        Ok(event(ty, init, "", 0))
+    }
+
+    pub fn type_(&self) -> String {
+        self.obj.event().ty.clone()
+    }
+
+    pub fn target(&self) -> Option<EventTarget> {
+        self.obj.event().target.borrow().clone().map(cast)
+    }
+
+    pub fn default_prevented(&self) -> bool {
+        self.obj.event().prevented.get()
+    }
+
+    /// Ignored in passive listeners and not cancelable events
+    pub fn prevent_default(&self) {
+        let data = self.obj.event();
+        if data.cancelable && !data.passive.get() {
+            data.prevented.set(true);
+        }
+    }
+
+    pub fn stop_propagation(&self) {
+        self.obj.event().stop.set(true);
+    }
+}
+
+impl KeyboardEvent {
+    pub fn new_with_keyboard_event_init_dict(
+        ty: &str,
+        init: &KeyboardEventInit,
+    ) -> Result<KeyboardEvent, JsValue> {
+        Ok(event(ty, &init.init, &init.key, init.key_code))
+    }
+
+    pub fn key(&self) -> String {
+        self.obj.event().key.clone()
+    }
+
+    pub fn key_code(&self) -> u32 {
+        self.obj.event().key_code
+    }
+}
+
+/// `addEventListener` options
+#[derive(Clone, Debug, Default)]
+pub struct AddEventListenerOptions {
+    capture: bool,
+    passive: bool,
+    once: bool,
+}
+
+impl AddEventListenerOptions {
+    pub fn new() -> AddEventListenerOptions {
+        AddEventListenerOptions::default()
+    }
+
+    pub fn capture(&mut self, val: bool) -> &mut Self {
+        self.capture = val;
+        self
+    }
+
+    pub fn passive(&mut self, val: bool) -> &mut Self {
+        self.passive = val;
+        self
+    }
+
+    pub fn once(&mut self, val: bool) -> &mut Self {
+        self.once = val;
+        self
+    }
+}
@@ -0,0 +1,596 @@
+extern crate yarte_native_dom as yarte_wasm_app;
+
+use std::{cell::Cell, rc::Rc};
+
+use yarte_wasm_app::{
+    body, set_body,
+    web::{self, Event, EventInit, KeyboardEvent, KeyboardEventInit},
+    Addr, Closure, JsCast, UnwrapThrowExt,
+};
+
+/// Server rendered html with the state script of the template with `hash`
+fn render(html: &str, hash: &str, state: &str) {
+    set_body(&format!(
+        r#"{}<script type="application/json" id="yarte-state" data-yarte-hash="{}">{}</script>"#,
+        html, hash, state
+    ));
+}
+
+/// Body without scripts
+fn inner_html() -> String {
+    let html = body().inner_html();
+    html[..html.find("<script").unwrap_or(html.len())].to_string()
+}
+
+fn element(id: &str) -> web::Element {
+    web::window()
+        .unwrap_throw()
+        .document()
+        .unwrap_throw()
+        .get_element_by_id(id)
+        .unwrap_throw()
+}
+
+fn click(id: &str) -> bool {
+    let event =
+        Event::new_with_event_init_dict("click", EventInit::new().bubbles(true).cancelable(true))
+            .unwrap_throw();
+    element(id).dispatch_event(&event).unwrap_throw()
+}
+
+mod counter {
+    use yarte_derive::App;
+    use yarte_wasm_app::Addr;
+
+    #[derive(App)]
+    #[template(src = "<!doctype html><html><body>\
+<div><p>{{ count }}</p><button id=\"inc\" onclick=\"{{? Msg::Inc }}\">+</button></div>\
+</body></html>")]
+    #[msg(pub enum Msg {
+    Inc,
+})]
+    pub struct Counter {
+        pub count: usize,
+        black_box: <Self as App>::BlackBox,
+    }
+
+    fn inc(app: &mut Counter, _addr: &'static Addr<Counter>) {
+        app.count += 1;
+        app.black_box.t_root |= 1;
+    }
+}
+
+#[test]
+fn test_counter() {
+    render(
+        r#"<div><p>1</p><button id="inc">+</button></div>"#,
+        counter::Counter::__YARTE_HASH,
+        r#"{"count":1}"#,
+    );
+    let addr = Addr::<counter::Counter>::run();
+
+    // Default action is prevented without modifiers
+    assert!(!click("inc"));
+    assert_eq!(
+        inner_html(),
+        r#"<div><p>2</p><button id="inc">+</button></div>"#
+    );
+
+    addr.send(counter::Msg::Inc);
+    assert_eq!(
+        inner_html(),
+        r#"<div><p>3</p><button id="inc">+</button></div>"#
+    );
+}
+
+mod list {
+    use yarte_derive::App;
+    use yarte_wasm_app::Addr;
+
+    #[derive(App)]
+    #[template(src = "<!doctype html><html><body>\
+<div><ul>{{#each items}}<li>{{ this }}</li>{{/each}}</ul><button id=\"pop\" onclick=\"{{? Msg::Pop }}\">-</button></div>\
+</body></html>")]
+    #[msg(pub enum Msg {
+    Push(String),
+    Pop,
+})]
+    pub struct List {
+        items: Vec<String>,
+        black_box: <Self as App>::BlackBox,
+    }
+
+    fn push(app: &mut List, item: String, _addr: &'static Addr<List>) {
+        app.items.push(item);
+        app.black_box.t_root |= 1;
+    }
+
+    fn pop(app: &mut List, _addr: &'static Addr<List>) {
+        app.items.pop();
+        app.black_box.t_root |= 1;
+    }
+}
+
+#[test]
+fn test_each() {
+    render(
+        r#"<div><ul><li>a</li></ul><button id="pop">-</button></div>"#,
+        list::List::__YARTE_HASH,
+        r#"{"items":["a"]}"#,
+    );
+    let addr = Addr::<list::List>::run();
+
+    addr.send(list::Msg::Push("b".into()));
+    addr.send(list::Msg::Push("c".into()));
+    assert_eq!(
+        inner_html(),
+        r#"<div><ul><li>a</li><li>b</li><li>c</li></ul><button id="pop">-</button></div>"#
+    );
+
+    click("pop");
+    click("pop");
+    assert_eq!(
+        inner_html(),
+        r#"<div><ul><li>a</li></ul><button id="pop">-</button></div>"#
+    );
+}
+
+mod keyed {
+    use serde::Deserialize;
+    use yarte_derive::App;
+    use yarte_wasm_app::Addr;
+
+    #[derive(Clone, Default, Deserialize)]
+    pub struct Item {
+        pub id: u32,
+        pub name: String,
+    }
+
+    #[derive(App)]
+    #[template(src = "<!doctype html><html><body>\
+<ul>{{#each items key = id }}<li>{{ name }}</li>{{/each}}</ul>\
+</body></html>")]
+    #[msg(pub enum Msg {
+    Reverse,
+    Set(Vec<Item>),
+})]
+    pub struct Keyed {
+        items: Vec<Item>,
+        black_box: <Self as App>::BlackBox,
+    }
+
+    fn reverse(app: &mut Keyed, _addr: &'static Addr<Keyed>) {
+        app.items.reverse();
+        app.black_box.t_root |= 1;
+    }
+
+    fn set(app: &mut Keyed, items: Vec<Item>, _addr: &'static Addr<Keyed>) {
+        app.items = items;
+        app.black_box.t_root |= 1;
+    }
+}
+
+#[test]
+fn test_keyed() {
+    use keyed::{Item, Msg};
+
+    fn item(id: u32, name: &str) -> Item {
+        Item {
+            id,
+            name: name.into(),
+        }
+    }
+
+    render(
+        "<ul><li>a</li><li>b</li><li>c</li></ul>",
+        keyed::Keyed::__YARTE_HASH,
+        r#"{"items":[{"id":1,"name":"a"},{"id":2,"name":"b"},{"id":3,"name":"c"}]}"#,
+    );
+    let addr = Addr::<keyed::Keyed>::run();
+    let first = body()
+        .first_element_child()
+        .unwrap()
+        .first_element_child()
+        .unwrap();
+    first.set_attribute("class", "first").unwrap_throw();
+
+    // Rows move with its key
+    addr.send(Msg::Reverse);
+    assert_eq!(
+        inner_html(),
+        r#"<ul><li>c</li><li>b</li><li class="first">a</li></ul>"#
+    );
+
+    // Repeated keys don't share a row
+    addr.send(Msg::Set(vec![item(1, "a"), item(2, "b"), item(2, "d")]));
+    assert_eq!(
+        inner_html(),
+        r#"<ul><li class="first">a</li><li>b</li><li>d</li></ul>"#
+    );
+
+    addr.send(Msg::Set(vec![item(2, "b"), item(1, "a")]));
+    assert_eq!(
+        inner_html(),
+        r#"<ul><li>b</li><li class="first">a</li></ul>"#
+    );
+}
+
+mod input {
+    use std::cell::RefCell;
+
+    use yarte_derive::App;
+    use yarte_wasm_app::Addr;
+
+    #[derive(App)]
+    #[template(src = "<!doctype html><html><body>\
+<div id=\"root\"><input id=\"name\" oninput.stop.default=\"{{? Msg::Input(value) }}\" onkeydown.once=\"{{? Msg::Key(key_code) }}\"><p>{{ name }}</p></div>\
+</body></html>")]
+    #[msg(pub enum Msg {
+    Input(String),
+    Key(u32),
+})]
+    pub struct Input {
+        name: String,
+        black_box: <Self as App>::BlackBox,
+    }
+
+    fn input(app: &mut Input, value: String, _addr: &'static Addr<Input>) {
+        app.name = value;
+        app.black_box.t_root |= 1;
+    }
+
+    fn key(_app: &mut Input, code: u32, _addr: &'static Addr<Input>) {
+        KEYS.with(|keys| keys.borrow_mut().push(code));
+    }
+
+    thread_local!(pub static KEYS: RefCell<Vec<u32>> = const { RefCell::new(vec![]) });
+}
+
+#[test]
+fn test_event_values() {
+    render(
+        r#"<div id="root"><input id="name"><p></p></div>"#,
+        input::Input::__YARTE_HASH,
+        "{}",
+    );
+    Addr::<input::Input>::run();
+
+    let bubbled = Rc::new(Cell::new(false));
+    let b = bubbled.clone();
+    let listener = Closure::wrap(Box::new(move |_: Event| b.set(true)) as Box<dyn Fn(Event)>);
+    element("root")
+        .add_event_listener_with_callback("input", listener.as_ref().unchecked_ref())
+        .unwrap_throw();
+
+    let name: web::HtmlInputElement = element("name").unchecked_into();
+    name.set_value("yarte");
+    let event =
+        Event::new_with_event_init_dict("input", EventInit::new().bubbles(true).cancelable(true))
+            .unwrap_throw();
+    // Stopped but default isn't prevented
+    assert!(name.dispatch_event(&event).unwrap_throw());
+    assert!(!bubbled.get());
+    assert_eq!(
+        inner_html(),
+        r#"<div id="root"><input id="name" value="yarte"><p>yarte</p></div>"#
+    );
+
+    // Default action is prevented by the listener, it's called once
+    let prevented: Vec<bool> = (0..2)
+        .map(|_| {
+            let event = KeyboardEvent::new_with_keyboard_event_init_dict(
+                "keydown",
+                KeyboardEventInit::new()
+                    .cancelable(true)
+                    .key("Enter")
+                    .key_code(13),
+            )
+            .unwrap_throw();
+            !name.dispatch_event(&event).unwrap_throw()
+        })
+        .collect();
+    assert_eq!(prevented, vec![true, false]);
+    assert_eq!(input::KEYS.with(|keys| keys.borrow().clone()), vec![13]);
+}
+
+#[test]
+#[should_panic(expected = "yarte: hydration mismatch, server template hash 0 isn't client")]
+fn test_hash_mismatch() {
+    set_body(concat!(
+        r#"<div><p>1</p><button id="inc">+</button></div>"#,
+        r#"<script type="application/json" id="yarte-state" data-yarte-hash="0">{"count":1}</script>"#,
+    ));
+    Addr::<counter::Counter>::run();
+}
+
+#[test]
+#[should_panic(
+    expected = "yarte: hydration mismatch, state script `yarte-state` without template hash"
+)]
+fn test_hash_missing() {
+    set_body(concat!(
+        r#"<div><p>1</p><button id="inc">+</button></div>"#,
+        r#"<script type="application/json" id="yarte-state">{"count":1}</script>"#,
+    ));
+    Addr::<counter::Counter>::run();
+}
+
+#[test]
+#[should_panic(expected = "yarte: hydration mismatch, state script `yarte-state`: ")]
+fn test_state_parse_error() {
+    render(
+        r#"<div><p>1</p><button id="inc">+</button></div>"#,
+        counter::Counter::__YARTE_HASH,
+        r#"{"count":"one"}"#,
+    );
+    Addr::<counter::Counter>::run();
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "yarte: hydration mismatch, expected <p> found <span>")]
+fn test_structure_mismatch() {
+    render(
+        r#"<div><span>1</span><button id="inc">+</button></div>"#,
+        counter::Counter::__YARTE_HASH,
+        r#"{"count":1}"#,
+    );
+    Addr::<counter::Counter>::run();
+}
+
+mod fragment {
+    use yarte_derive::App;
+    use yarte_wasm_app::Addr;
+
+    #[derive(App)]
+    #[template(
+        src = "<p>{{ count }}</p><button id=\"dec\" onclick=\"{{? Msg::Dec }}\">-</button>",
+        mount = "app"
+    )]
+    #[msg(pub enum Msg {
+    Dec,
+})]
+    pub struct Fragment {
+        count: usize,
+        black_box: <Self as App>::BlackBox,
+    }
+
+    fn dec(app: &mut Fragment, _addr: &'static Addr<Fragment>) {
+        app.count -= 1;
+        app.black_box.t_root |= 1;
+    }
+}
+
+#[test]
+#[should_panic(expected = "yarte: hydration mismatch, state script `app-state` not found")]
+fn test_mount_state_missing() {
+    set_body(r#"<div id="app"><p>2</p><button id="dec">-</button></div>"#);
+    Addr::<fragment::Fragment>::run();
+}
+
+#[test]
+fn test_mount() {
+    let state = format!(
+        r#"<script type="application/json" id="app-state" data-yarte-hash="{}">{{"count":2}}</script>"#,
+        fragment::Fragment::__YARTE_HASH
+    );
+    set_body(&format!(
+        r#"<h1>yarte</h1><div id="app"><p>2</p><button id="dec">-</button>{}</div>"#,
+        state
+    ));
+    Addr::<fragment::Fragment>::run();
+
+    click("dec");
+    assert_eq!(
+        element("app").inner_html(),
+        format!(r#"<p>1</p><button id="dec">-</button>{}"#, state)
+    );
+}
+
+mod nested {
+    use yarte_derive::App;
+    use yarte_wasm_app::Addr;
+
+    pub mod counter {
+        use yarte_derive::App;
+        use yarte_wasm_app::Addr;
+
+        #[derive(App)]
+        #[template(path = "counter", mount = "counter")]
+        #[msg(pub enum Msg {
+        Inc,
+    })]
+        pub struct Counter {
+            pub count: usize,
+            black_box: <Self as App>::BlackBox,
+        }
+
+        fn inc(app: &mut Counter, _addr: &'static Addr<Counter>) {
+            app.count += 1;
+            app.black_box.t_root |= 1;
+        }
+    }
+
+    #[derive(App)]
+    #[template(
+        src = "<!doctype html><html><body>\
+<div><p id=\"total\">{{ total }}</p>\
+<div id=\"counter\">{{> @counter::Counter count = total, forward = counter_forward }}</div></div>\
+</body></html>",
+        script = "./pkg/app.js"
+    )]
+    #[msg(pub enum Msg {
+    Add(usize),
+})]
+    pub struct Parent {
+        pub total: usize,
+        black_box: <Self as App>::BlackBox,
+    }
+
+    fn add(app: &mut Parent, n: usize, _addr: &'static Addr<Parent>) {
+        app.total += n;
+        app.black_box.t_root |= 1;
+    }
+
+    fn counter_forward(msg: &counter::Msg) -> Option<Msg> {
+        match msg {
+            counter::Msg::Inc => Some(Msg::Add(1)),
+        }
+    }
+}
+
+#[test]
+fn test_nested() {
+    use yarte::{BytesMut, TemplateBytesTrait};
+
+    let html = nested::ParentInitialState { total: 3 }.call::<BytesMut>(1024);
+    let html = std::str::from_utf8(&html).unwrap();
+    let start = html.find("<body>").unwrap() + "<body>".len();
+    let end = html.find("</body>").unwrap();
+    // Child is rendered with its props and state
+    assert!(html[start..end].starts_with(
+        r#"<div><p id="total">3</p><div id="counter"><span>3</span><button id="inc">+</button><script type="application/json" id="counter-state""#
+    ));
+    assert!(html[start..end].contains(r#">{"count":3}</script></div></div>"#));
+    set_body(&html[start..end]);
+
+    let addr = Addr::<nested::Parent>::run();
+    let counter = || {
+        let html = element("counter").inner_html();
+        html[..html.find("<script").unwrap()].to_string()
+    };
+
+    click("inc");
+    assert_eq!(element("total").inner_html(), "4");
+    assert_eq!(counter(), r#"<span>4</span><button id="inc">+</button>"#);
+
+    addr.send(nested::Msg::Add(2));
+    assert_eq!(element("total").inner_html(), "6");
+    assert_eq!(counter(), r#"<span>6</span><button id="inc">+</button>"#);
+}
+
+mod bind {
+    use yarte_derive::App;
+    use yarte_wasm_app::Addr;
+
+    #[derive(App)]
+    #[template(src = "<!doctype html><html><body><form>\
+<input id=\"name\" bind:value=\"{{ name }}\">\
+<input id=\"done\" type=\"checkbox\" bind:checked=\"{{ done }}\">\
+<input id=\"age\" bind:value=\"{{ age }}\">\
+<p>{{ name }}</p><p id=\"state\">{{ done }}</p><button id=\"reset\" onclick=\"{{? Msg::Reset }}\">x</button>\
+</form></body></html>")]
+    #[msg(pub enum Msg {
+    Reset,
+})]
+    pub struct Bind {
+        pub name: String,
+        pub done: bool,
+        pub age: u8,
+        black_box: <Self as App>::BlackBox,
+    }
+
+    fn reset(app: &mut Bind, _addr: &'static Addr<Bind>) {
+        app.name.clear();
+        app.done = false;
+        app.black_box.t_root |= 7;
+    }
+}
+
+fn dispatch(id: &str, event: &str) {
+    let event =
+        Event::new_with_event_init_dict(event, EventInit::new().bubbles(true)).unwrap_throw();
+    element(id).dispatch_event(&event).unwrap_throw();
+}
+
+#[test]
+fn test_bind() {
+    render(
+        r#"<form><input id="name" value="foo"><input id="done" type="checkbox"><input id="age" value="3"><p>foo</p><p id="state">false</p><button id="reset">x</button></form>"#,
+        bind::Bind::__YARTE_HASH,
+        r#"{"name":"foo","done":false,"age":3}"#,
+    );
+    Addr::<bind::Bind>::run();
+
+    let name: web::HtmlInputElement = element("name").unchecked_into();
+    name.set_value("bar");
+    dispatch("name", "input");
+    assert_eq!(
+        element("age")
+            .next_element_sibling()
+            .unwrap_throw()
+            .inner_html(),
+        "bar"
+    );
+
+    // Unparsed values don't change the state
+    let age: web::HtmlInputElement = element("age").unchecked_into();
+    age.set_value("x");
+    dispatch("age", "input");
+    assert_eq!(age.value(), "x");
+
+    // Focused input keeps the value of the user
+    age.focus().unwrap_throw();
+    age.set_value("04");
+    dispatch("age", "input");
+    assert_eq!(age.value(), "04");
+    age.blur().unwrap_throw();
+
+    let done: web::HtmlInputElement = element("done").unchecked_into();
+    done.set_checked(true);
+    dispatch("done", "change");
+    assert_eq!(element("state").inner_html(), "true");
+
+    click("reset");
+    assert!(name.value().is_empty());
+    assert!(!done.checked());
+    assert_eq!(
+        inner_html(),
+        r#"<form><input id="name" value=""><input id="done" type="checkbox"><input id="age" value="4"><p></p><p id="state">false</p><button id="reset">x</button></form>"#
+    );
+}
+
+mod ssr {
+    use yarte_derive::App;
+    use yarte_wasm_app::Addr;
+
+    #[derive(App)]
+    #[template(
+        src = "<!doctype html><html><body>\
+<div><p id=\"count\">{{ count }}</p><button id=\"inc\" onclick=\"{{? Msg::Inc }}\">+</button></div>\
+</body></html>",
+        script = "./pkg/app.js"
+    )]
+    #[msg(pub enum Msg {
+    Inc,
+})]
+    pub struct Ssr {
+        pub count: usize,
+        black_box: <Self as App>::BlackBox,
+    }
+
+    fn inc(app: &mut Ssr, _addr: &'static Addr<Ssr>) {
+        app.count += 1;
+        app.black_box.t_root |= 1;
+    }
+}
+
+#[test]
+fn test_ssr() {
+    use yarte::{BytesMut, TemplateBytesTrait};
+
+    let html = ssr::SsrInitialState { count: 3 }.call::<BytesMut>(1024);
+    let html = std::str::from_utf8(&html).unwrap();
+    let start = html.find("<body>").unwrap() + "<body>".len();
+    let end = html.find("</body>").unwrap();
+    set_body(&html[start..end]);
+
+    // Server and client share template hash
+    Addr::<ssr::Ssr>::run();
+
+    click("inc");
+    assert_eq!(
+        inner_html(),
+        r#"<div><p id="count">4</p><button id="inc">+</button></div><link rel="modulepreload" href="./pkg/app.js">"#
+    );
+}
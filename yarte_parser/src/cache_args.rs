@@ -0,0 +1,55 @@
+use syn::{
+    parse::{Parse, ParseStream},
+    Error, Expr, Ident, Result, Token,
+};
+
+/// Cache helper arguments `key = expr [ttl = expr]`
+pub(super) struct CacheArgs {
+    pub key: Expr,
+    pub ttl: Option<Expr>,
+}
+
+fn argument(input: ParseStream, name: &str) -> Result<Expr> {
+    let ident: Ident = input.parse()?;
+    if ident != name {
+        return Err(Error::new(
+            ident.span(),
+            format!("expected `{} = ..`", name),
+        ));
+    }
+    input.parse::<Token![=]>()?;
+    input.parse()
+}
+
+impl Parse for CacheArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let key = argument(input, "key")?;
+        let ttl = if input.is_empty() {
+            None
+        } else {
+            Some(argument(input, "ttl")?)
+        };
+
+        Ok(CacheArgs { key, ttl })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use syn::parse_str;
+
+    #[test]
+    fn test() {
+        let CacheArgs { key, ttl } = parse_str("key = self.id ttl = 300").unwrap();
+        assert_eq!(key, parse_str::<Expr>("self.id").unwrap());
+        assert_eq!(ttl, Some(parse_str::<Expr>("300").unwrap()));
+
+        let CacheArgs { key, ttl } = parse_str("key = (lang, user.id)").unwrap();
+        assert_eq!(key, parse_str::<Expr>("(lang, user.id)").unwrap());
+        assert!(ttl.is_none());
+
+        assert!(parse_str::<CacheArgs>("self.id").is_err());
+        assert!(parse_str::<CacheArgs>("key = self.id expires = 300").is_err());
+    }
+}
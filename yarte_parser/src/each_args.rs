@@ -0,0 +1,47 @@
+use syn::{
+    parse::{Parse, ParseStream},
+    Error, Expr, Ident, Result, Token,
+};
+
+/// Each helper arguments `args [key = expr]`
+pub(super) struct EachArgs {
+    pub args: Expr,
+    pub key: Option<Expr>,
+}
+
+impl Parse for EachArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let args = input.parse()?;
+        let key = if input.is_empty() {
+            None
+        } else {
+            let ident: Ident = input.parse()?;
+            if ident != "key" {
+                return Err(Error::new(ident.span(), "expected `key = ..`"));
+            }
+            input.parse::<Token![=]>()?;
+            Some(input.parse()?)
+        };
+
+        Ok(EachArgs { args, key })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use syn::parse_str;
+
+    #[test]
+    fn test() {
+        let EachArgs { args, key } = parse_str("items.iter() key = this.id").unwrap();
+        assert_eq!(args, parse_str::<Expr>("items.iter()").unwrap());
+        assert_eq!(key.unwrap(), parse_str::<Expr>("this.id").unwrap());
+
+        let EachArgs { args, key } = parse_str("0..10").unwrap();
+        assert_eq!(args, parse_str::<Expr>("0..10").unwrap());
+        assert!(key.is_none());
+
+        assert!(parse_str::<EachArgs>("items id = this.id").is_err());
+    }
+}
@@ -11,6 +11,8 @@ mod strnom;
 #[cfg(test)]
 mod test;
 
+mod cache_args;
+mod each_args;
 mod error;
 mod expr_list;
 mod parse;
@@ -165,21 +167,46 @@ pub enum Node<'a> {
 
 pub(crate) const JSON: &str = "json";
 pub(crate) const JSON_PRETTY: &str = "json_pretty";
+pub(crate) const JSON_SCRIPT: &str = "json_script";
+pub(crate) const SANITIZE: &str = "sanitize";
+pub(crate) const INCLUDE_STR: &str = "include_str";
+pub(crate) const INCLUDE_RAW: &str = "include_raw";
+pub(crate) const ASSET: &str = "asset";
 #[derive(Debug, PartialEq, Clone, Deserialize)]
 pub enum AtHelperKind {
     Json,
     JsonPretty,
+    JsonScript,
+    Sanitize,
+    /// File at templates directory as text
+    IncludeStr,
+    /// File at templates directory without escape
+    IncludeRaw,
+    /// Url of a file at templates directory with its content hash
+    Asset,
 }
 
 #[derive(Debug, PartialEq, Clone, Deserialize)]
 pub enum Helper<'a> {
-    Each((Ws, Ws), SExpr, #[serde(borrow)] Vec<SNode<'a>>),
+    Each(
+        (Ws, Ws),
+        SExpr,
+        Option<SExpr>,
+        #[serde(borrow)] Vec<SNode<'a>>,
+    ),
     If(
         ((Ws, Ws), SExpr, Vec<SNode<'a>>),
         Vec<(Ws, SExpr, Vec<SNode<'a>>)>,
         Option<(Ws, Vec<SNode<'a>>)>,
     ),
     With((Ws, Ws), SExpr, #[serde(borrow)] Vec<SNode<'a>>),
+    /// `{{#cache key = expr [ttl = expr] }}`
+    Cache(
+        (Ws, Ws),
+        SExpr,
+        Option<SExpr>,
+        #[serde(borrow)] Vec<SNode<'a>>,
+    ),
     Unless((Ws, Ws), SExpr, #[serde(borrow)] Vec<SNode<'a>>),
     // TODO:
     Defined(
@@ -1,13 +1,20 @@
-use syn::parse_str;
+use std::path::PathBuf;
+
+use syn::{parse::Parser, parse_str};
 use unicode_xid::UnicodeXID;
 
+use yarte_helpers::calculate_hash;
+
+use crate::cache_args::CacheArgs;
+use crate::each_args::EachArgs;
 use crate::error::{DOption, PError};
 use crate::expr_list::ExprList;
-use crate::source_map::{spanned, Span, S};
+use crate::source_map::{self, spanned, Parsed, Span, S};
 use crate::strnom::{is_ws, skip_ws, ws, LexError, PResult};
 use crate::{
     AtHelperKind, Cursor, ErrorMessage, Expr, Helper, Node, Partial, PartialBlock, SExpr, SNode,
-    SVExpr, StmtLocal, Ws, JSON, JSON_PRETTY,
+    SVExpr, StmtLocal, Ws, ASSET, INCLUDE_RAW, INCLUDE_STR, JSON, JSON_PRETTY, JSON_SCRIPT,
+    SANITIZE,
 };
 
 pub fn parse(i: Cursor) -> Result<Vec<SNode>, ErrorMessage<PError>> {
@@ -22,6 +29,22 @@ pub fn parse(i: Cursor) -> Result<Vec<SNode>, ErrorMessage<PError>> {
     }
 }
 
+/// Parse the template `src` at `path`, nodes of the same content are parsed once
+/// in the thread until `source_map::clean` or `source_map::evict`
+///
+/// The cache lives in memory: the expressions are `syn` trees, without a stable
+/// serialization, and the spans are offsets of the source map of this compilation
+pub fn parse_cached(path: &PathBuf, src: &str) -> Result<Parsed, ErrorMessage<PError>> {
+    let key = (path.clone(), calculate_hash(&src));
+    if let Some(parsed) = source_map::get_parsed(&key) {
+        return Ok(parsed);
+    }
+
+    let parsed = Parsed::new(src, |src| parse(source_map::get_cursor(path, src)))?;
+    source_map::set_parsed(key, parsed.clone());
+    Ok(parsed)
+}
+
 /// Step in eater
 ///     - Ok -> eat_lit -> push node -> restart in next cursor and continue
 ///     - Err(Next) -> advance
@@ -242,10 +265,10 @@ pub(crate) fn hel(i: Cursor, a_lws: bool) -> PResult<Node> {
         return partial_block(i.adv(1), a_lws).map(|(c, x)| (c, Node::PartialBlock(x)));
     }
 
-    let (i, (above_ws, ident, args)) = do_parse!(
+    let (i, (above_ws, ident, (args, key))) = do_parse!(
         i,
         ws >> ident: call!(spanned, identifier)
-            >> args: arguments
+            >> args: call!(helper_arguments, ident.0)
             >> rws: end_expr
             >> (((a_lws, rws), ident, args))
     )?;
@@ -270,7 +293,8 @@ pub(crate) fn hel(i: Cursor, a_lws: bool) -> PResult<Node> {
             c,
             Node::Helper(Box::new({
                 match ident.0 {
-                    "each" => Helper::Each((above_ws, below_ws), args, block),
+                    "each" => Helper::Each((above_ws, below_ws), args, key, block),
+                    "cache" => Helper::Cache((above_ws, below_ws), args, key, block),
                     "with" => Helper::With((above_ws, below_ws), args, block),
                     "unless" => Helper::Unless((above_ws, below_ws), args, block),
                     defined => Helper::Defined((above_ws, below_ws), defined, args, block),
@@ -422,6 +446,52 @@ make_argument!(arguments, eat_expr, PResult<SExpr>);
 // Eat arguments at partials
 make_argument!(args_list, eat_expr_list, PResult<SVExpr>);
 
+type SEachArgs = S<(Box<Expr>, Option<(Box<Expr>, usize)>)>;
+
+// Eat arguments at each helper
+make_argument!(each_arguments, eat_each_args, PResult<SEachArgs>);
+
+// Eat arguments at cache helper
+make_argument!(cache_arguments, eat_cache_args, PResult<SEachArgs>);
+
+/// Eat helper arguments, `each` can have a `key = expr` argument
+/// and `cache` a `ttl = expr` argument
+fn helper_arguments<'a>(i: Cursor<'a>, ident: &str) -> PResult<'a, (SExpr, Option<SExpr>)> {
+    let split = |(c, S((args, key), span)): (Cursor<'a>, SEachArgs)| {
+        let (args, key) = match key {
+            Some((key, at)) => {
+                let start = (span.lo - i.off) as usize;
+                let len = i.rest[start..start + at].trim_end().len() as u32;
+                let at = span.lo + at as u32;
+                (
+                    S(
+                        args,
+                        Span {
+                            lo: span.lo,
+                            hi: span.lo + len,
+                        },
+                    ),
+                    Some(S(
+                        key,
+                        Span {
+                            lo: at,
+                            hi: span.hi,
+                        },
+                    )),
+                )
+            }
+            None => (S(args, span), None),
+        };
+        (c, (args, key))
+    };
+
+    match ident {
+        "each" => each_arguments(i).map(split),
+        "cache" => cache_arguments(i).map(split),
+        _ => arguments(i).map(|(c, args)| (c, (args, None))),
+    }
+}
+
 /// Eat safe Node
 fn safe(i: Cursor, lws: bool) -> PResult<Node> {
     let mut at = 0;
@@ -486,6 +556,35 @@ fn at_helper(i: Cursor, lws: bool) -> PResult<Node> {
                 Node::AtHelper((lws, rws), AtHelperKind::JsonPretty, args),
             ))
         }
+        JSON_SCRIPT => {
+            check_args_len!(1);
+            Ok((
+                c,
+                Node::AtHelper((lws, rws), AtHelperKind::JsonScript, args),
+            ))
+        }
+        SANITIZE => {
+            check_args_len!(1);
+            Ok((c, Node::AtHelper((lws, rws), AtHelperKind::Sanitize, args)))
+        }
+        INCLUDE_STR => {
+            check_args_len!(1);
+            Ok((
+                c,
+                Node::AtHelper((lws, rws), AtHelperKind::IncludeStr, args),
+            ))
+        }
+        INCLUDE_RAW => {
+            check_args_len!(1);
+            Ok((
+                c,
+                Node::AtHelper((lws, rws), AtHelperKind::IncludeRaw, args),
+            ))
+        }
+        ASSET => {
+            check_args_len!(1);
+            Ok((c, Node::AtHelper((lws, rws), AtHelperKind::Asset, args)))
+        }
         _ => Err(LexError::Fail(PError::AtHelperNotExist, name.span())),
     }
 }
@@ -602,6 +701,52 @@ fn eat_local(i: &str) -> Result<Box<crate::Local>, MiddleError> {
         .map_err(|e| MiddleError::new(i, e))
 }
 
+/// Expression and trailing `name = expr` argument with its offset
+type Arguments = (Box<crate::Expr>, Option<(Box<crate::Expr>, usize)>);
+
+/// Parse each arguments with the offset of `key`
+fn eat_each_args(i: &str) -> Result<Arguments, MiddleError> {
+    parse_str::<EachArgs>(i)
+        .map(|EachArgs { args, key }| {
+            (
+                Box::new(crate::Expr(args)),
+                key.map(|key| (Box::new(crate::Expr(key)), argument_offset(i, "key"))),
+            )
+        })
+        .map_err(|e| MiddleError::new(i, e))
+}
+
+/// Parse cache arguments with the offset of `ttl`
+fn eat_cache_args(i: &str) -> Result<Arguments, MiddleError> {
+    parse_str::<CacheArgs>(i)
+        .map(|CacheArgs { key, ttl }| {
+            (
+                Box::new(crate::Expr(key)),
+                ttl.map(|ttl| (Box::new(crate::Expr(ttl)), argument_offset(i, "ttl"))),
+            )
+        })
+        .map_err(|e| MiddleError::new(i, e))
+}
+
+/// Offset of the trailing `name = expr` argument
+///
+/// Spans of the compiler don't have positions in the template source
+fn argument_offset(i: &str, name: &str) -> usize {
+    let argument = |input: syn::parse::ParseStream| {
+        let ident: syn::Ident = input.parse()?;
+        input.parse::<syn::Token![=]>()?;
+        input.parse::<syn::Expr>()?;
+        Ok(ident)
+    };
+    i.rmatch_indices(name)
+        .map(|(at, _)| at)
+        .find(|&at| {
+            !i[..at].ends_with(|c: char| c.is_xid_continue())
+                && matches!(argument.parse_str(&i[at..]), Ok(ident) if ident == name)
+        })
+        .expect("parsed argument")
+}
+
 /// Parse syn expression comma separated list
 pub(crate) fn eat_expr_list(i: &str) -> Result<Vec<crate::Expr>, MiddleError> {
     parse_str::<ExprList>(i)
@@ -7,15 +7,17 @@ use crate::{
     ErrorMessage, Partial,
 };
 
-pub fn parse_partials(rest: &str) -> Result<Vec<Partial>, ErrorMessage<PError>> {
-    let (c, res) = eat_partials(Cursor { rest, off: 0 })?;
+pub fn parse_partials(i: Cursor) -> Result<Vec<Partial>, ErrorMessage<PError>> {
+    let (c, res) = eat_partials(i)?;
     if c.is_empty() {
         Ok(res)
     } else {
-        let end = (rest.len() - c.len()) as u32;
         Err(ErrorMessage {
             message: PError::Uncompleted,
-            span: Span { lo: end, hi: end },
+            span: Span {
+                lo: c.off,
+                hi: c.off,
+            },
         })
     }
 }
@@ -101,28 +103,52 @@ mod tests {
     #[test]
     fn test_empty() {
         let src = r#""#;
-        assert_eq!(parse_partials(src).unwrap(), vec![]);
+        assert_eq!(
+            parse_partials(Cursor { rest: src, off: 0 }).unwrap(),
+            vec![]
+        );
         let src = r#"{{/"#;
-        assert_eq!(parse_partials(src).unwrap(), vec![]);
+        assert_eq!(
+            parse_partials(Cursor { rest: src, off: 0 }).unwrap(),
+            vec![]
+        );
         let src = r#"{{"#;
-        assert_eq!(parse_partials(src).unwrap(), vec![]);
+        assert_eq!(
+            parse_partials(Cursor { rest: src, off: 0 }).unwrap(),
+            vec![]
+        );
         let src = r#"{"#;
-        assert_eq!(parse_partials(src).unwrap(), vec![]);
+        assert_eq!(
+            parse_partials(Cursor { rest: src, off: 0 }).unwrap(),
+            vec![]
+        );
         let src = r#"{{>"#;
-        assert_eq!(parse_partials(src).unwrap(), vec![]);
+        assert_eq!(
+            parse_partials(Cursor { rest: src, off: 0 }).unwrap(),
+            vec![]
+        );
         let src = r#"{{>}}"#;
-        assert_eq!(parse_partials(src).unwrap(), vec![]);
+        assert_eq!(
+            parse_partials(Cursor { rest: src, off: 0 }).unwrap(),
+            vec![]
+        );
         let src = r#"{{! {{> foo }} !}}"#;
-        assert_eq!(parse_partials(src).unwrap(), vec![]);
+        assert_eq!(
+            parse_partials(Cursor { rest: src, off: 0 }).unwrap(),
+            vec![]
+        );
         let src = r#"{{R}} {{> foo }} {{/R}}"#;
-        assert_eq!(parse_partials(src).unwrap(), vec![]);
+        assert_eq!(
+            parse_partials(Cursor { rest: src, off: 0 }).unwrap(),
+            vec![]
+        );
     }
 
     #[test]
     fn test_partial_block() {
         let src = "{{#> foo }}bar{{/foo }}";
         assert_eq!(
-            parse_partials(src).unwrap(),
+            parse_partials(Cursor { rest: src, off: 0 }).unwrap(),
             vec![Partial(
                 (false, false),
                 S("foo", Span { lo: 5, hi: 8 }),
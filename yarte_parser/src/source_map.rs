@@ -1,17 +1,24 @@
 //! Adapted from [`proc-macro2`](https://github.com/alexcrichton/proc-macro2).
 use std::{
     cell::RefCell,
+    collections::BTreeMap,
     fmt::{self, Debug},
     path::PathBuf,
+    rc::Rc,
 };
 
-use crate::strnom::{skip_ws, Cursor, PResult};
+use yarte_helpers::calculate_hash;
+
+use crate::{
+    strnom::{skip_ws, Cursor, PResult},
+    SNode,
+};
 
 thread_local! {
     static SOURCE_MAP: RefCell<SourceMap> = RefCell::new(Default::default());
 }
 
-/// Add file to source map and return lower bound
+/// Add file to source map and return lower bound, same file and content has the same bound
 ///
 /// Use in the same thread
 pub fn get_cursor<'a>(p: &PathBuf, rest: &'a str) -> Cursor<'a> {
@@ -21,13 +28,66 @@ pub fn get_cursor<'a>(p: &PathBuf, rest: &'a str) -> Cursor<'a> {
     })
 }
 
-/// Reinitialize source map instance when run multiple times in the same thread
+/// Parsed nodes of `parse_cached` and the source they borrow
+///
+/// The nodes are only reachable through `nodes`, tied to this value,
+/// so `clean` or `evict` never free a source while its nodes are in use
+#[derive(Clone, Debug)]
+pub struct Parsed {
+    nodes: Rc<Vec<SNode<'static>>>,
+    /// Borrowed by `nodes`
+    _src: Rc<str>,
+}
+
+impl Parsed {
+    /// Parses a copy of `src` owned by the result
+    pub(crate) fn new<E>(
+        src: &str,
+        f: impl FnOnce(&'static str) -> Result<Vec<SNode<'static>>, E>,
+    ) -> Result<Self, E> {
+        let src: Rc<str> = src.into();
+        // Safety: the nodes only borrow the shared source, which doesn't move and
+        // outlives them because they are only reachable through `nodes`
+        let kept = unsafe { &*(&*src as *const str) };
+        Ok(Parsed {
+            nodes: Rc::new(f(kept)?),
+            _src: src,
+        })
+    }
+
+    pub fn nodes(&self) -> &[SNode<'_>] {
+        &self.nodes
+    }
+}
+
+/// Parsed nodes of file with content hash
+pub(crate) fn get_parsed(key: &(PathBuf, u64)) -> Option<Parsed> {
+    SOURCE_MAP.with(|x| x.borrow().parsed.get(key).cloned())
+}
+
+pub(crate) fn set_parsed(key: (PathBuf, u64), parsed: Parsed) {
+    SOURCE_MAP.with(|x| x.borrow_mut().parsed.insert(key, parsed));
+}
+
+/// Reinitialize source map instance, and parsed nodes, when run multiple times in the same thread
 ///
 /// Use in the same thread
 pub fn clean() {
     SOURCE_MAP.with(|x| *x.borrow_mut() = Default::default());
 }
 
+/// Length of the source map over which `evict` cleans it
+const EVICT_LEN: u32 = 64 * 1024 * 1024;
+
+/// Reinitialize source map instance, and parsed nodes, when its sources grow over 64 MiB
+///
+/// Use in the same thread, before parsing a template
+pub fn evict() {
+    if SOURCE_MAP.with(|x| EVICT_LEN < x.borrow().next_start_pos()) {
+        clean();
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct LineColumn {
     pub line: usize,
@@ -36,6 +96,7 @@ pub struct LineColumn {
 
 struct FileInfo {
     name: PathBuf,
+    hash: u64,
     span: Span,
     lines: Vec<usize>,
 }
@@ -97,6 +158,7 @@ fn lines_offsets(s: &str) -> Vec<usize> {
 #[derive(Default)]
 struct SourceMap {
     files: Vec<FileInfo>,
+    parsed: BTreeMap<(PathBuf, u64), Parsed>,
 }
 
 impl SourceMap {
@@ -106,6 +168,15 @@ impl SourceMap {
     }
 
     fn add_file(&mut self, name: &PathBuf, src: &str) -> Span {
+        let hash = calculate_hash(&src);
+        if let Some(file) = self
+            .files
+            .iter()
+            .find(|x| x.hash == hash && x.name == *name)
+        {
+            return file.span;
+        }
+
         let lines = lines_offsets(src);
         let lo = self.next_start_pos();
         let span = Span {
@@ -115,6 +186,7 @@ impl SourceMap {
 
         self.files.push(FileInfo {
             name: name.to_owned(),
+            hash,
             span,
             lines,
         });
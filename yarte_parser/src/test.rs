@@ -106,6 +106,7 @@ fn test_helpers() {
                     Box::new(parse_str::<crate::Expr>("name").unwrap()),
                     Span { lo: 5, hi: 9 },
                 ),
+                None,
                 vec![
                     S(
                         Expr(
@@ -137,6 +138,41 @@ fn test_helpers() {
     );
 }
 
+#[test]
+fn test_cache() {
+    let rest = "cache key = id ttl = 30 }}{{ a }}{{/cache}}";
+    assert_eq!(
+        hel(Cursor { rest, off: 0 }, false).unwrap(),
+        (
+            Cursor {
+                rest: "",
+                off: rest.len() as u32,
+            },
+            Helper(Box::new(Helper::Cache(
+                (WS, WS),
+                S(
+                    Box::new(parse_str::<crate::Expr>("id").unwrap()),
+                    Span { lo: 6, hi: 14 },
+                ),
+                Some(S(
+                    Box::new(parse_str::<crate::Expr>("30").unwrap()),
+                    Span { lo: 15, hi: 23 },
+                )),
+                vec![S(
+                    Expr(
+                        WS,
+                        S(
+                            Box::new(parse_str::<crate::Expr>("a").unwrap()),
+                            Span { lo: 29, hi: 30 },
+                        ),
+                    ),
+                    Span { lo: 26, hi: 33 },
+                )],
+            )))
+        )
+    );
+}
+
 #[test]
 fn test_if_else() {
     let rest = "foo{{/if}}";
@@ -323,3 +359,52 @@ fn test_error_at_helper_check_len() {
         bytes!(9..17),
     );
 }
+
+#[test]
+fn test_parse_cached() {
+    use crate::{parse_cached, source_map::get_cursor};
+    use std::path::PathBuf;
+
+    let path = PathBuf::from("cached.hbs");
+    let parsed = parse_cached(&path, "{{ a }}").unwrap();
+    let nodes = parsed.nodes();
+    assert_eq!(parse_cached(&path, "{{ a }}").unwrap().nodes(), nodes);
+    // same file and content at the same bounds
+    let off = get_cursor(&path, "{{ a }}").off;
+    assert_eq!(
+        nodes[0].span(),
+        Span {
+            lo: off,
+            hi: off + 7
+        }
+    );
+
+    let changed = parse_cached(&path, "{{ b }}").unwrap();
+    assert_ne!(changed.nodes(), nodes);
+    assert_eq!(changed.nodes()[0].span().file_path(), path);
+}
+
+#[test]
+fn test_parse_cached_evict() {
+    use crate::{
+        parse_cached,
+        source_map::{clean, evict, get_parsed},
+    };
+    use std::path::PathBuf;
+    use yarte_helpers::calculate_hash;
+
+    let path = PathBuf::from("evict.hbs");
+    let key = (path.clone(), calculate_hash(&"hello {{ a }}"));
+    let parsed = parse_cached(&path, "hello {{ a }}").unwrap();
+    // under the limit
+    evict();
+    assert_eq!(get_parsed(&key).unwrap().nodes(), parsed.nodes());
+
+    clean();
+    assert!(get_parsed(&key).is_none());
+    // the nodes keep their source after clean
+    match parsed.nodes()[0].t() {
+        Lit(_, lit, _) => assert_eq!(*lit.t(), "hello"),
+        node => panic!("unexpected node {:?}", node),
+    }
+}